@@ -0,0 +1,70 @@
+import 'dart:io';
+
+import 'package:turbo_mysql/turbo_mysql.dart';
+
+const rounds = 5;
+const batchSizes = [100, 1000, 10000];
+const table = 'bench_batch_insert';
+
+/// Compares the two batch insert modes: multi-row INSERT statements and a
+/// single-row INSERT prepared once and executed per row.
+///
+/// Connects with the same `DB_*` environment variables as the tests and
+/// reports the mean of [rounds] runs of each mode per batch size:
+///
+///     dart run benchmark/batch_insert_benchmark.dart
+Future<void> main() async {
+  final env = Platform.environment;
+  final mysql = MySqlPool(
+    MySqlConfig(
+      host: env['DB_HOST'] ?? '127.0.0.1',
+      user: env['DB_USER'] ?? 'root',
+      pass: env['DB_PASS'] ?? 'password',
+      dbName: env['DB_NAME'] ?? 'test',
+      port: int.tryParse(env['DB_PORT'] ?? '3306') ?? 3306,
+    ),
+  );
+  await mysql.connect();
+  try {
+    await mysql.query('DROP TABLE IF EXISTS $table');
+    await mysql.query(
+      'CREATE TABLE $table (id INT PRIMARY KEY, name VARCHAR(32), '
+      'score DOUBLE, created DATETIME)',
+    );
+    final created = DateTime.utc(2024, 1, 2, 3, 4, 5);
+    print('rows\tmulti-row ms\tprepared ms');
+    for (final size in batchSizes) {
+      final rows = [
+        for (var i = 0; i < size; i++) [i, 'name $i', i / 3, created],
+      ];
+      Future<Duration> run(bool prepared) async {
+        var total = Duration.zero;
+        for (var round = 0; round < rounds; round++) {
+          await mysql.query('TRUNCATE TABLE $table');
+          final stopwatch = Stopwatch()..start();
+          await mysql.insertBatch(
+            table,
+            ['id', 'name', 'score', 'created'],
+            rows,
+            prepared: prepared,
+          );
+          total += stopwatch.elapsed;
+        }
+        return total ~/ rounds;
+      }
+
+      // Warm up connections and the statement cache before timing.
+      await run(false);
+      await run(true);
+      final multiRow = await run(false);
+      final prepared = await run(true);
+      print(
+        '$size\t${multiRow.inMicroseconds / 1000}'
+        '\t${prepared.inMicroseconds / 1000}',
+      );
+    }
+  } finally {
+    await mysql.query('DROP TABLE IF EXISTS $table');
+    await mysql.close();
+  }
+}
@@ -87,6 +87,13 @@ class BinaryReader {
     _offset += len;
     return str;
   }
+
+  /// Reads every byte left after the offset.
+  Uint8List readRemaining() {
+    final bytes = _rawBytes.sublist(_offset);
+    _offset = _rawBytes.length;
+    return bytes;
+  }
 }
 
 /// A helper class to write binary data into a growing buffer.
@@ -100,6 +107,12 @@ class BinaryWriter {
     _builder.addByte(v);
   }
 
+  /// Writes a 16-bit unsigned integer (little-endian) to the buffer.
+  void writeUint16(int v) {
+    final b = ByteData(2)..setUint16(0, v, Endian.little);
+    _builder.add(b.buffer.asUint8List());
+  }
+
   /// Writes a 32-bit unsigned integer (little-endian) to the buffer.
   void writeUint32(int v) {
     final b = ByteData(4)..setUint32(0, v, Endian.little);
@@ -11,6 +11,83 @@ typedef QueryCallbackNative =
 )
 external Pointer<Void> mysql_pool_create(Pointer<Utf8> url);
 
+/// Pool options passed to [mysql_pool_create_with_config], mirroring the Rust
+/// `MysqlPoolConfig`. Zero and null fields keep the URL's value; a negative
+/// [stmtCacheSize] keeps the URL's cache size.
+final class MysqlPoolConfigNative extends Struct {
+  /// Must be `sizeOf<MysqlPoolConfigNative>()`.
+  @Size()
+  external int structSize;
+
+  external Pointer<Utf8> url;
+
+  @Uint32()
+  external int minConnections;
+
+  @Uint32()
+  external int maxConnections;
+
+  @Uint32()
+  external int maxConnLifetimeMs;
+
+  @Uint32()
+  external int inactiveConnTtlMs;
+
+  @Int32()
+  external int stmtCacheSize;
+
+  @Uint32()
+  external int connectRetries;
+
+  @Uint32()
+  external int connectBackoffMs;
+
+  @Uint32()
+  external int defaultQueryTimeoutMs;
+
+  external Pointer<Utf8> tlsCaPath;
+
+  external Pointer<Utf8> charset;
+
+  external Pointer<Utf8> initSql;
+
+  @Bool()
+  external bool readOnly;
+
+  @Bool()
+  external bool interactive;
+
+  @Uint32()
+  external int recentQueryCapacity;
+
+  @Bool()
+  external bool deadlockDiagnostics;
+
+  external Pointer<Utf8> isolationLevel;
+}
+
+/// Creates a connection pool from a [MysqlPoolConfigNative]; returns null on
+/// invalid options.
+@Native<Pointer<Void> Function(Pointer<MysqlPoolConfigNative>)>(
+  assetId: 'package:turbo_mysql/turbo_mysql_core',
+)
+external Pointer<Void> mysql_pool_create_with_config(
+  Pointer<MysqlPoolConfigNative> config,
+);
+
+/// Checks a connection URL; returns null if it is valid, otherwise an error
+/// description that must be released with [mysql_free_string].
+@Native<Pointer<Utf8> Function(Pointer<Utf8>)>(
+  assetId: 'package:turbo_mysql/turbo_mysql_core',
+)
+external Pointer<Utf8> mysql_validate_url(Pointer<Utf8> url);
+
+/// Releases a string returned by the native library.
+@Native<Void Function(Pointer<Utf8>)>(
+  assetId: 'package:turbo_mysql/turbo_mysql_core',
+)
+external void mysql_free_string(Pointer<Utf8> ptr);
+
 /// Destroys the connection pool and frees its resources.
 @Native<Void Function(Pointer<Void>)>(
   assetId: 'package:turbo_mysql/turbo_mysql_core',
@@ -35,13 +112,60 @@ external void mysql_pool_query_raw(
   Pointer<NativeFunction<QueryCallbackNative>> callback,
 );
 
+/// Per-call options of [mysql_pool_query], mirroring the Rust
+/// `MysqlQueryOptions`.
+final class MysqlQueryOptionsNative extends Struct {
+  /// Must be `sizeOf<MysqlQueryOptionsNative>()`.
+  @Size()
+  external int structSize;
+
+  /// Server-side execution limit (`max_statement_time` on MariaDB,
+  /// `MAX_EXECUTION_TIME` for SELECT elsewhere); 0 disables it.
+  @Uint32()
+  external int serverTimeoutMs;
+
+  @Bool()
+  external bool compress;
+
+  @Bool()
+  external bool enumOrdinals;
+
+  @Bool()
+  external bool typeNames;
+}
+
+/// Per-call options of [mysql_pool_batch_insert],
+/// [mysql_pool_batch_insert_stream] and [mysql_pool_batch_upsert], mirroring
+/// the Rust `MysqlBatchInsertOptions`.
+final class MysqlBatchInsertOptionsNative extends Struct {
+  /// Must be `sizeOf<MysqlBatchInsertOptionsNative>()`.
+  @Size()
+  external int structSize;
+
+  external Pointer<Utf8> dedupColumns;
+
+  external Pointer<Utf8> defaultColumns;
+
+  external Pointer<Uint8> typeHints;
+
+  @Int32()
+  external int typeHintsLen;
+
+  /// 0 for multi-row INSERTs, 1 for a prepared single-row INSERT per row.
+  @Uint8()
+  external int mode;
+}
+
 /// Executes a query with parameters on the pool using the MySQL Binary Protocol (Prepared Statements).
+///
+/// A null [options] applies the defaults.
 @Native<
   Void Function(
     Pointer<Void>,
     Pointer<Utf8>,
     Pointer<Uint8>,
     Int32,
+    Pointer<MysqlQueryOptionsNative>,
     Int64,
     Pointer<NativeFunction<QueryCallbackNative>>,
   )
@@ -51,6 +175,7 @@ external void mysql_pool_query(
   Pointer<Utf8> query,
   Pointer<Uint8> params,
   int paramsLen,
+  Pointer<MysqlQueryOptionsNative> options,
   int id,
   Pointer<NativeFunction<QueryCallbackNative>> callback,
 );
@@ -79,6 +204,7 @@ external void mysql_pool_prepare(
     Pointer<Void>,
     Pointer<Utf8>,
     Pointer<Utf8>,
+    Pointer<MysqlBatchInsertOptionsNative>,
     Pointer<Uint8>,
     Int32,
     Int64,
@@ -89,6 +215,7 @@ external void mysql_pool_batch_insert(
   Pointer<Void> pool,
   Pointer<Utf8> table,
   Pointer<Utf8> columns,
+  Pointer<MysqlBatchInsertOptionsNative> options,
   Pointer<Uint8> data,
   int dataLen,
   int id,
@@ -101,6 +228,7 @@ external void mysql_pool_batch_insert(
     Pointer<Void>,
     Pointer<Utf8>,
     Pointer<Utf8>,
+    Pointer<MysqlBatchInsertOptionsNative>,
     Pointer<Uint8>,
     Int32,
     Int64,
@@ -111,6 +239,7 @@ external void mysql_pool_batch_upsert(
   Pointer<Void> pool,
   Pointer<Utf8> table,
   Pointer<Utf8> columns,
+  Pointer<MysqlBatchInsertOptionsNative> options,
   Pointer<Uint8> data,
   int dataLen,
   int id,
@@ -133,6 +262,23 @@ external void mysql_pool_begin_transaction(
   Pointer<NativeFunction<QueryCallbackNative>> callback,
 );
 
+/// Starts a transaction with a consistent snapshot and returns a dedicated
+/// connection.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(
+  assetId: 'package:turbo_mysql/turbo_mysql_core',
+)
+external void mysql_pool_begin_snapshot(
+  Pointer<Void> pool,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
 /// Gets a dedicated connection from the pool without starting a transaction.
 @Native<
   Void Function(
@@ -295,6 +441,1362 @@ external void mysql_stmt_execute(
 )
 external void mysql_stmt_destroy(Pointer<Void> stmt);
 
+/// Opens a handle on one TEXT or BLOB value located by its row key; returns
+/// null on invalid arguments.
+@Native<
+  Pointer<Void> Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Pointer<Utf8>,
+    Pointer<Utf8>,
+    Pointer<Uint8>,
+    Int32,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external Pointer<Void> mysql_pool_lob_open(
+  Pointer<Void> pool,
+  Pointer<Utf8> table,
+  Pointer<Utf8> column,
+  Pointer<Utf8> keyColumns,
+  Pointer<Uint8> key,
+  int keyLen,
+);
+
+/// Reads a byte range of a value opened with [mysql_pool_lob_open].
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Uint64,
+    Uint32,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_lob_read(
+  Pointer<Void> lob,
+  int offset,
+  int len,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Releases a handle returned by [mysql_pool_lob_open].
+@Native<Void Function(Pointer<Void>)>(
+  assetId: 'package:turbo_mysql/turbo_mysql_core',
+)
+external void mysql_lob_destroy(Pointer<Void> lob);
+
+/// Runs one page of a query, optionally with the total row count.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Pointer<Uint8>,
+    Int32,
+    Int32,
+    Int32,
+    Bool,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_query_page(
+  Pointer<Void> pool,
+  Pointer<Utf8> query,
+  Pointer<Uint8> params,
+  int paramsLen,
+  int page,
+  int pageSize,
+  bool withTotal,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Runs several `;`-separated statements, reporting each one's outcome.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_exec_multi(
+  Pointer<Void> pool,
+  Pointer<Utf8> query,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Runs a SELECT with a locking clause on the transaction's connection.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Pointer<Uint8>,
+    Int32,
+    Int32,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_conn_query_locking(
+  Pointer<Void> conn,
+  Pointer<Utf8> query,
+  Pointer<Uint8> params,
+  int paramsLen,
+  int lockMode,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Inserts many rows like [mysql_pool_batch_insert], sending each chunk's
+/// first id and row count as it completes.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Pointer<Utf8>,
+    Pointer<MysqlBatchInsertOptionsNative>,
+    Pointer<Uint8>,
+    Int32,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_batch_insert_stream(
+  Pointer<Void> pool,
+  Pointer<Utf8> table,
+  Pointer<Utf8> columns,
+  Pointer<MysqlBatchInsertOptionsNative> options,
+  Pointer<Uint8> data,
+  int dataLen,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Runs a statement such as a procedure call and reports how many result sets
+/// it returned, in the affected rows slot.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_count_result_sets(
+  Pointer<Void> pool,
+  Pointer<Utf8> callStatement,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Makes native calls fail as if the runtime could not be created; only
+/// exported by builds with the `test-hooks` feature.
+@Native<Void Function(Bool)>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_test_simulate_runtime_failure(bool enabled);
+
+/// Replaces the statements run on every new connection; null clears them.
+@Native<Bool Function(Pointer<Void>, Pointer<Utf8>)>(
+  assetId: 'package:turbo_mysql/turbo_mysql_core',
+)
+external bool mysql_pool_set_session_init(
+  Pointer<Void> pool,
+  Pointer<Utf8> statements,
+);
+
+/// Creates a cancellation token; release it with [mysql_cancel_token_destroy].
+@Native<Pointer<Void> Function()>(
+  assetId: 'package:turbo_mysql/turbo_mysql_core',
+)
+external Pointer<Void> mysql_cancel_token_create();
+
+/// Cancels every operation the token was passed to.
+@Native<Void Function(Pointer<Void>)>(
+  assetId: 'package:turbo_mysql/turbo_mysql_core',
+)
+external void mysql_cancel_token_cancel(Pointer<Void> token);
+
+/// Releases a token returned by [mysql_cancel_token_create].
+@Native<Void Function(Pointer<Void>)>(
+  assetId: 'package:turbo_mysql/turbo_mysql_core',
+)
+external void mysql_cancel_token_destroy(Pointer<Void> token);
+
+/// Streams binlog events as replica `serverId` until [token] is cancelled;
+/// only exported by builds with the `binlog` feature.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Uint32,
+    Pointer<Utf8>,
+    Uint64,
+    Pointer<Void>,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_binlog_stream(
+  Pointer<Void> pool,
+  int serverId,
+  Pointer<Utf8> startFile,
+  int startPos,
+  Pointer<Void> token,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Runs a statement list on the transaction's connection, each statement
+/// behind its own savepoint.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Uint8>,
+    Int32,
+    Bool,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_conn_batch_statements(
+  Pointer<Void> conn,
+  Pointer<Uint8> statements,
+  int statementsLen,
+  bool stopOnError,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Looks up the next `AUTO_INCREMENT` value of a table, appended to the
+/// result as a presence byte and a `Uint64`.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_next_auto_increment(
+  Pointer<Void> pool,
+  Pointer<Utf8> table,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Switches new connections to the credentials of `newUrl`.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_rotate_credentials(
+  Pointer<Void> pool,
+  Pointer<Utf8> newUrl,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Calls a procedure, returning its result sets followed by a block holding
+/// the OUT parameters named in `outNames`.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Pointer<Uint8>,
+    Int32,
+    Pointer<Utf8>,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_call_full(
+  Pointer<Void> pool,
+  Pointer<Utf8> procedure,
+  Pointer<Uint8> inParams,
+  int inParamsLen,
+  Pointer<Utf8> outNames,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Turns the foreign key and unique checks of the connection off while
+/// `enabled`, and back on otherwise.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Bool,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_conn_set_import_mode(
+  Pointer<Void> conn,
+  bool enabled,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Executes an INSERT, returning only its generated id as a `Uint64`.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Pointer<Uint8>,
+    Int32,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_insert(
+  Pointer<Void> pool,
+  Pointer<Utf8> query,
+  Pointer<Uint8> params,
+  int paramsLen,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Executes a statement, appending its warning count and, when
+/// `fetchWarnings` is set, the `SHOW WARNINGS` rows.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Pointer<Uint8>,
+    Int32,
+    Bool,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_exec_with_warnings(
+  Pointer<Void> pool,
+  Pointer<Utf8> query,
+  Pointer<Uint8> params,
+  int paramsLen,
+  bool fetchWarnings,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Creates a pool like `pool` whose connections default to `dbName`; release
+/// it with [mysql_pool_destroy]. Returns null on invalid arguments.
+@Native<Pointer<Void> Function(Pointer<Void>, Pointer<Utf8>)>(
+  assetId: 'package:turbo_mysql/turbo_mysql_core',
+)
+external Pointer<Void> mysql_pool_with_database(
+  Pointer<Void> pool,
+  Pointer<Utf8> dbName,
+);
+
+/// Destroys the pool once its in-flight operations finish, blocking the
+/// caller; returns false, leaving the pool intact, if `timeoutMs` elapses.
+@Native<Bool Function(Pointer<Void>, Uint32)>(
+  assetId: 'package:turbo_mysql/turbo_mysql_core',
+)
+external bool mysql_pool_destroy_when_idle(Pointer<Void> pool, int timeoutMs);
+
+/// Clears the statement cache of a connection outside a transaction.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_conn_reset_stmt_cache(
+  Pointer<Void> conn,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Runs a query with the MySQL Text Protocol, streaming its rows in chunks of
+/// at most `chunkRows`.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Uint32,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_query_stream(
+  Pointer<Void> pool,
+  Pointer<Utf8> query,
+  int chunkRows,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Routes every callback through one dispatcher thread when
+/// `singleThreaded`; returns false if that thread could not be started.
+@Native<Bool Function(Bool)>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external bool mysql_set_callback_dispatch(bool singleThreaded);
+
+/// Returns how many distinct threads invoked a callback since the last call;
+/// only exported by builds with the `test-hooks` feature.
+@Native<Int32 Function()>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external int mysql_test_take_callback_thread_count();
+
+/// Returns the connection's last error message or null; release it with
+/// [mysql_free_string].
+@Native<Pointer<Utf8> Function(Pointer<Void>)>(
+  assetId: 'package:turbo_mysql/turbo_mysql_core',
+)
+external Pointer<Utf8> mysql_conn_last_error(Pointer<Void> conn);
+
+/// Returns the server error code of the connection's last error, or 0.
+@Native<Uint32 Function(Pointer<Void>)>(
+  assetId: 'package:turbo_mysql/turbo_mysql_core',
+)
+external int mysql_conn_last_error_code(Pointer<Void> conn);
+
+/// Executes a query, returning the column count, row count and values without
+/// any column metadata.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Pointer<Uint8>,
+    Int32,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_query_values(
+  Pointer<Void> pool,
+  Pointer<Utf8> query,
+  Pointer<Uint8> params,
+  int paramsLen,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Sets `valueColumn` of the rows matching each key in one `CASE` statement
+/// per chunk, reporting the total affected rows.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Pointer<Utf8>,
+    Pointer<Utf8>,
+    Pointer<Uint8>,
+    Int32,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_batch_update(
+  Pointer<Void> pool,
+  Pointer<Utf8> table,
+  Pointer<Utf8> keyColumn,
+  Pointer<Utf8> valueColumn,
+  Pointer<Uint8> data,
+  int dataLen,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Opens a cursor over a query's rows, holding its connection until the cursor
+/// is destroyed. Responds with the cursor pointer in the affected rows slot.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Pointer<Uint8>,
+    Int32,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_cursor_open(
+  Pointer<Void> pool,
+  Pointer<Utf8> query,
+  Pointer<Uint8> params,
+  int paramsLen,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Fetches up to [maxRows] rows from a cursor as one row chunk with a trailing
+/// state byte, waiting at most [timeoutMs] (0 for no limit).
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Uint32,
+    Uint32,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_cursor_fetch(
+  Pointer<Void> cursor,
+  int maxRows,
+  int timeoutMs,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Destroys a cursor, discarding its unread rows and releasing its connection.
+@Native<Void Function(Pointer<Void>)>(
+  assetId: 'package:turbo_mysql/turbo_mysql_core',
+)
+external void mysql_cursor_destroy(Pointer<Void> cursor);
+
+/// Executes a parameterized query, grouping its rows by the column at
+/// [keyColIndex].
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Uint32,
+    Pointer<Uint8>,
+    Int32,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_query_grouped(
+  Pointer<Void> pool,
+  Pointer<Utf8> query,
+  int keyColIndex,
+  Pointer<Uint8> params,
+  int paramsLen,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Opens up to [count] connections at once and returns them to the pool,
+/// responding with the number opened in the affected rows slot.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Uint32,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_warmup(
+  Pointer<Void> pool,
+  int count,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Executes a query whose `{orderby}` token is replaced by an ORDER BY clause
+/// built from [sortColumns], each of which must appear in the comma-separated
+/// [allowedColumns].
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Pointer<Uint8>,
+    Int32,
+    Pointer<Utf8>,
+    Pointer<Uint8>,
+    Int32,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_query_sorted(
+  Pointer<Void> pool,
+  Pointer<Utf8> queryTemplate,
+  Pointer<Uint8> sortColumns,
+  int sortColumnsLen,
+  Pointer<Utf8> allowedColumns,
+  Pointer<Uint8> params,
+  int paramsLen,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Reports queries running at least [thresholdMs] to [callback], with their
+/// `EXPLAIN` plan when [explain] is set; 0 removes the logger.
+@Native<
+  Bool Function(
+    Pointer<Void>,
+    Uint32,
+    Bool,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external bool mysql_pool_set_slow_query_log(
+  Pointer<Void> pool,
+  int thresholdMs,
+  bool explain,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Checks out a connection for consecutive queries without starting a
+/// transaction. Responds with the connection pointer in the affected rows
+/// slot; release it with [mysql_conn_release].
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_session_begin(
+  Pointer<Void> pool,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Returns a session's connection to the pool.
+@Native<Void Function(Pointer<Void>)>(
+  assetId: 'package:turbo_mysql/turbo_mysql_core',
+)
+external void mysql_conn_release(Pointer<Void> conn);
+
+/// Executes a parameterized query, returning only the columns in
+/// `[startCol, endCol)`.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Uint32,
+    Uint32,
+    Pointer<Uint8>,
+    Int32,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_query_col_range(
+  Pointer<Void> pool,
+  Pointer<Utf8> query,
+  int startCol,
+  int endCol,
+  Pointer<Uint8> params,
+  int paramsLen,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Executes a prepared statement, streaming its rows in chunks of at most
+/// [batchSize] rows before a final result with the counters.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Uint8>,
+    Int32,
+    Uint32,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_stmt_execute_stream(
+  Pointer<Void> stmt,
+  Pointer<Uint8> params,
+  int paramsLen,
+  int batchSize,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Makes the next [count] connection attempts of any pool fail as if the
+/// server refused them. Only exported by builds with the `test-hooks` feature.
+@Native<Void Function(Uint32)>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_test_fail_connects(int count);
+
+/// Folds the column at [colIndex] of a query's rows with SUM (0), MIN (1),
+/// MAX (2) or COUNT (3), responding with the tagged result value.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Pointer<Uint8>,
+    Int32,
+    Int32,
+    Uint32,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_query_reduce(
+  Pointer<Void> pool,
+  Pointer<Utf8> query,
+  Pointer<Uint8> params,
+  int paramsLen,
+  int op,
+  int colIndex,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Combines [count] pools into a sharded pool that takes ownership of them,
+/// routing by consistent hashing (0) or modulo (1); NULL on invalid arguments,
+/// including a pool passed twice.
+@Native<Pointer<Void> Function(Pointer<Pointer<Void>>, Int32, Uint8)>(
+  assetId: 'package:turbo_mysql/turbo_mysql_core',
+)
+external Pointer<Void> mysql_sharded_pool_create(
+  Pointer<Pointer<Void>> pools,
+  int count,
+  int strategy,
+);
+
+/// Destroys a sharded pool and the pools it owns.
+@Native<Void Function(Pointer<Void>)>(
+  assetId: 'package:turbo_mysql/turbo_mysql_core',
+)
+external void mysql_sharded_pool_destroy(Pointer<Void> sharded);
+
+/// Returns the index of the shard responsible for the key, or -1.
+@Native<Int32 Function(Pointer<Void>, Pointer<Uint8>, Int32)>(
+  assetId: 'package:turbo_mysql/turbo_mysql_core',
+)
+external int mysql_sharded_pool_shard_for(
+  Pointer<Void> sharded,
+  Pointer<Uint8> key,
+  int keyLen,
+);
+
+/// Executes a parameterized query on the pool of the shard responsible for
+/// the key.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Uint8>,
+    Int32,
+    Pointer<Utf8>,
+    Pointer<Uint8>,
+    Int32,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_sharded_query(
+  Pointer<Void> sharded,
+  Pointer<Uint8> key,
+  int keyLen,
+  Pointer<Utf8> query,
+  Pointer<Uint8> params,
+  int paramsLen,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Registers the callback receiving captured response frames, or removes it
+/// (turning capture off) with `nullptr`.
+@Native<Void Function(Pointer<NativeFunction<QueryCallbackNative>>)>(
+  assetId: 'package:turbo_mysql/turbo_mysql_core',
+)
+external void mysql_set_debug_capture_callback(
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Turns copying every response frame to the capture callback on or off;
+/// returns false when enabling without a registered capture callback.
+@Native<Bool Function(Bool)>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external bool mysql_set_debug_capture(bool enabled);
+
+/// Executes a parameterized query, then `SELECT FOUND_ROWS()` on the same
+/// connection, appending the count to the result as a u64 trailer.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Pointer<Uint8>,
+    Int32,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_query_with_found_rows(
+  Pointer<Void> pool,
+  Pointer<Utf8> query,
+  Pointer<Uint8> params,
+  int paramsLen,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Identifies the server flavor, appending to the result a trailer of the
+/// flavor byte (0 unknown, 1 MySQL, 2 MariaDB, 3 Percona), the u16 major,
+/// minor and patch versions and the version string.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_server_flavor(
+  Pointer<Void> pool,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Executes a parameterized query, appending to the result a trailer of the
+/// u64 result set hash, the row count and a u64 hash per row.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Pointer<Uint8>,
+    Int32,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_query_hashed(
+  Pointer<Void> pool,
+  Pointer<Utf8> query,
+  Pointer<Uint8> params,
+  int paramsLen,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Writes the single string or BLOB value of a query's first row to [fd],
+/// appending the number of bytes written to the result as a u64 trailer.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Pointer<Uint8>,
+    Int32,
+    Int32,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_blob_to_fd(
+  Pointer<Void> pool,
+  Pointer<Utf8> query,
+  Pointer<Uint8> params,
+  int paramsLen,
+  int fd,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Creates a statement prepared on whichever pooled connection runs it; NULL
+/// for an empty or rejected query. Destroy it before its pool.
+@Native<Pointer<Void> Function(Pointer<Void>, Pointer<Utf8>)>(
+  assetId: 'package:turbo_mysql/turbo_mysql_core',
+)
+external Pointer<Void> mysql_pool_stmt_create(
+  Pointer<Void> pool,
+  Pointer<Utf8> query,
+);
+
+/// Executes a pool statement on any available connection.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Uint8>,
+    Int32,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_stmt_exec(
+  Pointer<Void> stmt,
+  Pointer<Uint8> params,
+  int paramsLen,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Returns how many times the statement has been prepared on the server.
+@Native<Uint64 Function(Pointer<Void>)>(
+  assetId: 'package:turbo_mysql/turbo_mysql_core',
+)
+external int mysql_pool_stmt_prepare_count(Pointer<Void> stmt);
+
+/// Destroys a pool statement.
+@Native<Void Function(Pointer<Void>)>(
+  assetId: 'package:turbo_mysql/turbo_mysql_core',
+)
+external void mysql_pool_stmt_destroy(Pointer<Void> stmt);
+
+/// Executes a parameterized query, appending its rows to the result as an
+/// RFC 4180 CSV blob trailer, led by a header row when [includeHeader] is set.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Pointer<Uint8>,
+    Int32,
+    Bool,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_query_csv(
+  Pointer<Void> pool,
+  Pointer<Utf8> query,
+  Pointer<Uint8> params,
+  int paramsLen,
+  bool includeHeader,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Reports the replication lag in seconds, appending to the result a presence
+/// byte and, when replication is running, the lag as an i64.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_replication_lag(
+  Pointer<Void> pool,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Executes an INSERT and returns the inserted rows with their generated
+/// defaults, through `RETURNING` on MariaDB and otherwise by selecting the
+/// row back with the comma-separated [lookupKey] columns bound to the lookup
+/// parameters.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Pointer<Uint8>,
+    Int32,
+    Pointer<Utf8>,
+    Pointer<Utf8>,
+    Pointer<Uint8>,
+    Int32,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_insert_get_defaults(
+  Pointer<Void> pool,
+  Pointer<Utf8> query,
+  Pointer<Uint8> params,
+  int paramsLen,
+  Pointer<Utf8> table,
+  Pointer<Utf8> lookupKey,
+  Pointer<Uint8> lookupParams,
+  int lookupParamsLen,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Makes new operations on the pool wait for [mysql_pool_resume], failing at
+/// once while [maxQueued] operations are already waiting.
+@Native<Bool Function(Pointer<Void>, Uint32)>(
+  assetId: 'package:turbo_mysql/turbo_mysql_core',
+)
+external bool mysql_pool_pause(Pointer<Void> pool, int maxQueued);
+
+/// Lets the operations held by [mysql_pool_pause] proceed.
+@Native<Bool Function(Pointer<Void>)>(
+  assetId: 'package:turbo_mysql/turbo_mysql_core',
+)
+external bool mysql_pool_resume(Pointer<Void> pool);
+
+/// Executes a parameterized query, writing the response frame into [outBuf]
+/// when it fits in [outCap] bytes; the callback then receives [outBuf], which
+/// must not be passed to [mysql_buffer_free]. Otherwise it receives a
+/// buffer-too-small frame carrying the required length as a u64.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Pointer<Uint8>,
+    Int32,
+    Pointer<Uint8>,
+    Int32,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_query_into(
+  Pointer<Void> pool,
+  Pointer<Utf8> query,
+  Pointer<Uint8> params,
+  int paramsLen,
+  Pointer<Uint8> outBuf,
+  int outCap,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Executes a write in its own transaction, committing only when it affected
+/// exactly one row and otherwise rolling back with a row count error.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Pointer<Uint8>,
+    Int32,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_exec_one(
+  Pointer<Void> pool,
+  Pointer<Utf8> query,
+  Pointer<Uint8> params,
+  int paramsLen,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Executes a parameterized query, encoding each row as a [messageName]
+/// message of the serialized `FileDescriptorSet` in [descriptor]; appends to
+/// the result a trailer of the u32 row count and the length-delimited
+/// messages as a blob. Only exported by builds with the `protobuf` feature.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Pointer<Uint8>,
+    Int32,
+    Pointer<Uint8>,
+    Int32,
+    Pointer<Utf8>,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_query_protobuf(
+  Pointer<Void> pool,
+  Pointer<Utf8> query,
+  Pointer<Uint8> params,
+  int paramsLen,
+  Pointer<Uint8> descriptor,
+  int descriptorLen,
+  Pointer<Utf8> messageName,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Returns the queries recorded under the pool's `recentQueryCapacity`,
+/// oldest first, as a trailer of a u32 count and, per query, the u64 start
+/// time and duration in microseconds and the query text.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_recent_queries(
+  Pointer<Void> pool,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Executes a parameterized query, appending to an empty result a trailer of
+/// every value as text: the u32 column count and names, the u32 row count,
+/// then per value a presence byte, 0 for NULL, followed by its text.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Pointer<Uint8>,
+    Int32,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_query_text(
+  Pointer<Void> pool,
+  Pointer<Utf8> query,
+  Pointer<Uint8> params,
+  int paramsLen,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Resets the session of [conn], rolling back any open transaction and
+/// clearing its temporary tables and user variables.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_conn_reset(
+  Pointer<Void> conn,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Executes a parameterized query, returning only the rows whose
+/// [filterColumn] value matches [filterRegex]; only exported by builds with
+/// the `regex` feature.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Pointer<Utf8>,
+    Pointer<Utf8>,
+    Pointer<Uint8>,
+    Int32,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_query_filtered(
+  Pointer<Void> pool,
+  Pointer<Utf8> query,
+  Pointer<Utf8> filterColumn,
+  Pointer<Utf8> filterRegex,
+  Pointer<Uint8> params,
+  int paramsLen,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Turns the column metadata cache on or off; turning it off empties it.
+@Native<Void Function(Bool)>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_set_metadata_cache(bool enabled);
+
+/// Writes how many metadata blocks were served from the cache and how many
+/// were derived, counted since the library was loaded; returns false if
+/// either pointer is null.
+@Native<Bool Function(Pointer<Uint64>, Pointer<Uint64>)>(
+  assetId: 'package:turbo_mysql/turbo_mysql_core',
+)
+external bool mysql_metadata_cache_stats(
+  Pointer<Uint64> hits,
+  Pointer<Uint64> derivations,
+);
+
+/// Executes a parameterized query, failing it with a cancelled error and
+/// killing it on the server once [token] is cancelled; [token] must outlive
+/// the callback.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Pointer<Uint8>,
+    Int32,
+    Pointer<Void>,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_query_cancellable(
+  Pointer<Void> pool,
+  Pointer<Utf8> query,
+  Pointer<Uint8> params,
+  int paramsLen,
+  Pointer<Void> token,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Switches the pool to the user and password of [newUrl], draining the
+/// existing connections once their in-flight queries finish; completes when
+/// they are closed.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_rotate_credentials(
+  Pointer<Void> pool,
+  Pointer<Utf8> newUrl,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Makes `mysql_pool_query` fail when a column flagged `NOT NULL` holds NULL.
+@Native<Bool Function(Pointer<Void>, Bool)>(
+  assetId: 'package:turbo_mysql/turbo_mysql_core',
+)
+external bool mysql_pool_set_strict_nullability(
+  Pointer<Void> pool,
+  bool enabled,
+);
+
+/// Runs a parameterized query in each database of [databases], a name list of
+/// a u32 count and each name as a blob, concurrently; the response holds the
+/// u32 database count, then per database in list order a success byte
+/// followed by its affected rows, last insert id and result set, or by the
+/// error message.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Uint8>,
+    Int32,
+    Pointer<Utf8>,
+    Pointer<Uint8>,
+    Int32,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_query_fanout(
+  Pointer<Void> pool,
+  Pointer<Uint8> databases,
+  int databasesLen,
+  Pointer<Utf8> query,
+  Pointer<Uint8> params,
+  int paramsLen,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Runs a statement list in one transaction, rolling it back with a row count
+/// error if a statement whose [required] byte is nonzero affects no rows; on
+/// commit, appends a trailer of the u32 statement count and each statement's
+/// u64 affected rows and last insert id.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Uint8>,
+    Int32,
+    Pointer<Uint8>,
+    Int32,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_run_transaction(
+  Pointer<Void> pool,
+  Pointer<Uint8> statements,
+  int statementsLen,
+  Pointer<Uint8> required,
+  int requiredLen,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Counts the distinct non-NULL values of the column at [columnIndex] of a
+/// parameterized query's result, responding with the count as a u64.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Uint32,
+    Pointer<Uint8>,
+    Int32,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_count_distinct(
+  Pointer<Void> pool,
+  Pointer<Utf8> query,
+  int columnIndex,
+  Pointer<Uint8> params,
+  int paramsLen,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Streams a table for backup: a chunk with marker 2 and the `SHOW CREATE
+/// TABLE` DDL as a blob, then row chunks of up to [batchSize] rows; the final
+/// result's affected rows is the number of rows dumped.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Uint32,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_dump_table(
+  Pointer<Void> pool,
+  Pointer<Utf8> table,
+  int batchSize,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Executes a parameterized query whose `{in}` token is replaced by a
+/// subquery over a temporary table loaded with [values], encoded like a
+/// parameter list; the table is dropped before the response is sent.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Pointer<Uint8>,
+    Int32,
+    Pointer<Uint8>,
+    Int32,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_query_in_temp(
+  Pointer<Void> pool,
+  Pointer<Utf8> query,
+  Pointer<Uint8> values,
+  int valuesLen,
+  Pointer<Uint8> params,
+  int paramsLen,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Describes the options the pool resolved, appending a trailer of the host,
+/// port, socket, user, a password presence byte in place of the password,
+/// the optional database, pool constraints, timeouts and the TLS mode.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_describe_opts(
+  Pointer<Void> pool,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Executes a parameterized query, renaming its result columns to the
+/// comma-separated [aliasesCsv]; fails if the alias count differs from the
+/// column count.
+@Native<
+  Void Function(
+    Pointer<Void>,
+    Pointer<Utf8>,
+    Pointer<Utf8>,
+    Pointer<Uint8>,
+    Int32,
+    Int64,
+    Pointer<NativeFunction<QueryCallbackNative>>,
+  )
+>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external void mysql_pool_query_aliased(
+  Pointer<Void> pool,
+  Pointer<Utf8> query,
+  Pointer<Utf8> aliasesCsv,
+  Pointer<Uint8> params,
+  int paramsLen,
+  int id,
+  Pointer<NativeFunction<QueryCallbackNative>> callback,
+);
+
+/// Makes `mysql_pool_query` and `mysql_pool_query_raw` append to the result
+/// a trailer of the execution and total microseconds as u64s.
+@Native<Bool Function(Pointer<Void>, Bool)>(
+  assetId: 'package:turbo_mysql/turbo_mysql_core',
+)
+external bool mysql_pool_set_timing(Pointer<Void> pool, bool enabled);
+
+/// Returns the version of the native response format.
+@Native<Int32 Function()>(assetId: 'package:turbo_mysql/turbo_mysql_core')
+external int mysql_protocol_version();
+
 /// Frees a buffer allocated by the Rust side.
 @Native<Void Function(Pointer<Uint8>, Int32)>(
   assetId: 'package:turbo_mysql/turbo_mysql_core',
@@ -76,15 +76,35 @@ class DataConverter {
     }
   }
 
+  /// Reads a structured `TIME` value (sign, hours, minutes, seconds, microseconds).
+  static Duration readTime(BinaryReader reader) {
+    final negative = reader.readUint8() == 1;
+    final duration = Duration(
+      hours: reader.readUint32(),
+      minutes: reader.readUint8(),
+      seconds: reader.readUint8(),
+      microseconds: reader.readUint32(),
+    );
+    return negative ? -duration : duration;
+  }
+
   /// Encodes a list of Dart parameters into a native memory block for Rust.
   static Pointer<Uint8> encodeParams(
     List<dynamic> params,
     Arena arena,
     BinaryWriter writer,
   ) {
-    writer.writeUint32(params.length);
-    for (final param in params) {
-      writeParam(writer, param);
+    if (params.isNotEmpty && params.every((param) => param is int)) {
+      // All-integer lists skip the per-value type tags.
+      writer.writeUint32(params.length | SqlParamType.rawInt64ListFlag);
+      for (final param in params) {
+        writer.writeInt64(param as int);
+      }
+    } else {
+      writer.writeUint32(params.length);
+      for (final param in params) {
+        writeParam(writer, param);
+      }
     }
     final bytes = writer.toBytes();
     final ptr = arena.allocate<Uint8>(bytes.length);
@@ -106,12 +126,31 @@ class DataConverter {
       writer.writeUint8(SqlParamType.intValue);
       writer.writeInt64(param ? 1 : 0);
     } else if (param is DateTime) {
-      writer.writeUint8(SqlParamType.stringValue);
-      final str = param.toIso8601String().replaceAll('T', ' ').substring(0, 19);
-      writer.writeString(str);
+      writer.writeUint8(SqlParamType.datetimeValue);
+      writer.writeUint16(param.year);
+      writer.writeUint8(param.month);
+      writer.writeUint8(param.day);
+      writer.writeUint8(param.hour);
+      writer.writeUint8(param.minute);
+      writer.writeUint8(param.second);
+      writer.writeUint32(param.millisecond * 1000 + param.microsecond);
+    } else if (param is Duration) {
+      final magnitude = param.abs();
+      writer.writeUint8(SqlParamType.timeValue);
+      writer.writeUint8(param.isNegative ? 1 : 0);
+      writer.writeUint32(magnitude.inDays);
+      writer.writeUint8(magnitude.inHours % 24);
+      writer.writeUint8(magnitude.inMinutes % 60);
+      writer.writeUint8(magnitude.inSeconds % 60);
+      writer.writeUint32(magnitude.inMicroseconds % 1000000);
     } else if (param is BigInt) {
-      writer.writeUint8(SqlParamType.stringValue);
-      writer.writeString(param.toString());
+      if (!param.isNegative && param.bitLength <= 64) {
+        writer.writeUint8(SqlParamType.uintValue);
+        writer.writeInt64(param.toSigned(64).toInt());
+      } else {
+        writer.writeUint8(SqlParamType.stringValue);
+        writer.writeString(param.toString());
+      }
     } else if (param is String) {
       writer.writeUint8(SqlParamType.stringValue);
       writer.writeString(param);
@@ -0,0 +1,57 @@
+import 'dart:async';
+import 'dart:ffi';
+import 'dart:typed_data';
+import 'bindings.dart';
+import 'binary_io.dart';
+import 'query_dispatcher.dart';
+import 'mysql_exception.dart';
+
+final _lobFinalizer = NativeFinalizer(
+  Native.addressOf<NativeFunction<Void Function(Pointer<Void>)>>(
+    mysql_lob_destroy,
+  ).cast(),
+);
+
+/// A range read from a [MySqlLob].
+class LobRange {
+  /// Length of the whole value in bytes.
+  final int totalLength;
+
+  /// The requested bytes, shorter than asked when the value ends first.
+  final Uint8List bytes;
+
+  const LobRange(this.totalLength, this.bytes);
+}
+
+/// A TEXT or BLOB value located by its row key, read in ranges on demand.
+///
+/// Nothing is fetched until [read]; each read selects only the requested range
+/// on the server. Close the handle before closing its pool.
+class MySqlLob implements Finalizable {
+  final Pointer<Void> _lobPtr;
+  final NativeCallable<QueryCallbackNative> _callback;
+  bool _isClosed = false;
+
+  MySqlLob(this._lobPtr, this._callback) {
+    _lobFinalizer.attach(this, _lobPtr, detach: this);
+  }
+
+  /// Reads up to [length] bytes starting at [offset].
+  Future<LobRange> read(int offset, int length) async {
+    if (_isClosed) throw MySQLException('LOB handle is closed');
+
+    final (queryId, future) = registerQuery();
+    mysql_lob_read(_lobPtr, offset, length, queryId, _callback.nativeFunction);
+    final result = await future;
+    final reader = BinaryReader.fromBytes(result.trailer);
+    return LobRange(reader.readUint64(), reader.readBlob());
+  }
+
+  /// Releases the handle.
+  void close() {
+    if (_isClosed) return;
+    _lobFinalizer.detach(this);
+    mysql_lob_destroy(_lobPtr);
+    _isClosed = true;
+  }
+}
@@ -1,11 +1,69 @@
+/// Broad origin of a [MySQLException], as reported by the native layer.
+enum MySQLErrorCategory {
+  /// Client-side validation or an unclassified failure.
+  other,
+
+  /// Network or socket failure; the operation may succeed if retried.
+  io,
+
+  /// Protocol or driver failure.
+  driver,
+
+  /// Error returned by the MySQL server, such as a syntax error.
+  server,
+
+  /// Invalid connection URL.
+  url,
+
+  /// A statement affected a different number of rows than it required.
+  rowCount,
+
+  /// The server is at its connection limit; the operation may succeed later.
+  serverBusy,
+
+  /// The operation was cancelled by the caller before it completed.
+  cancelled,
+
+  /// The server rejected the credentials; retrying with them will not help.
+  authFailed,
+
+  /// The account's password has expired and must be changed.
+  passwordExpired,
+
+  /// A statement exceeded the server's `max_allowed_packet`; sending less per
+  /// statement, such as smaller batches, may succeed.
+  packetTooLarge,
+}
+
 /// Represents a generic exception that occurred during a MySQL operation.
 class MySQLException implements Exception {
   /// The error message returned by the operation.
   final String message;
 
+  /// The origin of the error.
+  final MySQLErrorCategory category;
+
   /// Creates a new [MySQLException] with the given [message].
-  MySQLException(this.message);
+  MySQLException(this.message, {this.category = MySQLErrorCategory.other});
+
+  /// Whether the failure was a network error or a busy server, so the
+  /// operation may succeed if retried.
+  bool get isRetryable =>
+      category == MySQLErrorCategory.io ||
+      category == MySQLErrorCategory.serverBusy;
 
   @override
   String toString() => 'MySQLException: $message';
 }
+
+/// Thrown when a query parameter could not be decoded by the native layer.
+class MySQLParameterException extends MySQLException {
+  /// Zero-based position of the malformed parameter.
+  final int index;
+
+  /// Creates a new [MySQLParameterException] for the parameter at [index].
+  MySQLParameterException(this.index, super.message);
+
+  @override
+  String toString() => 'MySQLParameterException: $message';
+}
@@ -40,4 +40,63 @@ class SqlParamType {
   static const int floatValue = 2;
   static const int stringValue = 3;
   static const int blobValue = 4;
+
+  /// An unsigned 64-bit integer, sent as its two's complement bit pattern.
+  static const int uintValue = 5;
+
+  /// A [MySqlColumnType] hint byte followed by a textual value, telling Rust
+  /// which native value to bind it as (e.g. a date string bound as `DATE`).
+  static const int typedValue = 10;
+
+  /// A [MySqlColumnType] byte, a flags byte (`0x80` for unsigned integers) and
+  /// the value pre-encoded in the MySQL binary protocol, bound as-is.
+  static const int rawValue = 11;
+
+  /// A date and time: the year as a [Uint16], then month, day, hour, minute
+  /// and second bytes and the microseconds as a [Uint32].
+  static const int datetimeValue = 12;
+
+  /// A time or duration: a sign byte (1 for negative), the days as a
+  /// [Uint32], then hour, minute and second bytes and the microseconds as a
+  /// [Uint32].
+  static const int timeValue = 13;
+
+  /// Set in a parameter list's count when the values are untagged int64s.
+  static const int rawInt64ListFlag = 0x80000000;
 }
+
+/// Status byte following the protocol version in every response frame.
+class ResponseStatus {
+  static const int error = 0;
+  static const int ok = 1;
+
+  /// An intermediate frame of a streamed response; more frames follow.
+  static const int chunk = 2;
+  static const int paramError = 3;
+
+  /// The uncompressed length as a u32, then the gzip of the original frame.
+  static const int compressed = 4;
+  static const int bufferTooSmall = 5;
+}
+
+/// Value tags used in the Rust-to-Dart result encoding protocol.
+class SqlValueTag {
+  static const int nullValue = 0;
+  static const int bytes = 1;
+  static const int time = 2;
+  static const int boolean = 3;
+
+  /// A u32 SRID followed by the WKB blob of a geometry value.
+  static const int geometry = 4;
+
+  /// A u8 bit width followed by the value of a `BIT(n)` column as a u64.
+  static const int bit = 5;
+}
+
+/// Set in a metadata block's column count when each column is followed by its
+/// SQL type name.
+const int columnsWithTypeNamesFlag = 0x80000000;
+
+/// Version of the native response format this package decodes, compared with
+/// the first byte of every frame and with `mysql_protocol_version()`.
+const int protocolVersion = 1;
@@ -7,9 +7,11 @@ import 'data_converter.dart';
 import 'query_dispatcher.dart';
 import 'mysql_config.dart';
 import 'mysql_exception.dart';
+import 'mysql_protocol.dart';
 import 'query_result.dart';
 import 'mysql_connection.dart';
 import 'prepared_statement.dart';
+import 'lob.dart';
 
 /// A pool of MySQL connections for executing queries and managing transactions.
 class MySqlPool {
@@ -28,6 +30,13 @@ class MySqlPool {
     if (_isInitialized) {
       throw MySQLException('Already connected');
     }
+    final nativeVersion = mysql_protocol_version();
+    if (nativeVersion != protocolVersion) {
+      throw MySQLException(
+        'Native library speaks response format $nativeVersion; '
+        'expected $protocolVersion',
+      );
+    }
 
     final urlStr = config.toConnectionString();
     final urlNative = urlStr.toNativeUtf8();
@@ -35,7 +44,17 @@ class MySqlPool {
       _poolPtr = mysql_pool_create(urlNative);
 
       if (_poolPtr == null || _poolPtr == nullptr) {
-        throw MySQLException('Failed to create MySQL pool');
+        final reason = mysql_validate_url(urlNative);
+        if (reason == nullptr) {
+          throw MySQLException('Failed to create MySQL pool');
+        }
+        try {
+          throw MySQLException(
+            'Failed to create MySQL pool: ${reason.toDartString()}',
+          );
+        } finally {
+          mysql_free_string(reason);
+        }
       }
 
       _callback = NativeCallable<QueryCallbackNative>.listener(
@@ -92,10 +111,33 @@ class MySqlPool {
   }
 
   /// Executes a parameterized SQL query using the MySQL Binary Protocol (Prepared Statements).
-  Future<QueryResult> query(
-    String sql, [
-    List<dynamic> params = const [],
-  ]) async {
+  Future<QueryResult> query(String sql, [List<dynamic> params = const []]) {
+    return queryWithOptions(sql, params);
+  }
+
+  /// Executes a parameterized SQL query like [query], with per-call options.
+  ///
+  /// When [maxExecutionTime] is set, the server aborts the statement once it runs
+  /// longer than the given duration. MariaDB supports this limit for any
+  /// statement; MySQL only for SELECT statements.
+  ///
+  /// When [compress] is `true`, the result is gzip-compressed before it crosses
+  /// into Dart, which lowers peak memory for large results at some CPU cost.
+  ///
+  /// When [enumOrdinals] is `true`, `ENUM` columns return the 1-based index of
+  /// each value in the column definition instead of its label; values matching
+  /// no member, such as the empty string, return 0.
+  ///
+  /// When [typeNames] is `true`, [QueryResult.columnTypeNames] holds the SQL
+  /// type name of each column, such as `VARCHAR` or `BIGINT UNSIGNED`.
+  Future<QueryResult> queryWithOptions(
+    String sql,
+    List<dynamic> params, {
+    Duration? maxExecutionTime,
+    bool compress = false,
+    bool enumOrdinals = false,
+    bool typeNames = false,
+  }) async {
     if (!_isInitialized || _poolPtr == null || _poolPtr == nullptr) {
       throw MySQLException('Not connected. Call connect() first.');
     }
@@ -106,12 +148,20 @@ class MySqlPool {
       final queryPtr = sql.toNativeUtf8(allocator: arena);
       final writer = BinaryWriter();
       final paramsPtr = DataConverter.encodeParams(params, arena, writer);
+      final options = arena<MysqlQueryOptionsNative>();
+      options.ref
+        ..structSize = sizeOf<MysqlQueryOptionsNative>()
+        ..serverTimeoutMs = maxExecutionTime?.inMilliseconds ?? 0
+        ..compress = compress
+        ..enumOrdinals = enumOrdinals
+        ..typeNames = typeNames;
 
       mysql_pool_query(
         _poolPtr!,
         queryPtr,
         paramsPtr,
         writer.toBytes().length,
+        options,
         queryId,
         _callback!.nativeFunction,
       );
@@ -119,6 +169,36 @@ class MySqlPool {
     });
   }
 
+  /// Opens a handle on the [column] value of the [table] row whose
+  /// [keyColumns] equal [keyValues], reading nothing until [MySqlLob.read].
+  MySqlLob openLob(
+    String table,
+    String column,
+    List<String> keyColumns,
+    List<dynamic> keyValues,
+  ) {
+    if (!_isInitialized || _poolPtr == null || _poolPtr == nullptr) {
+      throw MySQLException('Not connected. Call connect() first.');
+    }
+
+    return using((arena) {
+      final writer = BinaryWriter();
+      final keyPtr = DataConverter.encodeParams(keyValues, arena, writer);
+      final lobPtr = mysql_pool_lob_open(
+        _poolPtr!,
+        table.toNativeUtf8(allocator: arena),
+        column.toNativeUtf8(allocator: arena),
+        keyColumns.join(',').toNativeUtf8(allocator: arena),
+        keyPtr,
+        writer.toBytes().length,
+      );
+      if (lobPtr == nullptr) {
+        throw MySQLException('Invalid LOB location');
+      }
+      return MySqlLob(lobPtr, _callback!);
+    });
+  }
+
   /// Prepares a SQL statement for repeated execution.
   Future<PreparedStatement> prepare(String sql) async {
     if (!_isInitialized || _poolPtr == null || _poolPtr == nullptr) {
@@ -160,6 +240,28 @@ class MySqlPool {
     });
   }
 
+  /// Starts a `REPEATABLE READ` transaction with a consistent snapshot and
+  /// returns a dedicated [MySqlConnection].
+  ///
+  /// Every query on the connection sees the data as of the moment the
+  /// snapshot was taken, ignoring rows committed by others afterwards. End it
+  /// with `commit()` or `rollback()`.
+  Future<MySqlConnection> beginSnapshot() async {
+    if (!_isInitialized || _poolPtr == null || _poolPtr == nullptr) {
+      throw MySQLException('Not connected. Call connect() first.');
+    }
+
+    final (queryId, future) = registerQuery();
+
+    mysql_pool_begin_snapshot(_poolPtr!, queryId, _callback!.nativeFunction);
+
+    return future.then((res) {
+      final ptrAddr = res.affectedRows;
+      final ptr = Pointer<Void>.fromAddress(ptrAddr);
+      return MySqlConnection(ptr, _callback!, isTransaction: true);
+    });
+  }
+
   /// Gets a dedicated connection from the pool without starting a transaction.
   Future<MySqlConnection> getConnection() async {
     if (!_isInitialized || _poolPtr == null || _poolPtr == nullptr) {
@@ -178,21 +280,59 @@ class MySqlPool {
   }
 
   /// Performs a batch insert operation using a connection from the pool.
+  ///
+  /// When [dedupKey] names a subset of [columns], rows repeating an earlier
+  /// row's values in those columns are dropped before insertion.
+  ///
+  /// [typeHints] holds a `MySqlColumnType` per column, or null for none;
+  /// string values of a hinted column are bound as that type, e.g. date
+  /// strings as `MySqlColumnType.date`.
+  ///
+  /// When [prepared] is `true`, a single-row INSERT is prepared once and
+  /// executed for every row instead of sending multi-row INSERT statements.
+  ///
+  /// A null value in one of the [defaultOnNull] columns leaves that column
+  /// to its `DEFAULT`. Each such row is inserted on its own, in its place in
+  /// [rows], since a multi-row INSERT shares a single column list.
   Future<int> insertBatch(
     String table,
     List<String> columns,
-    List<List<dynamic>> rows,
-  ) async {
-    return _executeBatch(table, columns, rows, false);
+    List<List<dynamic>> rows, {
+    List<String>? dedupKey,
+    List<int?>? typeHints,
+    bool prepared = false,
+    List<String>? defaultOnNull,
+  }) async {
+    return _executeBatch(
+      table,
+      columns,
+      rows,
+      false,
+      dedupKey,
+      typeHints,
+      prepared,
+      defaultOnNull,
+    );
   }
 
   /// Performs a batch upsert operation (ON DUPLICATE KEY UPDATE) using a connection from the pool.
   Future<int> upsertBatch(
     String table,
     List<String> columns,
-    List<List<dynamic>> rows,
-  ) async {
-    return _executeBatch(table, columns, rows, true);
+    List<List<dynamic>> rows, {
+    List<int?>? typeHints,
+    bool prepared = false,
+  }) async {
+    return _executeBatch(
+      table,
+      columns,
+      rows,
+      true,
+      null,
+      typeHints,
+      prepared,
+      null,
+    );
   }
 
   Future<int> _executeBatch(
@@ -200,12 +340,19 @@ class MySqlPool {
     List<String> columns,
     List<List<dynamic>> rows,
     bool onDuplicate,
+    List<String>? dedupKey,
+    List<int?>? typeHints,
+    bool prepared,
+    List<String>? defaultOnNull,
   ) async {
     if (!_isInitialized || _poolPtr == null || _poolPtr == nullptr) {
       throw MySQLException('Not connected. Call connect() first.');
     }
     if (rows.isEmpty) return 0;
     if (columns.isEmpty) throw MySQLException('Columns must not be empty');
+    if (typeHints != null && typeHints.length != columns.length) {
+      throw MySQLException('Type hint count does not match columns length');
+    }
 
     for (final row in rows) {
       if (row.length != columns.length) {
@@ -231,11 +378,39 @@ class MySqlPool {
       final ptr = arena.allocate<Uint8>(bytes.length);
       ptr.asTypedList(bytes.length).setAll(0, bytes);
 
+      final options = arena<MysqlBatchInsertOptionsNative>();
+      options.ref
+        ..structSize = sizeOf<MysqlBatchInsertOptionsNative>()
+        ..dedupColumns = dedupKey == null || dedupKey.isEmpty
+            ? nullptr
+            : dedupKey.join(',').toNativeUtf8(allocator: arena)
+        ..defaultColumns = defaultOnNull == null || defaultOnNull.isEmpty
+            ? nullptr
+            : defaultOnNull.join(',').toNativeUtf8(allocator: arena)
+        ..typeHints = nullptr
+        ..typeHintsLen = 0
+        ..mode = prepared ? 1 : 0;
+      if (typeHints != null) {
+        final hintsWriter = BinaryWriter();
+        hintsWriter.writeUint32(typeHints.length);
+        for (final hint in typeHints) {
+          hintsWriter.writeUint8(hint == null ? 0 : 1);
+          hintsWriter.writeUint8(hint ?? 0);
+        }
+        final hintBytes = hintsWriter.toBytes();
+        final hintsPtr = arena.allocate<Uint8>(hintBytes.length);
+        hintsPtr.asTypedList(hintBytes.length).setAll(0, hintBytes);
+        options.ref
+          ..typeHints = hintsPtr
+          ..typeHintsLen = hintBytes.length;
+      }
+
       if (onDuplicate) {
         mysql_pool_batch_upsert(
           _poolPtr!,
           tablePtr,
           columnsPtr,
+          options,
           ptr,
           bytes.length,
           queryId,
@@ -246,6 +421,7 @@ class MySqlPool {
           _poolPtr!,
           tablePtr,
           columnsPtr,
+          options,
           ptr,
           bytes.length,
           queryId,
@@ -1,10 +1,12 @@
 import 'dart:async';
 import 'dart:ffi';
+import 'dart:io';
 import 'dart:typed_data';
 import 'bindings.dart';
 import 'binary_io.dart';
 import 'data_converter.dart';
 import 'mysql_exception.dart';
+import 'mysql_protocol.dart';
 import 'query_result.dart';
 
 /// Global map to track pending queries by their unique ID.
@@ -19,6 +21,31 @@ int _nextQueryId = 1;
   return (id, completer.future);
 }
 
+/// Handlers receiving the intermediate frames of streamed operations.
+final Map<int, void Function(Uint8List payload)> _chunkHandlers = {};
+
+/// Registers a streamed operation: [onChunk] receives the payload of every
+/// intermediate frame, after its status byte, and the future completes with
+/// the final frame.
+(int, Future<QueryResult>) registerStream(
+  void Function(Uint8List payload) onChunk,
+) {
+  final (id, future) = registerQuery();
+  _chunkHandlers[id] = onChunk;
+  return (id, future);
+}
+
+/// Requests whose successful frame is returned undecoded.
+final Set<int> _rawRequests = {};
+
+/// Registers an operation whose successful payload, after its status byte,
+/// does not follow the result set layout and is returned as is.
+(int, Future<Uint8List>) registerRaw() {
+  final (id, future) = registerOp<Uint8List>();
+  _rawRequests.add(id);
+  return (id, future);
+}
+
 /// Registers a transaction or specialized operation that returns void/affected rows
 /// but is treated generically in the pipeline.
 (int, Future<T>) registerOp<T>() {
@@ -30,59 +57,152 @@ int _nextQueryId = 1;
 
 /// Global callback function invoked by Rust when a query completes.
 void handleQueryCallback(int id, Pointer<Uint8> dataPtr, int len) {
-  final Uint8List localBytes = Uint8List.fromList(dataPtr.asTypedList(len));
+  Uint8List localBytes = Uint8List.fromList(dataPtr.asTypedList(len));
 
   mysql_buffer_free(dataPtr, len);
 
-  final completer = _pendingQueries.remove(id);
+  final completer = _pendingQueries[id];
   if (completer == null) return;
 
+  if (localBytes.length < 2 || localBytes[0] != protocolVersion) {
+    _finish(id);
+    completer.completeError(
+      MySQLException(
+        'Unsupported response format version '
+        '${localBytes.isEmpty ? 'none' : localBytes[0]}; '
+        'expected $protocolVersion',
+      ),
+    );
+    return;
+  }
+  localBytes = Uint8List.sublistView(localBytes, 1);
+
   try {
+    if (localBytes[0] == ResponseStatus.compressed) {
+      localBytes = Uint8List.fromList(gzip.decode(localBytes.sublist(5)));
+    }
+    if (localBytes[0] == ResponseStatus.chunk) {
+      final onChunk = _chunkHandlers[id];
+      if (onChunk == null) {
+        throw StateError('unexpected streamed frame');
+      }
+      onChunk(Uint8List.sublistView(localBytes, 1));
+      return;
+    }
+    final raw = _rawRequests.contains(id);
+    _finish(id);
     final reader = BinaryReader.fromBytes(localBytes);
     final status = reader.readUint8();
 
-    if (status == 0) {
+    if (status == ResponseStatus.error) {
+      final categoryIndex = reader.readUint8();
       final msg = reader.readString();
-      completer.completeError(MySQLException(msg));
+      final category = categoryIndex < MySQLErrorCategory.values.length
+          ? MySQLErrorCategory.values[categoryIndex]
+          : MySQLErrorCategory.other;
+      completer.completeError(MySQLException(msg, category: category));
+    } else if (status == ResponseStatus.paramError) {
+      final index = reader.readUint32();
+      final msg = reader.readString();
+      completer.completeError(MySQLParameterException(index, msg));
+    } else if (status != ResponseStatus.ok) {
+      completer.completeError(
+        MySQLException('Unsupported response status $status'),
+      );
+    } else if (raw) {
+      completer.complete(Uint8List.sublistView(localBytes, 1));
     } else {
       final affectedRows = reader.readUint64();
       final lastInsertId = reader.readUint64();
-      final colCount = reader.readUint32();
-
-      final columns = <String>[];
-      final colTypes = <int>[];
-      final charsets = <int>[];
-
-      for (var i = 0; i < colCount; i++) {
-        columns.add(reader.readString());
-        colTypes.add(reader.readUint16());
-        charsets.add(reader.readUint16());
-      }
-
-      final rowCount = reader.readUint32();
-      final rows = List<List<dynamic>>.generate(rowCount, (_) {
-        return List<dynamic>.generate(colCount, (i) {
-          final isPresent = reader.readUint8() == 1;
-          if (!isPresent) return null;
-          final bytes = reader.readBlob();
-          return DataConverter.decodeValue(bytes, colTypes[i], charsets[i]);
-        }, growable: false);
-      }, growable: false);
-
-      completer.complete(QueryResult(
-        columns: columns,
-        rows: rows,
+      completer.complete(readResultSet(
+        reader,
         affectedRows: affectedRows,
         lastInsertId: lastInsertId,
+        withTrailer: true,
       ));
     }
   } catch (e, st) {
-    completer.completeError(
-      MySQLException('Failed to parse binary result: $e $st'),
+    _finish(id);
+    if (!completer.isCompleted) {
+      completer.completeError(
+        MySQLException('Failed to parse binary result: $e $st'),
+      );
+    }
+  }
+}
+
+/// Reads a result set block: column metadata, then the rows.
+///
+/// With [withTrailer], every byte left after the rows becomes the result's
+/// [QueryResult.trailer].
+QueryResult readResultSet(
+  BinaryReader reader, {
+  int affectedRows = 0,
+  int lastInsertId = 0,
+  bool withTrailer = false,
+}) {
+  final colCountField = reader.readUint32();
+  final hasTypeNames = colCountField & columnsWithTypeNamesFlag != 0;
+  final colCount = colCountField & ~columnsWithTypeNamesFlag;
+
+  final columns = <String>[];
+  final colTypes = <int>[];
+  final charsets = <int>[];
+  final typeNames = hasTypeNames ? <String>[] : null;
+
+  for (var i = 0; i < colCount; i++) {
+    columns.add(reader.readString());
+    colTypes.add(reader.readUint16());
+    charsets.add(reader.readUint16());
+    typeNames?.add(reader.readString());
+  }
+
+  final rowCount = reader.readUint32();
+  final rows = List<List<dynamic>>.generate(rowCount, (_) {
+    return List<dynamic>.generate(
+      colCount,
+      (i) => readCell(reader, colTypes[i], charsets[i]),
+      growable: false,
     );
+  }, growable: false);
+
+  return QueryResult(
+    columns: columns,
+    rows: rows,
+    affectedRows: affectedRows,
+    lastInsertId: lastInsertId,
+    columnTypeNames: typeNames,
+    trailer: withTrailer ? reader.readRemaining() : null,
+  );
+}
+
+/// Reads one tagged cell of a column with the given type and charset.
+dynamic readCell(BinaryReader reader, int colType, int charset) {
+  switch (reader.readUint8()) {
+    case SqlValueTag.nullValue:
+      return null;
+    case SqlValueTag.time:
+      return DataConverter.readTime(reader);
+    case SqlValueTag.boolean:
+      return reader.readUint8() != 0;
+    case SqlValueTag.geometry:
+      return MySqlGeometry(reader.readUint32(), reader.readBlob());
+    case SqlValueTag.bit:
+      reader.readUint8(); // Declared width; the value is complete without it.
+      return reader.readUint64();
+    default:
+      final bytes = reader.readBlob();
+      return DataConverter.decodeValue(bytes, colType, charset);
   }
 }
 
+/// Stops tracking a request once its final frame arrived.
+void _finish(int id) {
+  _pendingQueries.remove(id);
+  _chunkHandlers.remove(id);
+  _rawRequests.remove(id);
+}
+
 /// Clears all pending queries with an error (used when pool closes).
 void clearPendingQueries() {
   for (final completer in _pendingQueries.values) {
@@ -91,4 +211,6 @@ void clearPendingQueries() {
     }
   }
   _pendingQueries.clear();
+  _chunkHandlers.clear();
+  _rawRequests.clear();
 }
@@ -1,3 +1,5 @@
+import 'dart:typed_data';
+
 /// Represents the result of a MySQL query execution.
 class QueryResult {
   /// The list of column names returned by the query.
@@ -12,13 +14,23 @@ class QueryResult {
   /// The auto-generated ID from the last INSERT operation.
   final int lastInsertId;
 
+  /// The SQL type name of each column (e.g. `INT UNSIGNED`), or `null` unless
+  /// type names were requested for the query.
+  final List<String>? columnTypeNames;
+
+  /// Fields some operations append after the rows, in the layout documented
+  /// by the native function; empty for plain queries.
+  final Uint8List trailer;
+
   /// Creates a [QueryResult] with the given data.
   QueryResult({
     required this.columns,
     required this.rows,
     required this.affectedRows,
     required this.lastInsertId,
-  });
+    this.columnTypeNames,
+    Uint8List? trailer,
+  }) : trailer = trailer ?? Uint8List(0);
 
   /// Returns the rows as a list of maps.
   ///
@@ -40,3 +52,18 @@ class QueryResult {
   String toString() =>
       'QueryResult(rows: ${rows.length}, affected: $affectedRows, id: $lastInsertId)';
 }
+
+/// A spatial value read from a geometry column.
+class MySqlGeometry {
+  /// The spatial reference system identifier, or 0 when unknown.
+  final int srid;
+
+  /// The geometry in Well-Known Binary format.
+  final Uint8List wkb;
+
+  /// Creates a [MySqlGeometry] with the given SRID and WKB bytes.
+  const MySqlGeometry(this.srid, this.wkb);
+
+  @override
+  String toString() => 'MySqlGeometry(srid: $srid, ${wkb.length} bytes)';
+}
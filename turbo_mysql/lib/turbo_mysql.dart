@@ -49,3 +49,4 @@ export 'src/query_result.dart';
 export 'src/mysql_config.dart';
 export 'src/pool.dart';
 export 'src/mysql_connection.dart';
+export 'src/lob.dart';
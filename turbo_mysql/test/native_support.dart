@@ -0,0 +1,211 @@
+import 'dart:ffi';
+import 'dart:io';
+import 'dart:typed_data';
+import 'package:ffi/ffi.dart';
+import 'package:turbo_mysql/turbo_mysql.dart';
+import 'package:turbo_mysql/src/bindings.dart';
+import 'package:turbo_mysql/src/binary_io.dart';
+import 'package:turbo_mysql/src/data_converter.dart';
+import 'package:turbo_mysql/src/query_dispatcher.dart';
+
+/// Plumbing for tests of native functions that have no Dart API.
+
+/// Connection settings shared by the integration tests.
+MySqlConfig testConfig({String? dbName}) => MySqlConfig(
+  host: Platform.environment['DB_HOST'] ?? '127.0.0.1',
+  user: Platform.environment['DB_USER'] ?? 'root',
+  pass: Platform.environment['DB_PASS'] ?? 'password',
+  dbName: dbName ?? Platform.environment['DB_NAME'] ?? 'test',
+  port: int.tryParse(Platform.environment['DB_PORT'] ?? '3306') ?? 3306,
+);
+
+/// Cargo features the native library was built with, from
+/// `TURBO_MYSQL_FEATURES` (comma-separated).
+final Set<String> nativeFeatures = (Platform.environment['TURBO_MYSQL_FEATURES'] ?? '')
+    .split(',')
+    .map((f) => f.trim())
+    .where((f) => f.isNotEmpty)
+    .toSet();
+
+/// A skip reason unless the native library was built with [feature].
+String? requiresFeature(String feature) => nativeFeatures.contains(feature)
+    ? null
+    : 'requires a native library built with the $feature feature';
+
+final _callback = NativeCallable<QueryCallbackNative>.listener(
+  handleQueryCallback,
+)..keepIsolateAlive = false;
+
+/// The callback routing native responses to the package dispatcher.
+Pointer<NativeFunction<QueryCallbackNative>> get nativeCallback =>
+    _callback.nativeFunction;
+
+/// Creates a native pool for [config], failing the test if it is rejected.
+Pointer<Void> createNativePool([MySqlConfig? config]) {
+  final url = (config ?? testConfig()).toConnectionString().toNativeUtf8();
+  try {
+    final pool = mysql_pool_create(url);
+    if (pool == nullptr) throw StateError('mysql_pool_create failed');
+    return pool;
+  } finally {
+    malloc.free(url);
+  }
+}
+
+/// Starts a native call with a request id and returns its decoded result.
+Future<QueryResult> nativeQuery(void Function(int id) start) {
+  final (id, future) = registerQuery();
+  start(id);
+  return future;
+}
+
+/// Starts a native call whose successful payload has its own layout.
+Future<BinaryReader> nativeRaw(void Function(int id) start) async {
+  final (id, future) = registerRaw();
+  start(id);
+  return BinaryReader.fromBytes(await future);
+}
+
+/// Starts a streamed native call, returning its chunk payloads and final result.
+Future<(List<BinaryReader>, QueryResult)> nativeStream(
+  void Function(int id) start,
+) async {
+  final chunks = <BinaryReader>[];
+  final (id, future) = registerStream(
+    (payload) => chunks.add(BinaryReader.fromBytes(Uint8List.fromList(payload))),
+  );
+  start(id);
+  final result = await future;
+  return (chunks, result);
+}
+
+/// Encodes [params] in the parameter layout, allocated in [arena].
+(Pointer<Uint8>, int) encodeParams(List<dynamic> params, Arena arena) {
+  final writer = BinaryWriter();
+  final ptr = DataConverter.encodeParams(params, arena, writer);
+  return (ptr, writer.toBytes().length);
+}
+
+/// Copies [bytes] into native memory allocated in [arena].
+(Pointer<Uint8>, int) nativeBytes(List<int> bytes, Arena arena) {
+  final ptr = arena.allocate<Uint8>(bytes.isEmpty ? 1 : bytes.length);
+  ptr.asTypedList(bytes.length).setAll(0, bytes);
+  return (ptr, bytes.length);
+}
+
+/// Creates a native pool from the test URL with the options set by
+/// [configure], failing the test if they are rejected.
+Pointer<Void> createConfiguredPool(
+  void Function(MysqlPoolConfigNative config, Arena arena) configure,
+) {
+  return using((arena) {
+    final config = arena<MysqlPoolConfigNative>();
+    config.ref
+      ..structSize = sizeOf<MysqlPoolConfigNative>()
+      ..url = testConfig().toConnectionString().toNativeUtf8(allocator: arena)
+      ..stmtCacheSize = -1;
+    configure(config.ref, arena);
+    final pool = mysql_pool_create_with_config(config);
+    if (pool == nullptr) throw StateError('mysql_pool_create_with_config failed');
+    return pool;
+  });
+}
+
+/// Runs [sql] through `mysql_pool_query` with default options.
+Future<QueryResult> poolQuery(
+  Pointer<Void> pool,
+  String sql, [
+  List<dynamic> params = const [],
+]) {
+  return using((arena) {
+    final query = sql.toNativeUtf8(allocator: arena);
+    final (paramsPtr, paramsLen) = encodeParams(params, arena);
+    return nativeQuery(
+      (id) => mysql_pool_query(
+        pool,
+        query,
+        paramsPtr,
+        paramsLen,
+        nullptr,
+        id,
+        nativeCallback,
+      ),
+    );
+  });
+}
+
+/// Starts a transaction on [pool] and returns its connection handle, to be
+/// released with `mysql_conn_destroy`.
+Future<Pointer<Void>> beginNativeTransaction(Pointer<Void> pool) async {
+  final result = await nativeQuery(
+    (id) => mysql_pool_begin_transaction(pool, id, nativeCallback),
+  );
+  return Pointer<Void>.fromAddress(result.affectedRows);
+}
+
+/// Takes a dedicated connection from [pool] without starting a transaction.
+Future<Pointer<Void>> nativeConnection(Pointer<Void> pool) async {
+  final result = await nativeQuery(
+    (id) => mysql_pool_get_connection(pool, id, nativeCallback),
+  );
+  return Pointer<Void>.fromAddress(result.affectedRows);
+}
+
+/// Runs [sql] on a dedicated connection through `mysql_conn_query`.
+Future<QueryResult> connQuery(
+  Pointer<Void> conn,
+  String sql, [
+  List<dynamic> params = const [],
+]) {
+  return using((arena) {
+    final query = sql.toNativeUtf8(allocator: arena);
+    final (paramsPtr, paramsLen) = encodeParams(params, arena);
+    return nativeQuery(
+      (id) => mysql_conn_query(
+        conn,
+        query,
+        paramsPtr,
+        paramsLen,
+        id,
+        nativeCallback,
+      ),
+    );
+  });
+}
+
+/// Commits the transaction on [conn].
+Future<void> connCommit(Pointer<Void> conn) =>
+    nativeQuery((id) => mysql_conn_commit(conn, id, nativeCallback));
+
+/// Rolls back the transaction on [conn].
+Future<void> connRollback(Pointer<Void> conn) =>
+    nativeQuery((id) => mysql_conn_rollback(conn, id, nativeCallback));
+
+/// Encodes [rows] in the batch insert layout, allocated in [arena].
+(Pointer<Uint8>, int) encodeRows(List<List<dynamic>> rows, Arena arena) {
+  final writer = BinaryWriter()..writeUint32(rows.length);
+  for (final row in rows) {
+    for (final value in row) {
+      DataConverter.writeParam(writer, value);
+    }
+  }
+  return nativeBytes(writer.toBytes(), arena);
+}
+
+/// Encodes `(query, params)` pairs in the statement list layout, allocated in
+/// [arena].
+(Pointer<Uint8>, int) encodeStatements(
+  List<(String, List<dynamic>)> statements,
+  Arena arena,
+) {
+  final writer = BinaryWriter()..writeUint32(statements.length);
+  for (final (query, params) in statements) {
+    writer
+      ..writeString(query)
+      ..writeUint32(params.length);
+    for (final param in params) {
+      DataConverter.writeParam(writer, param);
+    }
+  }
+  return nativeBytes(writer.toBytes(), arena);
+}
@@ -174,6 +174,27 @@ void main() {
       expect(result.rows[0][0].toString(), contains('23:59:59'));
     });
 
+    test('TIME range boundaries decode as Duration', () async {
+      await mysql.query('INSERT INTO test_datatypes (time_val) VALUES (?)', [
+        '838:59:59',
+      ]);
+      await mysql.query('INSERT INTO test_datatypes (time_val) VALUES (?)', [
+        '-12:30:15',
+      ]);
+
+      final result = await mysql.query(
+        'SELECT time_val FROM test_datatypes ORDER BY id',
+      );
+      expect(
+        result.rows[0][0],
+        const Duration(hours: 838, minutes: 59, seconds: 59),
+      );
+      expect(
+        result.rows[1][0],
+        -const Duration(hours: 12, minutes: 30, seconds: 15),
+      );
+    });
+
     test('DATETIME full precision', () async {
       await mysql.query(
         'INSERT INTO test_datatypes (datetime_val) VALUES (?)',
@@ -1661,6 +1661,49 @@ void main() {
       expect(result.rows[0][0].toString(), '1');
     });
 
+    test('max execution time aborts a slow SELECT server-side', () async {
+      final stopwatch = Stopwatch()..start();
+      try {
+        final result = await mysql.queryWithOptions(
+          'SELECT SLEEP(5) AS slept',
+          [],
+          maxExecutionTime: const Duration(milliseconds: 100),
+        );
+        expect(result.rows[0][0], 1);
+      } on MySQLException catch (e) {
+        expect(e.message, contains('maximum statement execution time'));
+      }
+      expect(stopwatch.elapsed, lessThan(const Duration(seconds: 2)));
+    });
+
+    test('max execution time rejects non-SELECT statements', () async {
+      expect(
+        () => mysql.queryWithOptions(
+          'INSERT INTO test_edge (data) VALUES (?)',
+          ['x'],
+          maxExecutionTime: const Duration(milliseconds: 100),
+        ),
+        throwsA(isA<MySQLException>()),
+      );
+    });
+
+    test('query options are applied through named parameters', () async {
+      final plain = await mysql.queryWithOptions(
+        'SELECT ? AS n, ? AS s',
+        [7, 'seven'],
+      );
+      final compressed = await mysql.queryWithOptions(
+        'SELECT ? AS n, ? AS s',
+        [7, 'seven'],
+        compress: true,
+        typeNames: true,
+      );
+      expect(compressed.rows[0][0].toString(), plain.rows[0][0].toString());
+      expect(compressed.rows[0][1], plain.rows[0][1]);
+      expect(plain.columnTypeNames, isNull);
+      expect(compressed.columnTypeNames, hasLength(2));
+    });
+
     test('handles query with no results', () async {
       final result = await mysql.query(
         'SELECT * FROM test_edge WHERE id = ?',
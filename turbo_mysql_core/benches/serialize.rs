@@ -0,0 +1,163 @@
+//! Serialization and parameter decoding benchmarks, run with
+//! `cargo bench --bench serialize`.
+
+use mysql_async::consts::ColumnType;
+use mysql_async::{Column, Row, Value};
+use std::sync::Arc;
+use std::time::Instant;
+use turbo_mysql_core::utils::{
+    ResultSetEncoder, estimate_result_set_size, try_parse_params_list, write_result_set,
+};
+
+/// Builds `rows` rows of `cols` columns alternating short strings, long strings and integers.
+fn wide_rows(rows: usize, cols: usize) -> Vec<Row> {
+    let columns: Arc<[Column]> = (0..cols)
+        .map(|i| {
+            let kind = if i % 3 == 2 {
+                ColumnType::MYSQL_TYPE_LONGLONG
+            } else {
+                ColumnType::MYSQL_TYPE_VAR_STRING
+            };
+            Column::new(kind).with_name(format!("column_{}", i).as_bytes())
+        })
+        .collect();
+    (0..rows)
+        .map(|r| {
+            let values = (0..cols)
+                .map(|i| match i % 3 {
+                    0 => Value::Bytes(format!("row {}", r).into_bytes()),
+                    1 => Value::Bytes(vec![b'x'; 40 + r % 80]),
+                    _ => Value::Int(r as i64),
+                })
+                .collect();
+            mysql_common::row::new_row(values, columns.clone())
+        })
+        .collect()
+}
+
+/// Encodes `rows` into a buffer starting at `capacity` bytes, returning how
+/// often the buffer had to grow and the elapsed time.
+fn encode_counting(rows: Vec<Row>, capacity: usize) -> (usize, u128) {
+    let start = Instant::now();
+    let mut buf = Vec::with_capacity(capacity);
+    let mut reallocations = 0;
+    let mut last_capacity = buf.capacity();
+    let mut encoder = ResultSetEncoder::new(&mut buf);
+    for row in rows {
+        encoder.push(row);
+        let capacity = encoder.capacity();
+        if capacity != last_capacity {
+            reallocations += 1;
+            last_capacity = capacity;
+        }
+    }
+    encoder.finish();
+    (reallocations, start.elapsed().as_micros())
+}
+
+/// Reallocations of a wide result under the former fixed 64 bytes per row
+/// guess and under the sampled estimate.
+fn result_buffer_preallocation() {
+    for (rows, cols) in [(10_000, 4), (10_000, 30), (1_000, 120)] {
+        let data = wide_rows(rows, cols);
+        let fixed = 20 + data.len() * 64;
+        let estimated = 17 + estimate_result_set_size(&data);
+        let (fixed_reallocs, fixed_us) = encode_counting(data.clone(), fixed);
+        let (estimated_reallocs, estimated_us) = encode_counting(data, estimated);
+        println!(
+            "preallocation {}x{}: fixed guess {} reallocations in {} us, estimate {} reallocations in {} us",
+            rows, cols, fixed_reallocs, fixed_us, estimated_reallocs, estimated_us
+        );
+    }
+}
+
+/// Peak resident memory of this process in KiB, from `VmHWM`.
+fn peak_rss_kib() -> u64 {
+    std::fs::read_to_string("/proc/self/status")
+        .ok()
+        .and_then(|status| {
+            let line = status.lines().find(|l| l.starts_with("VmHWM:"))?;
+            line.split_whitespace().nth(1)?.parse().ok()
+        })
+        .unwrap_or(0)
+}
+
+/// Encodes a large result in a child process, either keeping every row alive
+/// until the buffer is complete or dropping each row once it is encoded.
+fn encode_large_result(keep_rows: bool) {
+    let rows = wide_rows(200_000, 12);
+    let baseline = peak_rss_kib();
+    let mut buf = Vec::new();
+    if keep_rows {
+        let mut encoder = ResultSetEncoder::new(&mut buf);
+        for row in &rows {
+            encoder.push(row.clone());
+        }
+        encoder.finish();
+        drop(rows);
+    } else {
+        write_result_set(&mut buf, rows);
+    }
+    println!("{} {}", baseline, peak_rss_kib());
+}
+
+/// Peak memory of serializing a large buffered result, holding all rows plus
+/// the output versus dropping each row after it is encoded.
+fn result_peak_memory() {
+    for (label, mode) in [("rows kept", "keep"), ("rows dropped", "drop")] {
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["--peak-memory", mode])
+            .output()
+            .expect("failed to run the benchmark child process");
+        let report = String::from_utf8_lossy(&output.stdout);
+        let mut kib = report
+            .split_whitespace()
+            .filter_map(|v| v.parse::<u64>().ok());
+        let (rows, peak) = (kib.next().unwrap_or(0), kib.next().unwrap_or(0));
+        println!(
+            "peak memory, {}: {} KiB after building the rows, {} KiB peak",
+            label, rows, peak
+        );
+    }
+}
+
+/// Decoding of homogeneous integer parameters, tagged versus raw i64s.
+fn int_param_decoding() {
+    const COUNT: u32 = 10_000;
+    const ROUNDS: u32 = 200;
+    let mut tagged = COUNT.to_le_bytes().to_vec();
+    let mut raw = (COUNT | 0x8000_0000).to_le_bytes().to_vec();
+    for i in 0..COUNT as i64 {
+        tagged.push(1);
+        tagged.extend_from_slice(&i.to_le_bytes());
+        raw.extend_from_slice(&i.to_le_bytes());
+    }
+    let decode = |data: &[u8]| {
+        try_parse_params_list(data.as_ptr(), data.len() as i32)
+            .ok()
+            .unwrap()
+    };
+    assert_eq!(decode(&tagged), decode(&raw));
+    for (label, data) in [("tagged", &tagged), ("raw", &raw)] {
+        let start = Instant::now();
+        for _ in 0..ROUNDS {
+            std::hint::black_box(decode(std::hint::black_box(data)));
+        }
+        println!(
+            "int parameters, {}: {} ns per value",
+            label,
+            start.elapsed().as_nanos() / (COUNT * ROUNDS) as u128
+        );
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(pos) = args.iter().position(|a| a == "--peak-memory") {
+        encode_large_result(args.get(pos + 1).map(String::as_str) == Some("keep"));
+        return;
+    }
+    result_buffer_preallocation();
+    result_peak_memory();
+    int_param_decoding();
+}
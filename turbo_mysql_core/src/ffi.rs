@@ -1,21 +1,126 @@
+//! C ABI of the library.
+//!
+//! # Response protocol
+//!
+//! Every callback receives a frame led by `PROTOCOL_VERSION` and a status byte
+//! (`STATUS_*` in `utils`). Integers are little-endian and blobs are a u32
+//! length followed by the bytes.
+//!
+//! A `STATUS_OK` frame holds the affected rows and last insert id as u64s, then
+//! a result set block: the column count as a u32, each column's name, type and
+//! charset, the row count as a u32 and the tagged values row by row. Functions
+//! that return no rows send an empty block. Unless listed below, nothing
+//! follows the block.
+//!
+//! Fields appended after the block:
+//!
+//! - `mysql_pool_query`, `mysql_pool_query_raw` with timing enabled: the
+//!   microseconds until the last row was read, then including the connection
+//!   acquisition, as u64s.
+//! - `mysql_pool_exec_with_warnings`: the warning count as a u16, then a block
+//!   of `SHOW WARNINGS` rows, empty unless they were fetched.
+//! - `mysql_pool_query_with_found_rows`: `FOUND_ROWS()` as a u64.
+//! - `mysql_pool_query_hashed`: a u64 hash of the block, a u32 row count and a
+//!   u64 hash per row.
+//! - `mysql_pool_batch_insert` with dedup columns: the rows dropped as a u64.
+//! - `mysql_pool_call_full`: a block holding one row of OUT values.
+//!
+//! Fields appended after an empty block:
+//!
+//! - `mysql_pool_recent_queries`: a u32 count, then per query its start in
+//!   microseconds since the Unix epoch and duration in microseconds as u64s
+//!   and its text as a blob.
+//! - `mysql_pool_describe_opts`: host blob, port u16, socket and user blobs, a
+//!   password-set u8, an optional database blob, minimum and maximum
+//!   connections as u32s, the inactive connection TTL in milliseconds as a
+//!   u64, the optional connection TTL in milliseconds, `wait_timeout` in
+//!   seconds and default query timeout in milliseconds as u64s, and the TLS
+//!   mode (`TLS_*`) as a u8.
+//! - `mysql_pool_run_transaction`: a u32 statement count, then each
+//!   statement's affected rows and last insert id as u64s.
+//! - `mysql_pool_blob_to_fd`: the bytes written as a u64.
+//! - `mysql_pool_query_csv`: the CSV as a blob.
+//! - `mysql_pool_query_protobuf`: the message count as a u32 and the
+//!   length-delimited messages as a blob.
+//! - `mysql_pool_query_text`: a u32 column count and the names as blobs, then
+//!   a u32 row count and the cells as written by `write_text_row`.
+//! - `mysql_pool_replication_lag`: the optional lag in seconds as an i64.
+//! - `mysql_pool_server_flavor`: `FLAVOR_*` as a u8, major, minor and patch
+//!   version as u16s and `VERSION()` as a blob.
+//! - `mysql_pool_next_auto_increment`: the optional next value as a u64.
+//! - `mysql_lob_read`: the value's total length as a u64 and the range as a
+//!   blob.
+//!
+//! Optional fields are a presence byte followed by the value when it is 1.
+//!
+//! Frames without the leading counters:
+//!
+//! - `mysql_pool_query_values`: the column count, row count and values.
+//! - `mysql_pool_query_reduce`: the tagged result value.
+//! - `mysql_pool_count_distinct`: the count as a u64.
+//! - `mysql_pool_insert`: the last insert id as a u64.
+//! - `mysql_pool_query_page`: see `serialize_page`.
+//! - `mysql_pool_query_grouped`: see `serialize_grouped_result`.
+//! - `mysql_pool_exec_multi`: see `serialize_multi_result`.
+//! - `mysql_conn_batch_statements`, `mysql_pool_query_fanout`: see
+//!   `serialize_statement_results`.
+//!
+//! `mysql_pool_count_result_sets`, `mysql_pool_warmup` and
+//! `mysql_pool_dump_table` report their count in the affected rows slot.
+//!
+//! Streamed responses send `STATUS_CHUNK` frames and end with a `STATUS_OK`
+//! frame carrying the totals. Row chunks are laid out by `RowChunkEncoder`,
+//! dumped DDL by `encode_ddl_chunk`, batch insert progress by
+//! `encode_insert_chunk` and binlog events by `encode_binlog_event`.
+//! `mysql_cursor_fetch` sends one row chunk with a trailing state byte: 0
+//! when more rows may follow, 1 once exhausted, 2 on timeout.
+
 use crate::get_runtime;
 use crate::types::{
-    CallbackType, CallbackWrapper, MysqlConnection, MysqlPool, MysqlPreparedStatement,
+    BatchOptions, CallbackType, CallbackWrapper, ConnectRetry, CursorEvent, CursorState, HashRing,
+    MysqlBatchInsertOptions, MysqlCancelToken, MysqlConnection, MysqlCursor, MysqlLob, MysqlPool,
+    MysqlPoolConfig, MysqlPoolStatement, MysqlPreparedStatement, MysqlQueryOptions,
+    MysqlShardedPool, PoolHandle, RecentQueries, ShardStrategy, SlowQueryLog,
 };
 use crate::utils::{
-    BinaryWrite, parse_params_list, ptr_to_string, ptr_to_vec, send_error, send_response,
-    serialize_result,
+    BinaryWrite, ERROR_CANCELLED, ERROR_PACKET_TOO_LARGE, ERROR_ROW_COUNT, ERROR_URL, EnumLabels,
+    FLAVOR_MARIADB, Reduction, ResultSetEncoder, RowChunkEncoder, StatementOutput, begin_result,
+    compress_response, contains_keyword, encode_ddl_chunk, finish_result, in_list_column_type,
+    is_connection_lost, is_packet_too_large, is_select_statement, is_write_statement,
+    latest_deadlock, null_in_not_null_column, ok_frame, parse_enum_labels, parse_name_list,
+    parse_sort_list, parse_statement_list, parse_type_hints, parse_version, ptr_to_string,
+    ptr_to_vec, quote_identifier, report_error, send_categorized_error, send_error,
+    send_param_error, send_response, send_response_into, serialize_column_range,
+    serialize_grouped_result, serialize_multi_result, serialize_page, serialize_result,
+    serialize_statement_results, server_flavor, stable_hash, statement_size, trim_statement,
+    try_parse_params_list, with_server_timeout, write_csv_header, write_csv_row, write_result_set,
+    write_text_row, write_value,
 };
+#[cfg(feature = "binlog")]
+use crate::utils::{ERROR_SERVER, encode_binlog_event};
+use mysql_async::consts::{ColumnFlags, StatusFlags};
 use mysql_async::prelude::*;
-use mysql_async::{Opts, Params, Pool};
-use std::ffi::CStr;
-use std::os::raw::{c_char, c_int, c_longlong, c_uchar};
-use std::sync::Arc;
+use mysql_async::{
+    ChangeUserOpts, Column, Conn, Opts, OptsBuilder, Params, PoolConstraints, QueryResult, Row,
+    SslOpts, Statement,
+};
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int, c_longlong, c_uchar, c_uint, c_ulonglong};
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, OnceLock, PoisonError};
+use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
 
 macro_rules! parse_params {
-    ($params_owned:expr) => {{
-        let params_parsed = parse_params_list($params_owned.as_ptr(), $params_owned.len() as c_int);
+    ($params_owned:expr, $cb:expr, $req_id:expr) => {{
+        let params_parsed =
+            match try_parse_params_list($params_owned.as_ptr(), $params_owned.len() as c_int) {
+                Ok(params) => params,
+                Err(err) => {
+                    send_param_error(&$cb, $req_id, &err);
+                    return;
+                }
+            };
         if params_parsed.is_empty() {
             Params::Empty
         } else {
@@ -24,8 +129,37 @@ macro_rules! parse_params {
     }};
 }
 
+const READ_ONLY_ERROR: &str = "Write statements are not allowed on a read-only pool";
+
+/// Like `unwrap_or_return!` for operations on a `MysqlConnection`'s connection:
+/// a connection-level failure also empties `$slot`, so later calls report
+/// "Connection is closed" instead of failing on the dead connection.
+macro_rules! unwrap_or_close {
+    ($expr:expr, $slot:ident, $cb:expr, $id:expr) => {
+        match $expr {
+            Ok(val) => val,
+            Err(e) => {
+                if is_connection_lost(&e) {
+                    *$slot = None;
+                }
+                send_categorized_error(&$cb, $id, &e);
+                return;
+            }
+        }
+    };
+}
+
+macro_rules! reject_if_read_only {
+    ($read_only:expr, $query:expr, $cb:expr, $req_id:expr) => {
+        if $read_only && is_write_statement(&$query) {
+            send_error(&$cb, $req_id, READ_ONLY_ERROR);
+            return;
+        }
+    };
+}
+
 macro_rules! execute_batch {
-    ($conn:expr, $table_str:expr, $columns_str:expr, $data:expr, $req_id:expr, $cb:expr, $on_duplicate:expr) => {
+    ($conn:expr, $table_str:expr, $columns_str:expr, $data:expr, $req_id:expr, $cb:expr, $opts:expr) => {
         let mut reader = crate::utils::BinaryReader::new(&$data);
         let num_rows =
             unwrap_or_return!(reader.read_u32(), $cb, $req_id, "Failed to read row count") as usize;
@@ -39,14 +173,75 @@ macro_rules! execute_batch {
             send_error(&$cb, $req_id, "No columns specified");
             return;
         }
+        if !$opts.type_hints.is_empty() && $opts.type_hints.len() != num_cols {
+            send_error(&$cb, $req_id, "Type hint count does not match column count");
+            return;
+        }
         let total_values = num_rows * num_cols;
         let mut all_values = Vec::with_capacity(total_values);
-        for _ in 0..total_values {
-            all_values.push(crate::utils::parse_value(&mut reader));
+        for index in 0..total_values {
+            match crate::utils::try_parse_value(&mut reader) {
+                Ok(mysql_async::Value::Bytes(bytes)) => {
+                    all_values.push(match $opts.type_hints.get(index % num_cols) {
+                        Some(Some(hint)) => crate::utils::parse_typed_value(*hint, bytes),
+                        _ => mysql_async::Value::Bytes(bytes),
+                    })
+                }
+                Ok(value) => all_values.push(value),
+                Err(message) => {
+                    let err = crate::utils::ParamError {
+                        index: index as u32,
+                        message,
+                    };
+                    crate::utils::send_param_error(&$cb, $req_id, &err);
+                    return;
+                }
+            }
+        }
+        let mut duplicates = 0;
+        if !$opts.dedup_key.is_empty() {
+            let mut key = Vec::with_capacity($opts.dedup_key.len());
+            for name in &$opts.dedup_key {
+                match column_names.iter().position(|c| c.trim() == name.trim()) {
+                    Some(index) => key.push(index),
+                    None => {
+                        send_error(&$cb, $req_id, &format!("Unknown dedup column: {}", name));
+                        return;
+                    }
+                }
+            }
+            (all_values, duplicates) = crate::utils::dedup_batch_rows(all_values, num_cols, &key);
+        }
+        let mut defaulted = Vec::with_capacity($opts.default_on_null.len());
+        for name in &$opts.default_on_null {
+            match column_names.iter().position(|c| c.trim() == name.trim()) {
+                Some(index) => defaulted.push(index),
+                None => {
+                    send_error(&$cb, $req_id, &format!("Unknown default column: {}", name));
+                    return;
+                }
+            }
+        }
+        // Rows leaving a column to its default need their own column list, so
+        // the batch is split into runs of ordinary rows, each followed by the
+        // row that ended it; inserting them in turn keeps the input order.
+        let mut segments = Vec::new();
+        if defaulted.is_empty() {
+            segments.push((all_values, None));
+        } else {
+            let mut run = Vec::new();
+            for row in all_values.chunks(num_cols) {
+                if defaulted.iter().any(|&i| row[i] == mysql_async::Value::NULL) {
+                    segments.push((std::mem::take(&mut run), Some(row.to_vec())));
+                } else {
+                    run.extend_from_slice(row);
+                }
+            }
+            segments.push((run, None));
         }
 
         let base_placeholders = vec!["?"; num_cols].join(",");
-        let update_clause = if $on_duplicate {
+        let update_clause = if $opts.on_duplicate {
             let updates: Vec<String> = column_names
                 .iter()
                 .map(|c| format!("{} = VALUES({})", c, c))
@@ -56,344 +251,4014 @@ macro_rules! execute_batch {
             String::new()
         };
 
+        // Reports a failed statement of about `size` bytes, naming the size
+        // when it exceeded `max_allowed_packet`.
+        let batch_error = |e: mysql_async::Error, size: usize| {
+            let message = if crate::utils::is_packet_too_large(&e) {
+                format!(
+                    "Batch insert error: {}; the statement was about {} bytes, retry with smaller chunks",
+                    e, size
+                )
+            } else {
+                format!("Batch insert error: {}", e)
+            };
+            crate::utils::report_error(
+                &$cb,
+                $req_id,
+                crate::utils::ErrorCategory::category(&e),
+                crate::utils::ErrorCategory::code(&e),
+                &message,
+            );
+        };
+        let row_query = format!(
+            "INSERT INTO {} ({}) VALUES ({}){}",
+            $table_str, $columns_str, base_placeholders, update_clause
+        );
+        let row_statement = if $opts.per_row {
+            match timed($opts.step_timeout, $conn.prep(row_query.as_str())).await {
+                Ok(statement) => Some(statement),
+                Err(e) => {
+                    batch_error(e, row_query.len());
+                    return;
+                }
+            }
+        } else {
+            None
+        };
+
         let rows_per_chunk = (60000 / num_cols).max(1);
-        let chunks = all_values.chunks(rows_per_chunk * num_cols);
         let mut total_affected = 0;
         let mut last_id = 0;
+        let mut record = |chunk_affected: u64, current_id: u64| {
+            total_affected += chunk_affected;
+            if current_id > 0 {
+                last_id = current_id;
+            }
+            if $opts.stream {
+                send_response(
+                    &$cb,
+                    $req_id,
+                    crate::utils::encode_insert_chunk(current_id, chunk_affected),
+                );
+            }
+        };
 
-        for chunk in chunks {
-            let params = Params::Positional(chunk.to_vec());
-            let current_chunk_size = chunk.len() / num_cols;
-            let chunk_placeholders: Vec<String> =
-                std::iter::repeat(format!("({})", base_placeholders))
-                    .take(current_chunk_size)
-                    .collect();
-            let chunk_query = format!(
-                "INSERT INTO {} ({}) VALUES {}{}",
-                $table_str,
-                $columns_str,
-                chunk_placeholders.join(","),
-                update_clause
-            );
-            match $conn.exec_drop(chunk_query, params).await {
-                Ok(_) => {
-                    total_affected += $conn.affected_rows();
-                    let current_id = $conn.last_insert_id().unwrap_or(0);
-                    if current_id > 0 {
-                        last_id = current_id;
+        for (run, default_row) in segments {
+            for chunk in run.chunks(rows_per_chunk * num_cols) {
+                let executed = match &row_statement {
+                    Some(statement) => {
+                        // Affected rows summed over the chunk, and the first generated id.
+                        let mut executed = Ok((0, 0));
+                        for row in chunk.chunks(num_cols) {
+                            if let Err(e) = timed($opts.step_timeout, $conn.exec_drop(statement, row.to_vec())).await {
+                                executed = Err((e, crate::utils::statement_size(&row_query, row)));
+                                break;
+                            }
+                            if let Ok((affected, first_id)) = &mut executed {
+                                *affected += $conn.affected_rows();
+                                if *first_id == 0 {
+                                    *first_id = $conn.last_insert_id().unwrap_or(0);
+                                }
+                            }
+                        }
+                        executed
+                    }
+                    None => {
+                        let params = Params::Positional(chunk.to_vec());
+                        let current_chunk_size = chunk.len() / num_cols;
+                        let chunk_placeholders: Vec<String> =
+                            std::iter::repeat(format!("({})", base_placeholders))
+                                .take(current_chunk_size)
+                                .collect();
+                        let chunk_query = format!(
+                            "INSERT INTO {} ({}) VALUES {}{}",
+                            $table_str,
+                            $columns_str,
+                            chunk_placeholders.join(","),
+                            update_clause
+                        );
+                        let size = crate::utils::statement_size(&chunk_query, chunk);
+                        match timed($opts.step_timeout, $conn.exec_drop(chunk_query, params)).await {
+                            Ok(()) => Ok(($conn.affected_rows(), $conn.last_insert_id().unwrap_or(0))),
+                            Err(e) => Err((e, size)),
+                        }
+                    }
+                };
+                match executed {
+                    Ok((chunk_affected, current_id)) => record(chunk_affected, current_id),
+                    Err((e, size)) => {
+                        batch_error(e, size);
+                        return;
                     }
                 }
-                Err(e) => {
-                    send_error(&$cb, $req_id, &format!("Batch insert error: {}", e));
-                    return;
+            }
+            if let Some(row) = default_row {
+                let (names, values): (Vec<&str>, Vec<mysql_async::Value>) = column_names
+                    .iter()
+                    .zip(row)
+                    .enumerate()
+                    .filter(|(index, (_, value))| {
+                        !defaulted.contains(index) || *value != mysql_async::Value::NULL
+                    })
+                    .map(|(_, (name, value))| (name.trim(), value))
+                    .unzip();
+                let row_update_clause = if $opts.on_duplicate && !names.is_empty() {
+                    let updates: Vec<String> = names
+                        .iter()
+                        .map(|c| format!("{} = VALUES({})", c, c))
+                        .collect();
+                    format!(" ON DUPLICATE KEY UPDATE {}", updates.join(", "))
+                } else {
+                    String::new()
+                };
+                let query = format!(
+                    "INSERT INTO {} ({}) VALUES ({}){}",
+                    $table_str,
+                    names.join(","),
+                    vec!["?"; names.len()].join(","),
+                    row_update_clause
+                );
+                let size = crate::utils::statement_size(&query, &values);
+                match timed($opts.step_timeout, $conn.exec_drop(query, values)).await {
+                    Ok(()) => record($conn.affected_rows(), $conn.last_insert_id().unwrap_or(0)),
+                    Err(e) => {
+                        batch_error(e, size);
+                        return;
+                    }
                 }
             }
         }
-        send_response(
-            &$cb,
-            $req_id,
-            serialize_result(Vec::new(), total_affected, last_id),
-        );
+        let mut buf = serialize_result(Vec::new(), total_affected, last_id);
+        if !$opts.dedup_key.is_empty() {
+            buf.write_u64(duplicates);
+        }
+        send_response(&$cb, $req_id, buf);
+    };
+}
+
+/// Encodes a query result as a result payload, dropping each row as soon as it
+/// is encoded instead of buffering the whole result set first.
+///
+/// Only the first result set is encoded; any further ones are drained. The
+/// header is completed with [`finish_result`] once the connection's final OK
+/// packet is known.
+async fn encode_query_result<P: Protocol>(
+    result: QueryResult<'_, '_, P>,
+) -> mysql_async::Result<Vec<u8>> {
+    let mut buf = begin_result();
+    let mut encoder = ResultSetEncoder::new(&mut buf);
+    result.for_each_and_drop(|row| encoder.push(row)).await?;
+    encoder.finish();
+    Ok(buf)
+}
+
+/// Looks up the member labels of the ENUM columns in `cols`.
+///
+/// Result set metadata only flags ENUM columns, so the definitions are read
+/// from `information_schema`. Columns that are not backed by a table column,
+/// such as ENUM expressions in derived tables, get no labels.
+async fn fetch_enum_labels(conn: &mut Conn, cols: &[Column]) -> mysql_async::Result<EnumLabels> {
+    let mut labels = Vec::with_capacity(cols.len());
+    for col in cols {
+        if !col.flags().contains(ColumnFlags::ENUM_FLAG) || col.org_table_ref().is_empty() {
+            labels.push(None);
+            continue;
+        }
+        let column_type: Option<String> = conn
+            .exec_first(
+                "SELECT COLUMN_TYPE FROM information_schema.COLUMNS \
+                 WHERE TABLE_SCHEMA = ? AND TABLE_NAME = ? AND COLUMN_NAME = ?",
+                (col.schema_ref(), col.org_table_ref(), col.org_name_ref()),
+            )
+            .await?;
+        labels.push(column_type.as_deref().and_then(parse_enum_labels));
+    }
+    Ok(labels)
+}
+
+/// Reports a query to the slow query logger if it ran for at least its threshold.
+///
+/// The log frame is an OK frame carrying the request id of the query. It holds
+/// the elapsed time in microseconds, the query text and a result set block with
+/// the `EXPLAIN` output, empty unless plans are enabled and the query is a
+/// single SELECT. The plan is fetched on the query's connection after its
+/// response was sent; it is not itself timed or logged.
+async fn log_slow_query(
+    log: &SlowQueryLog,
+    conn: &mut Conn,
+    req_id: c_longlong,
+    query: &str,
+    params: Params,
+    elapsed: Duration,
+) {
+    if elapsed < log.threshold {
+        return;
+    }
+    let plan = if log.explain && is_select_statement(query) {
+        conn.exec(format!("EXPLAIN {}", query), params)
+            .await
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+    let mut buf = ok_frame(12 + query.len());
+    buf.write_u64(elapsed.as_micros() as u64);
+    buf.write_blob(query.as_bytes());
+    write_result_set(&mut buf, plan);
+    send_response(&log.callback, req_id, buf);
+}
+
+/// Spawns `task` on the runtime, abandoning it with an error response once it
+/// has run for longer than `timeout`.
+///
+/// Dropping the task closes or resets whatever connection it was using, but a
+/// statement that already reached the server may still run to completion there.
+fn spawn_with_timeout<F>(
+    timeout: Option<Duration>,
+    cb: CallbackWrapper,
+    req_id: c_longlong,
+    task: F,
+) where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let runtime = unwrap_or_return!(get_runtime(), cb, req_id);
+    let Some(limit) = timeout else {
+        runtime.spawn(task);
+        return;
+    };
+    runtime.spawn(async move {
+        // The task keeps its pool handle, and so the in-flight count, until
+        // the timeout error has been delivered.
+        let mut task = std::pin::pin!(task);
+        if tokio::time::timeout(limit, task.as_mut()).await.is_err() {
+            let msg = format!("Operation timed out after {} ms", limit.as_millis());
+            send_error(&cb, req_id, &msg);
+        }
+    });
+}
+
+/// Converts a millisecond timeout from the FFI boundary, where 0 disables it.
+fn timeout_from_ms(ms: c_uint) -> Option<Duration> {
+    (ms > 0).then(|| Duration::from_millis(ms as u64))
+}
+
+/// Bounds one step of a streaming operation, whose total duration is unbounded.
+async fn timed<T>(
+    timeout: Option<Duration>,
+    step: impl Future<Output = mysql_async::Result<T>>,
+) -> mysql_async::Result<T> {
+    let Some(limit) = timeout else {
+        return step.await;
+    };
+    tokio::time::timeout(limit, step).await.unwrap_or_else(|_| {
+        let msg = format!("Operation timed out after {} ms", limit.as_millis());
+        Err(mysql_async::Error::Other(msg.into()))
+    })
+}
+
+fn parse_url_opts(url: *const c_char) -> Option<Opts> {
+    if url.is_null() {
+        return None;
+    }
+    let url_str = unsafe { CStr::from_ptr(url) }.to_str().ok()?;
+    check_url(url_str).ok()
+}
+
+/// Parses a connection URL, describing what is wrong with it on failure.
+///
+/// The scheme, host and port are checked up front because the driver reports
+/// most problems with them as a generic invalid URL.
+fn check_url(url: &str) -> Result<Opts, String> {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return Err("URL must start with `mysql://`".to_string());
+    };
+    if scheme != "mysql" {
+        return Err(format!(
+            "Unsupported URL scheme `{}`; expected `mysql`",
+            scheme
+        ));
+    }
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host_port = authority.rsplit_once('@').map_or(authority, |(_, hp)| hp);
+    let (host, port) = match host_port.strip_prefix('[') {
+        Some(ipv6) => match ipv6.split_once(']') {
+            Some((host, tail)) => (host, tail.strip_prefix(':')),
+            None => return Err("Unterminated IPv6 address in URL host".to_string()),
+        },
+        None => match host_port.split_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (host_port, None),
+        },
+    };
+    if host.is_empty() {
+        return Err("URL is missing a host".to_string());
+    }
+    if let Some(port) = port
+        && !port.is_empty()
+        && !matches!(port.parse::<u16>(), Ok(1..))
+    {
+        return Err(format!(
+            "Invalid port `{}`; expected a number between 1 and 65535",
+            port
+        ));
+    }
+    Opts::from_url(url).map_err(|e| e.to_string())
+}
+
+/// Returns NULL for a valid URL, else the problem, released with `mysql_free_string`.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_validate_url(url: *const c_char) -> *mut c_char {
+    if url.is_null() {
+        return into_c_string("URL is NULL".to_string());
+    }
+    let result = match unsafe { CStr::from_ptr(url) }.to_str() {
+        Ok(url_str) => check_url(url_str),
+        Err(_) => Err("URL is not valid UTF-8".to_string()),
     };
+    match result {
+        Ok(_) => std::ptr::null_mut(),
+        Err(msg) => into_c_string(msg),
+    }
+}
+
+/// Hands a string to the caller, who releases it with `mysql_free_string`.
+fn into_c_string(msg: String) -> *mut c_char {
+    CString::new(msg).unwrap_or_default().into_raw()
+}
+
+/// Releases a string returned by this library, e.g. by `mysql_validate_url`.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_free_string(ptr: *mut c_char) {
+    if !ptr.is_null() {
+        unsafe {
+            let _ = CString::from_raw(ptr);
+        }
+    }
 }
 
 #[unsafe(no_mangle)]
 pub extern "C" fn mysql_pool_create(url: *const c_char) -> *mut MysqlPool {
-    if url.is_null() {
+    let opts = match parse_url_opts(url) {
+        Some(opts) => opts,
+        None => return std::ptr::null_mut(),
+    };
+    Box::into_raw(Box::new(MysqlPool::new(opts)))
+}
+
+/// Shorthand for `mysql_pool_create_with_config` with the other options left at defaults.
+#[unsafe(no_mangle)]
+pub extern "C" fn mysql_pool_create_with_opts(
+    url: *const c_char,
+    read_only: bool,
+    max_conn_lifetime_ms: c_uint,
+    stmt_cache_size: c_int,
+    connect_retries: c_uint,
+    connect_backoff_ms: c_uint,
+) -> *mut MysqlPool {
+    let config = MysqlPoolConfig {
+        url,
+        max_conn_lifetime_ms,
+        stmt_cache_size,
+        connect_retries,
+        connect_backoff_ms,
+        read_only,
+        ..MysqlPoolConfig::default()
+    };
+    mysql_pool_create_with_config(&config)
+}
+
+/// Creates a pool from a `MysqlPoolConfig`; returns NULL on invalid options.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_create_with_config(config: *const MysqlPoolConfig) -> *mut MysqlPool {
+    if config.is_null() {
         return std::ptr::null_mut();
     }
-    let url_str = match unsafe { CStr::from_ptr(url) }.to_str() {
-        Ok(s) => s,
-        Err(..) => return std::ptr::null_mut(),
+    let Some(full) = read_sized_struct(config, MIN_POOL_CONFIG_SIZE) else {
+        return std::ptr::null_mut();
     };
-    let opts = match Opts::from_url(url_str) {
-        Ok(opts) => opts,
-        Err(..) => return std::ptr::null_mut(),
+    match build_pool(&full) {
+        Some(pool) => Box::into_raw(Box::new(pool)),
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Smallest `MysqlPoolConfig::struct_size` accepted: the size and the URL.
+const MIN_POOL_CONFIG_SIZE: usize =
+    std::mem::offset_of!(MysqlPoolConfig, url) + std::mem::size_of::<*const c_char>();
+
+/// Copies the part of a caller's struct it knows about, as given by the leading
+/// `struct_size`, over the defaults. `None` if the size is out of range.
+fn read_sized_struct<T: Default>(ptr: *const T, min_size: usize) -> Option<T> {
+    let size = unsafe { ptr.cast::<usize>().read_unaligned() };
+    if !(min_size..=std::mem::size_of::<T>()).contains(&size) {
+        return None;
+    }
+    let mut full = T::default();
+    unsafe {
+        std::ptr::copy_nonoverlapping(ptr.cast::<u8>(), (&mut full as *mut T).cast::<u8>(), size);
+    }
+    Some(full)
+}
+
+/// Reads an optional per-call options struct, NULL meaning the defaults.
+fn read_call_options<T: Default>(ptr: *const T) -> Result<T, String> {
+    if ptr.is_null() {
+        return Ok(T::default());
+    }
+    read_sized_struct(ptr, std::mem::size_of::<usize>())
+        .ok_or_else(|| "Unsupported options struct size".to_string())
+}
+
+/// Reads an optional C string, treating NULL and the empty string as absent.
+fn optional_string(ptr: *const c_char) -> Result<Option<String>, String> {
+    if ptr.is_null() {
+        return Ok(None);
+    }
+    ptr_to_string(ptr).map(|s| Some(s).filter(|s| !s.is_empty()))
+}
+
+fn build_pool(config: &MysqlPoolConfig) -> Option<MysqlPool> {
+    let opts = parse_url_opts(config.url)?;
+    let tls_ca_path = optional_string(config.tls_ca_path).ok()?;
+    let charset = optional_string(config.charset).ok()?;
+    let init_sql = optional_string(config.init_sql).ok()?;
+    let isolation_level = optional_string(config.isolation_level).ok()?;
+
+    let mut setup = opts.setup().to_vec();
+    if let Some(charset) = charset {
+        if !is_identifier(&charset, false) {
+            return None;
+        }
+        setup.push(format!("SET NAMES {}", charset));
+    }
+    setup.extend(init_sql);
+    if config.read_only {
+        setup.push("SET SESSION TRANSACTION READ ONLY".to_string());
+    }
+    if let Some(level) = isolation_level {
+        setup.push(format!(
+            "SET SESSION TRANSACTION ISOLATION LEVEL {}",
+            isolation_level_name(&level)?
+        ));
+    }
+    if config.interactive {
+        // The driver offers no way to send CLIENT_INTERACTIVE, whose only
+        // effect is this assignment made by the server at login.
+        setup.push("SET SESSION wait_timeout = @@GLOBAL.interactive_timeout".to_string());
+    }
+
+    let mut pool_opts = opts.pool_opts().clone();
+    if config.min_connections > 0 || config.max_connections > 0 {
+        let max = match config.max_connections {
+            0 => pool_opts.constraints().max(),
+            max => max as usize,
+        };
+        pool_opts =
+            pool_opts.with_constraints(PoolConstraints::new(config.min_connections as usize, max)?);
+    }
+    if config.max_conn_lifetime_ms > 0 {
+        pool_opts = pool_opts.with_abs_conn_ttl(Some(Duration::from_millis(
+            config.max_conn_lifetime_ms as u64,
+        )));
+    }
+    if config.inactive_conn_ttl_ms > 0 {
+        pool_opts = pool_opts.with_inactive_connection_ttl(Duration::from_millis(
+            config.inactive_conn_ttl_ms as u64,
+        ));
+    }
+    let stmt_cache_size = match usize::try_from(config.stmt_cache_size) {
+        Ok(size) => size,
+        Err(_) => opts.stmt_cache_size(),
     };
-    Box::into_raw(Box::new(MysqlPool {
-        pool: Pool::new(opts),
-    }))
+    if stmt_cache_size == 0 {
+        pool_opts = pool_opts.with_reset_connection(true);
+    }
+
+    let mut builder = OptsBuilder::from_opts(opts)
+        .setup(setup)
+        .stmt_cache_size(stmt_cache_size)
+        .pool_opts(pool_opts);
+    if let Some(path) = tls_ca_path {
+        let ssl_opts =
+            SslOpts::default().with_root_certs(vec![std::path::PathBuf::from(path).into()]);
+        builder = builder.ssl_opts(ssl_opts);
+    }
+    let mut pool = MysqlPool::new(builder.into());
+    pool.settings.read_only = config.read_only;
+    pool.settings.connect_retry = ConnectRetry {
+        attempts: config.connect_retries,
+        backoff: Duration::from_millis(config.connect_backoff_ms as u64),
+    };
+    pool.settings.default_timeout = timeout_from_ms(config.default_query_timeout_ms);
+    pool.settings.deadlock_diagnostics = config.deadlock_diagnostics;
+    if config.recent_query_capacity > 0 {
+        pool.settings.recent_queries = Some(Arc::new(RecentQueries::new(
+            config.recent_query_capacity as usize,
+        )));
+    }
+    Some(pool)
 }
 
+/// Replaces the statements run on every connection before it is handed out; NULL clears them.
 #[unsafe(no_mangle)]
-pub extern "C" fn mysql_pool_destroy(pool_ptr: *mut MysqlPool) {
-    if !pool_ptr.is_null() {
-        unsafe {
-            let _ = Box::from_raw(pool_ptr);
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_set_session_init(
+    pool_ptr: *mut MysqlPool,
+    statements: *const c_char,
+) -> bool {
+    if pool_ptr.is_null() {
+        return false;
+    }
+    let statements = if statements.is_null() {
+        String::new()
+    } else {
+        match ptr_to_string(statements) {
+            Ok(s) => s,
+            Err(_) => return false,
         }
+    };
+    let pool_ref = unsafe { &*pool_ptr };
+    let mut setup = pool_ref.opts().setup().to_vec();
+    if !statements.trim().is_empty() {
+        setup.push(statements);
     }
+    let opts = OptsBuilder::from_opts(pool_ref.opts()).setup(setup);
+    pool_ref.replace_pool(opts.into());
+    true
 }
 
+/// Switches new connections to the credentials of `new_url`, once checked-out ones are closed.
 #[unsafe(no_mangle)]
-pub extern "C" fn mysql_pool_query_raw(
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_rotate_credentials(
     pool_ptr: *mut MysqlPool,
-    query: *const c_char,
+    new_url: *const c_char,
     req_id: c_longlong,
     callback: CallbackType,
 ) {
-    let cb = CallbackWrapper(callback);
+    let cb = CallbackWrapper::new(callback);
     if pool_ptr.is_null() {
         send_error(&cb, req_id, "Invalid pointers");
         return;
     }
-    let query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
-    let pool = unsafe { &*pool_ptr }.pool.clone();
-    get_runtime().spawn(async move {
-        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
-        let rows = unwrap_or_return!(conn.query(query_str).await, cb, req_id);
-        send_response(
-            &cb,
-            req_id,
-            serialize_result(
-                rows,
-                conn.affected_rows(),
-                conn.last_insert_id().unwrap_or(0),
-            ),
-        );
+    let url = unwrap_or_return!(ptr_to_string(new_url), cb, req_id);
+    let opts = match check_url(&url) {
+        Ok(opts) => opts,
+        Err(msg) => {
+            report_error(&cb, req_id, ERROR_URL, 0, &msg);
+            return;
+        }
+    };
+    let pool_ref = unsafe { &*pool_ptr };
+    let handle = pool_ref.pool();
+    let previous = pool_ref.rotate_credentials(
+        opts.user().map(str::to_string),
+        opts.pass().map(str::to_string),
+    );
+    spawn_with_timeout(None, cb.clone(), req_id, async move {
+        let _handle = handle;
+        unwrap_or_return!(previous.disconnect().await, cb, req_id);
+        send_response(&cb, req_id, serialize_result(Vec::new(), 0, 0));
     });
 }
 
+/// Creates a pool like `pool_ptr` defaulting to `db_name`; see `MysqlPool::with_database`.
 #[unsafe(no_mangle)]
-pub extern "C" fn mysql_pool_query(
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_with_database(
+    pool_ptr: *mut MysqlPool,
+    db_name: *const c_char,
+) -> *mut MysqlPool {
+    if pool_ptr.is_null() {
+        return std::ptr::null_mut();
+    }
+    let db_name = match ptr_to_string(db_name) {
+        Ok(db_name) if !db_name.is_empty() => db_name,
+        _ => return std::ptr::null_mut(),
+    };
+    let pool = unsafe { &*pool_ptr }.with_database(&db_name);
+    Box::into_raw(Box::new(pool))
+}
+
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_destroy(pool_ptr: *mut MysqlPool) {
+    if !pool_ptr.is_null() {
+        unsafe {
+            let _ = Box::from_raw(pool_ptr);
+        }
+    }
+}
+
+/// Destroys the pool once its in-flight operations finish; `false` if `timeout_ms` elapses.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_destroy_when_idle(
+    pool_ptr: *mut MysqlPool,
+    timeout_ms: c_uint,
+) -> bool {
+    if pool_ptr.is_null() {
+        return true;
+    }
+    let pool_ref = unsafe { &*pool_ptr };
+    if !pool_ref.wait_idle(Duration::from_millis(timeout_ms as u64)) {
+        return false;
+    }
+    mysql_pool_destroy(pool_ptr);
+    true
+}
+
+/// Makes new operations wait for `mysql_pool_resume`, failing once `max_queued` are waiting.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_pause(pool_ptr: *mut MysqlPool, max_queued: c_uint) -> bool {
+    if pool_ptr.is_null() {
+        return false;
+    }
+    unsafe { &*pool_ptr }.pause(max_queued as usize);
+    true
+}
+
+/// Lets the operations queued by `mysql_pool_pause` proceed.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_resume(pool_ptr: *mut MysqlPool) -> bool {
+    if pool_ptr.is_null() {
+        return false;
+    }
+    unsafe { &*pool_ptr }.resume();
+    true
+}
+
+/// Returns the queries recorded under the pool's `recent_query_capacity`, oldest first.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_recent_queries(
     pool_ptr: *mut MysqlPool,
-    query: *const c_char,
-    params_ptr: *const c_uchar,
-    params_len: c_int,
     req_id: c_longlong,
     callback: CallbackType,
 ) {
-    let cb = CallbackWrapper(callback);
+    let cb = CallbackWrapper::new(callback);
     if pool_ptr.is_null() {
         send_error(&cb, req_id, "Invalid pointers");
         return;
     }
-    let query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
-    let params_owned = ptr_to_vec(params_ptr, params_len);
-    let pool = unsafe { &*pool_ptr }.pool.clone();
-    get_runtime().spawn(async move {
-        let params_pos = parse_params!(params_owned);
-        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
-        let rows = unwrap_or_return!(conn.exec(query_str, params_pos).await, cb, req_id);
-        send_response(
-            &cb,
-            req_id,
-            serialize_result(
-                rows,
-                conn.affected_rows(),
-                conn.last_insert_id().unwrap_or(0),
-            ),
-        );
-    });
+    let mut buf = serialize_result(Vec::new(), 0, 0);
+    match &unsafe { &*pool_ptr }.recent_queries {
+        Some(recent) => recent.with_entries(|entries| {
+            buf.write_u32(entries.len() as u32);
+            for entry in entries {
+                buf.write_u64(entry.started_at_us);
+                buf.write_u64(entry.duration.as_micros() as u64);
+                buf.write_blob(entry.query.as_bytes());
+            }
+        }),
+        None => buf.write_u32(0),
+    }
+    send_response(&cb, req_id, buf);
+}
+
+/// TLS mode reported by `mysql_pool_describe_opts` for plain connections.
+const TLS_DISABLED: u8 = 0;
+/// TLS with the certificate chain and host name verified.
+const TLS_VERIFY_FULL: u8 = 1;
+/// TLS with the certificate chain verified but not the host name.
+const TLS_VERIFY_CA: u8 = 2;
+/// TLS accepting any certificate.
+const TLS_UNVERIFIED: u8 = 3;
+
+/// Appends a presence byte and, when present, the value as a u64.
+fn write_optional_u64(buf: &mut Vec<u8>, value: Option<u64>) {
+    match value {
+        Some(value) => {
+            buf.write_u8(1);
+            buf.write_u64(value);
+        }
+        None => buf.write_u8(0),
+    }
 }
 
+/// Describes the server, account and limits the pool currently connects with.
 #[unsafe(no_mangle)]
-pub extern "C" fn mysql_pool_prepare(
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_describe_opts(
     pool_ptr: *mut MysqlPool,
-    query: *const c_char,
     req_id: c_longlong,
     callback: CallbackType,
 ) {
-    let cb = CallbackWrapper(callback);
+    let cb = CallbackWrapper::new(callback);
     if pool_ptr.is_null() {
         send_error(&cb, req_id, "Invalid pointers");
         return;
     }
-    let query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
-    let pool = unsafe { &*pool_ptr }.pool.clone();
-    get_runtime().spawn(async move {
-        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
-        let stmt = unwrap_or_return!(conn.prep(query_str).await, cb, req_id);
-        let ptr = Box::into_raw(Box::new(MysqlPreparedStatement {
-            conn: Arc::new(Mutex::new(Some(conn))),
-            stmt,
-        }));
-        let mut buf = Vec::new();
-        buf.write_u8(1);
-        buf.write_u64(ptr as u64);
-        buf.write_u64(0);
-        buf.write_u32(0);
-        buf.write_u32(0);
-        send_response(&cb, req_id, buf);
+    let pool_ref = unsafe { &*pool_ptr };
+    let opts = pool_ref.opts();
+    let mut buf = serialize_result(Vec::new(), 0, 0);
+    buf.write_blob(opts.ip_or_hostname().as_bytes());
+    buf.write_u16(opts.tcp_port());
+    buf.write_blob(opts.socket().unwrap_or_default().as_bytes());
+    buf.write_blob(opts.user().unwrap_or_default().as_bytes());
+    buf.write_u8(opts.pass().is_some_and(|pass| !pass.is_empty()) as u8);
+    match opts.db_name() {
+        Some(db) => {
+            buf.write_u8(1);
+            buf.write_blob(db.as_bytes());
+        }
+        None => buf.write_u8(0),
+    }
+    let pool_opts = opts.pool_opts();
+    buf.write_u32(pool_opts.constraints().min() as u32);
+    buf.write_u32(pool_opts.constraints().max() as u32);
+    buf.write_u64(pool_opts.inactive_connection_ttl().as_millis() as u64);
+    write_optional_u64(&mut buf, opts.conn_ttl().map(|ttl| ttl.as_millis() as u64));
+    write_optional_u64(&mut buf, opts.wait_timeout().map(|secs| secs as u64));
+    write_optional_u64(
+        &mut buf,
+        pool_ref
+            .default_timeout
+            .map(|timeout| timeout.as_millis() as u64),
+    );
+    buf.write_u8(match opts.ssl_opts() {
+        None => TLS_DISABLED,
+        Some(ssl) if ssl.accept_invalid_certs() => TLS_UNVERIFIED,
+        Some(ssl) if ssl.skip_domain_validation() => TLS_VERIFY_CA,
+        Some(_) => TLS_VERIFY_FULL,
     });
+    send_response(&cb, req_id, buf);
 }
 
+/// Makes `mysql_pool_query` fail when a column flagged `NOT NULL` holds NULL.
 #[unsafe(no_mangle)]
-pub extern "C" fn mysql_pool_begin_transaction(
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_set_strict_nullability(
     pool_ptr: *mut MysqlPool,
-    req_id: c_longlong,
-    callback: CallbackType,
-) {
-    let cb = CallbackWrapper(callback);
+    enabled: bool,
+) -> bool {
     if pool_ptr.is_null() {
-        send_error(&cb, req_id, "Invalid pointers");
-        return;
+        return false;
     }
-    let pool = unsafe { &*pool_ptr }.pool.clone();
-    get_runtime().spawn(async move {
-        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
-        unwrap_or_return!(conn.query_drop("START TRANSACTION").await, cb, req_id);
+    unsafe { &*pool_ptr }
+        .strict_nullability
+        .store(enabled, Ordering::Relaxed);
+    true
+}
 
-        let ptr = Box::into_raw(Box::new(MysqlConnection {
+/// Makes `mysql_pool_query` and `mysql_pool_query_raw` append their timings.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_set_timing(pool_ptr: *mut MysqlPool, enabled: bool) -> bool {
+    if pool_ptr.is_null() {
+        return false;
+    }
+    unsafe { &*pool_ptr }
+        .report_timing
+        .store(enabled, Ordering::Relaxed);
+    true
+}
+
+/// Reports queries running at least `threshold_ms` to `callback`; 0 removes the logger.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_set_slow_query_log(
+    pool_ptr: *mut MysqlPool,
+    threshold_ms: c_uint,
+    explain: bool,
+    callback: CallbackType,
+) -> bool {
+    if pool_ptr.is_null() {
+        return false;
+    }
+    let log = (threshold_ms > 0).then(|| SlowQueryLog {
+        threshold: Duration::from_millis(threshold_ms as u64),
+        explain,
+        callback: CallbackWrapper::new(callback),
+    });
+    unsafe { &*pool_ptr }.set_slow_query_log(log);
+    true
+}
+
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_query_raw(
+    pool_ptr: *mut MysqlPool,
+    query: *const c_char,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
+    let pool_ref = unsafe { &*pool_ptr };
+    reject_if_read_only!(pool_ref.read_only, query_str, cb, req_id);
+    let pool = pool_ref.pool();
+    let slow_log = pool_ref.slow_query_log();
+    let recent = pool_ref.recent_queries.clone();
+    let report_timing = pool_ref.report_timing.load(Ordering::Relaxed);
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let acquiring = Instant::now();
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        let started = Instant::now();
+        let result = unwrap_or_return!(conn.query_iter(query_str.as_str()).await, cb, req_id);
+        let mut buf = unwrap_or_return!(encode_query_result(result).await, cb, req_id);
+        let elapsed = started.elapsed();
+        if let Some(recent) = recent {
+            recent.record(started, &query_str);
+        }
+        finish_result(
+            &mut buf,
+            conn.affected_rows(),
+            conn.last_insert_id().unwrap_or(0),
+        );
+        if report_timing {
+            buf.write_u64(elapsed.as_micros() as u64);
+            buf.write_u64(acquiring.elapsed().as_micros() as u64);
+        }
+        send_response(&cb, req_id, buf);
+        if let Some(log) = slow_log {
+            log_slow_query(&log, &mut conn, req_id, &query_str, Params::Empty, elapsed).await;
+        }
+    });
+}
+
+/// Executes a parameterized query with the per-call `options`, NULL for the defaults.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_query(
+    pool_ptr: *mut MysqlPool,
+    query: *const c_char,
+    params_ptr: *const c_uchar,
+    params_len: c_int,
+    options: *const MysqlQueryOptions,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let MysqlQueryOptions {
+        server_timeout_ms,
+        compress,
+        enum_ordinals,
+        type_names,
+        ..
+    } = unwrap_or_return!(read_call_options(options), cb, req_id);
+    let mut query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
+    let params_owned = ptr_to_vec(params_ptr, params_len);
+    let pool_ref = unsafe { &*pool_ptr };
+    reject_if_read_only!(pool_ref.read_only, query_str, cb, req_id);
+    let timeout = timeout_from_ms(server_timeout_ms).or(pool_ref.default_timeout);
+    let flavor_cache = pool_ref.flavor.clone();
+    let pool = pool_ref.pool();
+    let slow_log = pool_ref.slow_query_log();
+    let recent = pool_ref.recent_queries.clone();
+    let deadlock_diagnostics = pool_ref.deadlock_diagnostics;
+    let strict_nullability = pool_ref.strict_nullability.load(Ordering::Relaxed);
+    let report_timing = pool_ref.report_timing.load(Ordering::Relaxed);
+    spawn_with_timeout(timeout, cb.clone(), req_id, async move {
+        let params_pos = parse_params!(params_owned, cb, req_id);
+        let explain_params = slow_log
+            .as_ref()
+            .filter(|log| log.explain)
+            .map(|_| params_pos.clone());
+        let acquiring = Instant::now();
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        if server_timeout_ms > 0 {
+            let flavor =
+                unwrap_or_return!(detect_flavor(&flavor_cache, &mut conn).await, cb, req_id);
+            query_str = unwrap_or_return!(
+                with_server_timeout(&query_str, flavor, server_timeout_ms),
+                cb,
+                req_id,
+                "server_timeout_ms is only supported for SELECT statements on MySQL"
+            );
+        }
+        let size = match &params_pos {
+            Params::Positional(values) => statement_size(&query_str, values),
+            _ => query_str.len(),
+        };
+        let started = Instant::now();
+        let result = match conn.exec_iter(query_str.as_str(), params_pos).await {
+            Err(e) if is_packet_too_large(&e) => {
+                report_error(
+                    &cb,
+                    req_id,
+                    ERROR_PACKET_TOO_LARGE,
+                    crate::utils::ErrorCategory::code(&e),
+                    &format!("{}; the statement was about {} bytes", e, size),
+                );
+                return;
+            }
+            Err(e) => Err(with_deadlock_report(&mut conn, e, deadlock_diagnostics).await),
+            result => result,
+        };
+        let result = unwrap_or_return!(result, cb, req_id);
+        let mut violation = None;
+        let mut row_index = 0u64;
+        let mut check_row = |row: &Row| {
+            if strict_nullability
+                && violation.is_none()
+                && let Some(col) = null_in_not_null_column(row)
+            {
+                violation = Some(format!(
+                    "Column {} is declared NOT NULL but holds NULL in row {}",
+                    col.name_str(),
+                    row_index
+                ));
+            }
+            row_index += 1;
+        };
+        let (mut buf, elapsed) = if enum_ordinals {
+            // The labels are looked up on the same connection, so the rows
+            // have to be read first.
+            let rows: Vec<Row> = unwrap_or_return!(result.collect_and_drop().await, cb, req_id);
+            let elapsed = started.elapsed();
+            let (affected_rows, last_insert_id) =
+                (conn.affected_rows(), conn.last_insert_id().unwrap_or(0));
+            let labels = match rows.first() {
+                Some(row) => unwrap_or_return!(
+                    fetch_enum_labels(&mut conn, row.columns_ref()).await,
+                    cb,
+                    req_id
+                ),
+                None => Vec::new(),
+            };
+            let mut buf = begin_result();
+            let mut encoder = ResultSetEncoder::new(&mut buf)
+                .with_enum_ordinals(labels)
+                .with_type_names(type_names);
+            for row in rows {
+                check_row(&row);
+                encoder.push(row);
+            }
+            encoder.finish();
+            finish_result(&mut buf, affected_rows, last_insert_id);
+            (buf, elapsed)
+        } else {
+            let mut buf = begin_result();
+            let mut encoder = ResultSetEncoder::new(&mut buf).with_type_names(type_names);
+            unwrap_or_return!(
+                result
+                    .for_each_and_drop(|row| {
+                        check_row(&row);
+                        encoder.push(row)
+                    })
+                    .await,
+                cb,
+                req_id
+            );
+            encoder.finish();
+            let elapsed = started.elapsed();
+            finish_result(
+                &mut buf,
+                conn.affected_rows(),
+                conn.last_insert_id().unwrap_or(0),
+            );
+            (buf, elapsed)
+        };
+        if let Some(recent) = recent {
+            recent.record(started, &query_str);
+        }
+        if let Some(msg) = violation {
+            send_error(&cb, req_id, &msg);
+            return;
+        }
+        if report_timing {
+            buf.write_u64(elapsed.as_micros() as u64);
+            buf.write_u64(acquiring.elapsed().as_micros() as u64);
+        }
+        if compress {
+            buf = compress_response(buf);
+        }
+        send_response(&cb, req_id, buf);
+        if let Some(log) = slow_log {
+            let params = explain_params.unwrap_or(Params::Empty);
+            log_slow_query(&log, &mut conn, req_id, &query_str, params, elapsed).await;
+        }
+    });
+}
+
+/// Creates a token, released with `mysql_cancel_token_destroy`.
+#[unsafe(no_mangle)]
+pub extern "C" fn mysql_cancel_token_create() -> *mut MysqlCancelToken {
+    Box::into_raw(Box::new(MysqlCancelToken(Arc::default())))
+}
+
+/// Cancels every query the token was passed to, now and later.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_cancel_token_cancel(token_ptr: *mut MysqlCancelToken) {
+    if !token_ptr.is_null() {
+        unsafe { &*token_ptr }.0.cancel();
+    }
+}
+
+/// Releases a token; queries it was passed to keep their own reference.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_cancel_token_destroy(token_ptr: *mut MysqlCancelToken) {
+    if !token_ptr.is_null() {
+        unsafe {
+            let _ = Box::from_raw(token_ptr);
+        }
+    }
+}
+
+/// Executes a parameterized query, killing it on the server once `token` is cancelled.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_query_cancellable(
+    pool_ptr: *mut MysqlPool,
+    query: *const c_char,
+    params_ptr: *const c_uchar,
+    params_len: c_int,
+    token: *mut MysqlCancelToken,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() || token.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
+    let params_owned = ptr_to_vec(params_ptr, params_len);
+    let pool_ref = unsafe { &*pool_ptr };
+    reject_if_read_only!(pool_ref.read_only, query_str, cb, req_id);
+    let timeout = pool_ref.default_timeout;
+    let pool = pool_ref.pool();
+    let deadlock_diagnostics = pool_ref.deadlock_diagnostics;
+    let token = unsafe { &*token }.0.clone();
+    spawn_with_timeout(timeout, cb.clone(), req_id, async move {
+        let params_pos = parse_params!(params_owned, cb, req_id);
+        let cancelled = |cb: &CallbackWrapper| {
+            report_error(cb, req_id, ERROR_CANCELLED, 0, "Query cancelled");
+        };
+        let Some(conn) = token.run(pool.get_conn()).await else {
+            cancelled(&cb);
+            return;
+        };
+        let mut conn = unwrap_or_return!(conn, cb, req_id);
+        let connection_id = conn.id();
+        let run = async {
+            let result = match conn.exec_iter(query_str.as_str(), params_pos).await {
+                Err(e) => {
+                    return Err(with_deadlock_report(&mut conn, e, deadlock_diagnostics).await);
+                }
+                Ok(result) => result,
+            };
+            let mut buf = begin_result();
+            let mut encoder = ResultSetEncoder::new(&mut buf);
+            result.for_each_and_drop(|row| encoder.push(row)).await?;
+            encoder.finish();
+            finish_result(
+                &mut buf,
+                conn.affected_rows(),
+                conn.last_insert_id().unwrap_or(0),
+            );
+            Ok(buf)
+        };
+        match token.run(run).await {
+            Some(Ok(buf)) => send_response(&cb, req_id, buf),
+            Some(Err(e)) => send_categorized_error(&cb, req_id, &e),
+            None => {
+                cancelled(&cb);
+                // The abandoned connection is only reusable once the server
+                // finishes the statement, so stop it there too.
+                if let Ok(mut killer) = pool.get_conn().await {
+                    let _ = killer
+                        .query_drop(format!("KILL QUERY {}", connection_id))
+                        .await;
+                }
+            }
+        }
+    });
+}
+
+/// Runs `query` on `conn` with `database` as the default database, then
+/// switches back to `home`, the pool's default database, so the connection
+/// can be returned to the pool. A connection that cannot be switched back is
+/// closed instead.
+async fn query_in_database(
+    mut conn: Conn,
+    database: &str,
+    home: Option<&str>,
+    query: &str,
+    params: Params,
+) -> mysql_async::Result<StatementOutput> {
+    conn.query_drop(format!("USE {}", quote_identifier(database)))
+        .await?;
+    let result = match conn.exec(query, params).await {
+        Ok(rows) => Ok((
+            rows,
+            conn.affected_rows(),
+            conn.last_insert_id().unwrap_or(0),
+        )),
+        Err(e) => Err(e),
+    };
+    let restored = match home {
+        Some(home) => {
+            conn.query_drop(format!("USE {}", quote_identifier(home)))
+                .await
+        }
+        // Without a default database to return to, only re-authenticating
+        // clears the one selected above.
+        None => conn.change_user(ChangeUserOpts::default()).await,
+    };
+    if restored.is_err() {
+        let _ = conn.disconnect().await;
+    }
+    result
+}
+
+/// Executes a parameterized query concurrently against each database of `databases_ptr`.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_query_fanout(
+    pool_ptr: *mut MysqlPool,
+    databases_ptr: *const c_uchar,
+    databases_len: c_int,
+    query: *const c_char,
+    params_ptr: *const c_uchar,
+    params_len: c_int,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let databases = unwrap_or_return!(
+        parse_name_list(&ptr_to_vec(databases_ptr, databases_len)),
+        cb,
+        req_id
+    );
+    let query_str: Arc<str> = unwrap_or_return!(ptr_to_string(query), cb, req_id).into();
+    let params_owned = ptr_to_vec(params_ptr, params_len);
+    let pool_ref = unsafe { &*pool_ptr };
+    reject_if_read_only!(pool_ref.read_only, query_str, cb, req_id);
+    let home: Option<Arc<str>> = pool_ref.opts().db_name().map(Into::into);
+    let pool = Arc::new(pool_ref.pool());
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let params_pos = parse_params!(params_owned, cb, req_id);
+        let mut pending = tokio::task::JoinSet::new();
+        for (index, database) in databases.iter().cloned().enumerate() {
+            let (pool, home) = (pool.clone(), home.clone());
+            let (query, params) = (query_str.clone(), params_pos.clone());
+            pending.spawn(async move {
+                let result = match pool.get_conn().await {
+                    Ok(conn) => {
+                        query_in_database(conn, &database, home.as_deref(), &query, params).await
+                    }
+                    Err(e) => Err(e),
+                };
+                (index, result.map_err(|e| e.to_string()))
+            });
+        }
+        let mut results: Vec<Result<StatementOutput, String>> =
+            vec![Err("Fan-out task failed".to_string()); databases.len()];
+        while let Some(joined) = pending.join_next().await {
+            if let Ok((index, result)) = joined {
+                results[index] = result;
+            }
+        }
+        send_response(&cb, req_id, serialize_statement_results(results));
+    });
+}
+
+/// Placeholder of `mysql_pool_query_in_temp` replaced by the IN list subquery.
+const IN_LIST_PLACEHOLDER: &str = "{in}";
+/// Rows per INSERT when loading an IN list into its temporary table.
+const IN_LIST_INSERT_ROWS: usize = 1000;
+
+/// Loads `values` into a temporary table and runs `query` with `{in}`
+/// replaced by a subquery over it, dropping the table afterwards.
+async fn query_with_in_table(
+    conn: &mut Conn,
+    query: &str,
+    values: &[mysql_async::Value],
+    params: Params,
+) -> mysql_async::Result<Vec<Row>> {
+    conn.query_drop(format!(
+        "CREATE TEMPORARY TABLE turbo_in_values (v {})",
+        in_list_column_type(values)
+    ))
+    .await?;
+    for chunk in values.chunks(IN_LIST_INSERT_ROWS) {
+        let rows = vec!["(?)"; chunk.len()].join(",");
+        conn.exec_drop(
+            format!("INSERT INTO turbo_in_values (v) VALUES {}", rows),
+            chunk.to_vec(),
+        )
+        .await?;
+    }
+    let query = query.replace(IN_LIST_PLACEHOLDER, "(SELECT v FROM turbo_in_values)");
+    conn.exec(query, params).await
+}
+
+/// Executes a query whose `{in}` token is replaced by a temporary table of `values_ptr`.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_query_in_temp(
+    pool_ptr: *mut MysqlPool,
+    query: *const c_char,
+    values_ptr: *const c_uchar,
+    values_len: c_int,
+    params_ptr: *const c_uchar,
+    params_len: c_int,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
+    // A temporary table can be opened only once per statement.
+    if query_str.matches(IN_LIST_PLACEHOLDER).count() != 1 {
+        send_error(&cb, req_id, "Query must contain {in} exactly once");
+        return;
+    }
+    let values = match try_parse_params_list(values_ptr, values_len) {
+        Ok(values) => values,
+        Err(err) => {
+            send_param_error(&cb, req_id, &err);
+            return;
+        }
+    };
+    let params_owned = ptr_to_vec(params_ptr, params_len);
+    let pool_ref = unsafe { &*pool_ptr };
+    reject_if_read_only!(pool_ref.read_only, query_str, cb, req_id);
+    let pool = pool_ref.pool();
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let params_pos = parse_params!(params_owned, cb, req_id);
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        let result = query_with_in_table(&mut conn, &query_str, &values, params_pos).await;
+        let (affected, last_id) = (conn.affected_rows(), conn.last_insert_id().unwrap_or(0));
+        // A connection still holding the table must not go back to the pool.
+        if conn
+            .query_drop("DROP TEMPORARY TABLE IF EXISTS turbo_in_values")
+            .await
+            .is_err()
+        {
+            let _ = conn.disconnect().await;
+        }
+        let rows = unwrap_or_return!(result, cb, req_id);
+        send_response(&cb, req_id, serialize_result(rows, affected, last_id));
+    });
+}
+
+/// Executes a parameterized query, renaming its columns to the comma-separated aliases.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_query_aliased(
+    pool_ptr: *mut MysqlPool,
+    query: *const c_char,
+    aliases_csv: *const c_char,
+    params_ptr: *const c_uchar,
+    params_len: c_int,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
+    let aliases: Vec<String> = unwrap_or_return!(ptr_to_string(aliases_csv), cb, req_id)
+        .split(',')
+        .map(|alias| alias.trim().to_owned())
+        .collect();
+    let params_owned = ptr_to_vec(params_ptr, params_len);
+    let pool_ref = unsafe { &*pool_ptr };
+    reject_if_read_only!(pool_ref.read_only, query_str, cb, req_id);
+    let pool = pool_ref.pool();
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let params_pos = parse_params!(params_owned, cb, req_id);
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        let result = unwrap_or_return!(
+            conn.exec_iter(query_str.as_str(), params_pos).await,
+            cb,
+            req_id
+        );
+        let column_count = result.columns().map_or(0, |cols| cols.len());
+        if column_count != aliases.len() {
+            send_error(
+                &cb,
+                req_id,
+                &format!(
+                    "Got {} aliases for {} result columns",
+                    aliases.len(),
+                    column_count
+                ),
+            );
+            return;
+        }
+        let mut buf = begin_result();
+        let mut encoder = ResultSetEncoder::new(&mut buf).with_aliases(aliases);
+        unwrap_or_return!(
+            result.for_each_and_drop(|row| encoder.push(row)).await,
+            cb,
+            req_id
+        );
+        encoder.finish();
+        finish_result(
+            &mut buf,
+            conn.affected_rows(),
+            conn.last_insert_id().unwrap_or(0),
+        );
+        send_response(&cb, req_id, buf);
+    });
+}
+
+/// Locates a LOB by row key for `mysql_lob_read`; destroy it before its pool.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_lob_open(
+    pool_ptr: *mut MysqlPool,
+    table: *const c_char,
+    column: *const c_char,
+    key_columns: *const c_char,
+    key_ptr: *const c_uchar,
+    key_len: c_int,
+) -> *mut MysqlLob {
+    if pool_ptr.is_null() {
+        return std::ptr::null_mut();
+    }
+    let (Ok(table), Ok(column), Ok(key_columns)) = (
+        ptr_to_string(table),
+        ptr_to_string(column),
+        optional_column_list(key_columns),
+    ) else {
+        return std::ptr::null_mut();
+    };
+    let Ok(key) = try_parse_params_list(key_ptr, key_len) else {
+        return std::ptr::null_mut();
+    };
+    if table.is_empty() || column.is_empty() || key_columns.is_empty() {
+        return std::ptr::null_mut();
+    }
+    if key.len() != key_columns.len() {
+        return std::ptr::null_mut();
+    }
+    let column = quote_identifier(&column);
+    let condition = key_columns
+        .iter()
+        .map(|c| format!("{} = ?", quote_identifier(c.trim())))
+        .collect::<Vec<_>>()
+        .join(" AND ");
+    let query = format!(
+        "SELECT SUBSTRING(CAST({col} AS BINARY), ?, ?), LENGTH({col}) FROM {} WHERE {}",
+        quote_identifier(&table),
+        condition,
+        col = column
+    );
+    Box::into_raw(Box::new(MysqlLob {
+        pool: pool_ptr,
+        query,
+        key,
+    }))
+}
+
+/// Reads up to `len` bytes at `offset` of a LOB, fetching only that range.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_lob_read(
+    lob_ptr: *const MysqlLob,
+    offset: c_ulonglong,
+    len: c_uint,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if lob_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid LOB handle");
+        return;
+    }
+    let lob = unsafe { &*lob_ptr };
+    let pool_ref = unsafe { &*lob.pool };
+    let pool = pool_ref.pool();
+    let query = lob.query.clone();
+    let mut params = vec![
+        mysql_async::Value::UInt(offset.saturating_add(1)),
+        mysql_async::Value::UInt(len as u64),
+    ];
+    params.extend(lob.key.iter().cloned());
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        let row: Option<(Option<Vec<u8>>, Option<u64>)> =
+            unwrap_or_return!(conn.exec_first(query, params).await, cb, req_id);
+        let (range, total) = match row {
+            Some((Some(range), Some(total))) => (range, total),
+            Some(_) => {
+                send_error(&cb, req_id, "LOB value is NULL");
+                return;
+            }
+            None => {
+                send_error(&cb, req_id, "LOB row not found");
+                return;
+            }
+        };
+        let mut buf = serialize_result(Vec::new(), 0, 0);
+        buf.write_u64(total);
+        buf.write_blob(&range);
+        send_response(&cb, req_id, buf);
+    });
+}
+
+/// Releases a handle returned by `mysql_pool_lob_open`.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_lob_destroy(lob_ptr: *mut MysqlLob) {
+    if !lob_ptr.is_null() {
+        unsafe {
+            let _ = Box::from_raw(lob_ptr);
+        }
+    }
+}
+
+/// Shard key routing strategies accepted by `mysql_sharded_pool_create`.
+const SHARD_CONSISTENT_HASH: c_uchar = 0;
+const SHARD_MODULO: c_uchar = 1;
+
+/// Combines pools into a sharded pool that takes ownership of them; NULL on invalid arguments,
+/// including a pool passed twice.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_sharded_pool_create(
+    pools: *const *mut MysqlPool,
+    count: c_int,
+    strategy: c_uchar,
+) -> *mut MysqlShardedPool {
+    if pools.is_null() || count <= 0 {
+        return std::ptr::null_mut();
+    }
+    let ptrs = unsafe { std::slice::from_raw_parts(pools, count as usize) };
+    let mut seen = std::collections::HashSet::with_capacity(ptrs.len());
+    if ptrs.iter().any(|ptr| ptr.is_null() || !seen.insert(*ptr)) {
+        return std::ptr::null_mut();
+    }
+    let strategy = match strategy {
+        SHARD_CONSISTENT_HASH => ShardStrategy::ConsistentHash(HashRing::new(ptrs.len())),
+        SHARD_MODULO => ShardStrategy::Modulo,
+        _ => return std::ptr::null_mut(),
+    };
+    let pools = ptrs
+        .iter()
+        .map(|ptr| unsafe { Box::from_raw(*ptr) })
+        .collect();
+    Box::into_raw(Box::new(MysqlShardedPool { pools, strategy }))
+}
+
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_sharded_pool_destroy(sharded_ptr: *mut MysqlShardedPool) {
+    if !sharded_ptr.is_null() {
+        unsafe {
+            let _ = Box::from_raw(sharded_ptr);
+        }
+    }
+}
+
+/// Returns the index of the shard responsible for the key, or -1.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_sharded_pool_shard_for(
+    sharded_ptr: *mut MysqlShardedPool,
+    key_ptr: *const c_uchar,
+    key_len: c_int,
+) -> c_int {
+    if sharded_ptr.is_null() {
+        return -1;
+    }
+    let sharded = unsafe { &*sharded_ptr };
+    sharded.shard_for(&ptr_to_vec(key_ptr, key_len)) as c_int
+}
+
+/// Returns the pool of shard `index`, owned by the sharded pool, or NULL.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_sharded_pool_get(
+    sharded_ptr: *mut MysqlShardedPool,
+    index: c_int,
+) -> *mut MysqlPool {
+    if sharded_ptr.is_null() || index < 0 {
+        return std::ptr::null_mut();
+    }
+    let sharded = unsafe { &mut *sharded_ptr };
+    match sharded.pools.get_mut(index as usize) {
+        Some(pool) => &mut **pool,
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Executes a parameterized query on the pool of the shard responsible for the key.
+#[unsafe(no_mangle)]
+pub extern "C" fn mysql_sharded_query(
+    sharded_ptr: *mut MysqlShardedPool,
+    key_ptr: *const c_uchar,
+    key_len: c_int,
+    query: *const c_char,
+    params_ptr: *const c_uchar,
+    params_len: c_int,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let shard = mysql_sharded_pool_shard_for(sharded_ptr, key_ptr, key_len);
+    let pool_ptr = mysql_sharded_pool_get(sharded_ptr, shard);
+    mysql_pool_query(
+        pool_ptr,
+        query,
+        params_ptr,
+        params_len,
+        std::ptr::null(),
+        req_id,
+        callback,
+    );
+}
+
+/// Executes a query whose `{orderby}` token is replaced by an allowlisted sort list.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_query_sorted(
+    pool_ptr: *mut MysqlPool,
+    query_template: *const c_char,
+    sort_columns_ptr: *const c_uchar,
+    sort_columns_len: c_int,
+    allowed_columns: *const c_char,
+    params_ptr: *const c_uchar,
+    params_len: c_int,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let template = unwrap_or_return!(ptr_to_string(query_template), cb, req_id);
+    if !template.contains("{orderby}") {
+        send_error(&cb, req_id, "Query template has no {orderby} token");
+        return;
+    }
+    let allowed_str = unwrap_or_return!(ptr_to_string(allowed_columns), cb, req_id);
+    let allowed: Vec<&str> = allowed_str
+        .split(',')
+        .map(str::trim)
+        .filter(|c| !c.is_empty())
+        .collect();
+    let sort_data = ptr_to_vec(sort_columns_ptr, sort_columns_len);
+    let sort_keys = unwrap_or_return!(parse_sort_list(&sort_data, &allowed), cb, req_id);
+    let order_by = if sort_keys.is_empty() {
+        String::new()
+    } else {
+        format!("ORDER BY {}", sort_keys)
+    };
+    let query_str = template.replace("{orderby}", &order_by);
+    let params_owned = ptr_to_vec(params_ptr, params_len);
+    let pool_ref = unsafe { &*pool_ptr };
+    reject_if_read_only!(pool_ref.read_only, query_str, cb, req_id);
+    let pool = pool_ref.pool();
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let params_pos = parse_params!(params_owned, cb, req_id);
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        let result = unwrap_or_return!(conn.exec_iter(query_str, params_pos).await, cb, req_id);
+        let mut buf = unwrap_or_return!(encode_query_result(result).await, cb, req_id);
+        finish_result(
+            &mut buf,
+            conn.affected_rows(),
+            conn.last_insert_id().unwrap_or(0),
+        );
+        send_response(&cb, req_id, buf);
+    });
+}
+
+/// Executes a parameterized query, returning only its values.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_query_values(
+    pool_ptr: *mut MysqlPool,
+    query: *const c_char,
+    params_ptr: *const c_uchar,
+    params_len: c_int,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
+    let params_owned = ptr_to_vec(params_ptr, params_len);
+    let pool_ref = unsafe { &*pool_ptr };
+    reject_if_read_only!(pool_ref.read_only, query_str, cb, req_id);
+    let pool = pool_ref.pool();
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let params_pos = parse_params!(params_owned, cb, req_id);
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        let result = unwrap_or_return!(conn.exec_iter(query_str, params_pos).await, cb, req_id);
+        let mut buf = ok_frame(0);
+        let mut encoder = ResultSetEncoder::values_only(&mut buf);
+        unwrap_or_return!(
+            result.for_each_and_drop(|row| encoder.push(row)).await,
+            cb,
+            req_id
+        );
+        encoder.finish();
+        send_response(&cb, req_id, buf);
+    });
+}
+
+/// Executes a parameterized query, grouping its rows by the column at `key_col_index`.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_query_grouped(
+    pool_ptr: *mut MysqlPool,
+    query: *const c_char,
+    key_col_index: c_uint,
+    params_ptr: *const c_uchar,
+    params_len: c_int,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
+    let params_owned = ptr_to_vec(params_ptr, params_len);
+    let pool_ref = unsafe { &*pool_ptr };
+    reject_if_read_only!(pool_ref.read_only, query_str, cb, req_id);
+    let pool = pool_ref.pool();
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let params_pos = parse_params!(params_owned, cb, req_id);
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        let result = unwrap_or_return!(conn.exec_iter(query_str, params_pos).await, cb, req_id);
+        let columns = result.columns().unwrap_or_default();
+        if columns.is_empty() {
+            send_error(&cb, req_id, "Query did not return a result set");
+            return;
+        }
+        let key_index = key_col_index as usize;
+        if key_index >= columns.len() {
+            send_error(
+                &cb,
+                req_id,
+                &format!(
+                    "Key column index {} is out of range for {} columns",
+                    key_index,
+                    columns.len()
+                ),
+            );
+            return;
+        }
+        let rows = unwrap_or_return!(result.collect_and_drop::<Row>().await, cb, req_id);
+        send_response(
+            &cb,
+            req_id,
+            serialize_grouped_result(&columns, rows, key_index),
+        );
+    });
+}
+
+/// Executes a parameterized query, returning only the columns in `[start_col, end_col)`.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_query_col_range(
+    pool_ptr: *mut MysqlPool,
+    query: *const c_char,
+    start_col: c_uint,
+    end_col: c_uint,
+    params_ptr: *const c_uchar,
+    params_len: c_int,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
+    let params_owned = ptr_to_vec(params_ptr, params_len);
+    let pool_ref = unsafe { &*pool_ptr };
+    reject_if_read_only!(pool_ref.read_only, query_str, cb, req_id);
+    let pool = pool_ref.pool();
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let params_pos = parse_params!(params_owned, cb, req_id);
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        let result = unwrap_or_return!(conn.exec_iter(query_str, params_pos).await, cb, req_id);
+        let columns = result.columns().unwrap_or_default();
+        let range = start_col as usize..end_col as usize;
+        if range.is_empty() || range.end > columns.len() {
+            send_error(
+                &cb,
+                req_id,
+                &format!(
+                    "Column range {}..{} is invalid for {} columns",
+                    range.start,
+                    range.end,
+                    columns.len()
+                ),
+            );
+            return;
+        }
+        let rows = unwrap_or_return!(result.collect_and_drop::<Row>().await, cb, req_id);
+        let buf = serialize_column_range(
+            &columns,
+            rows,
+            range,
+            conn.affected_rows(),
+            conn.last_insert_id().unwrap_or(0),
+        );
+        send_response(&cb, req_id, buf);
+    });
+}
+
+/// Folds the column at `col_index` with SUM (0), MIN (1), MAX (2) or COUNT (3).
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_query_reduce(
+    pool_ptr: *mut MysqlPool,
+    query: *const c_char,
+    params_ptr: *const c_uchar,
+    params_len: c_int,
+    op: c_int,
+    col_index: c_uint,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let mut reduction = unwrap_or_return!(Reduction::new(op), cb, req_id, "Invalid reduce op");
+    let query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
+    let params_owned = ptr_to_vec(params_ptr, params_len);
+    let pool_ref = unsafe { &*pool_ptr };
+    reject_if_read_only!(pool_ref.read_only, query_str, cb, req_id);
+    let pool = pool_ref.pool();
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let params_pos = parse_params!(params_owned, cb, req_id);
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        let mut result = unwrap_or_return!(conn.exec_iter(query_str, params_pos).await, cb, req_id);
+        let column_count = result.columns().map_or(0, |cols| cols.len());
+        let col_index = col_index as usize;
+        if col_index >= column_count {
+            send_error(
+                &cb,
+                req_id,
+                &format!(
+                    "Column index {} is out of range for {} columns",
+                    col_index, column_count
+                ),
+            );
+            return;
+        }
+        while let Some(row) = unwrap_or_return!(result.next().await, cb, req_id) {
+            let value = row.as_ref(col_index).unwrap_or(&mysql_async::Value::NULL);
+            unwrap_or_return!(reduction.push(value), cb, req_id);
+        }
+        unwrap_or_return!(result.drop_result().await, cb, req_id);
+        let mut buf = ok_frame(14);
+        write_value(&mut buf, &reduction.finish());
+        send_response(&cb, req_id, buf);
+    });
+}
+
+/// Counts the distinct non-NULL values of the column at `col_index`.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_count_distinct(
+    pool_ptr: *mut MysqlPool,
+    query: *const c_char,
+    col_index: c_uint,
+    params_ptr: *const c_uchar,
+    params_len: c_int,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
+    let params_owned = ptr_to_vec(params_ptr, params_len);
+    let pool_ref = unsafe { &*pool_ptr };
+    reject_if_read_only!(pool_ref.read_only, query_str, cb, req_id);
+    let pool = pool_ref.pool();
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let params_pos = parse_params!(params_owned, cb, req_id);
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        let mut result = unwrap_or_return!(conn.exec_iter(query_str, params_pos).await, cb, req_id);
+        let column_count = result.columns().map_or(0, |cols| cols.len());
+        let col_index = col_index as usize;
+        if col_index >= column_count {
+            send_error(
+                &cb,
+                req_id,
+                &format!(
+                    "Column index {} is out of range for {} columns",
+                    col_index, column_count
+                ),
+            );
+            return;
+        }
+        let mut seen = std::collections::HashSet::new();
+        while let Some(row) = unwrap_or_return!(result.next().await, cb, req_id) {
+            match row.as_ref(col_index) {
+                None | Some(mysql_async::Value::NULL) => {}
+                Some(value) => {
+                    seen.insert(value.as_sql(true));
+                }
+            }
+        }
+        unwrap_or_return!(result.drop_result().await, cb, req_id);
+        let mut buf = ok_frame(9);
+        buf.write_u64(seen.len() as u64);
+        send_response(&cb, req_id, buf);
+    });
+}
+
+/// Executes a parameterized INSERT, returning only its generated id.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_insert(
+    pool_ptr: *mut MysqlPool,
+    query: *const c_char,
+    params_ptr: *const c_uchar,
+    params_len: c_int,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
+    let params_owned = ptr_to_vec(params_ptr, params_len);
+    let pool_ref = unsafe { &*pool_ptr };
+    reject_if_read_only!(pool_ref.read_only, query_str, cb, req_id);
+    let pool = pool_ref.pool();
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let params_pos = parse_params!(params_owned, cb, req_id);
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        unwrap_or_return!(conn.exec_drop(query_str, params_pos).await, cb, req_id);
+        let mut buf = ok_frame(9);
+        buf.write_u64(conn.last_insert_id().unwrap_or(0));
+        send_response(&cb, req_id, buf);
+    });
+}
+
+/// Executes a parameterized statement, reporting the warnings it raised.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_exec_with_warnings(
+    pool_ptr: *mut MysqlPool,
+    query: *const c_char,
+    params_ptr: *const c_uchar,
+    params_len: c_int,
+    fetch_warnings: bool,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
+    let params_owned = ptr_to_vec(params_ptr, params_len);
+    let pool_ref = unsafe { &*pool_ptr };
+    reject_if_read_only!(pool_ref.read_only, query_str, cb, req_id);
+    let pool = pool_ref.pool();
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let params_pos = parse_params!(params_owned, cb, req_id);
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        let result = unwrap_or_return!(conn.exec_iter(query_str, params_pos).await, cb, req_id);
+        let mut buf = unwrap_or_return!(encode_query_result(result).await, cb, req_id);
+        finish_result(
+            &mut buf,
+            conn.affected_rows(),
+            conn.last_insert_id().unwrap_or(0),
+        );
+        let warnings = conn.get_warnings();
+        buf.write_u16(warnings);
+        let warning_rows = if fetch_warnings && warnings > 0 {
+            unwrap_or_return!(conn.query::<Row, _>("SHOW WARNINGS").await, cb, req_id)
+        } else {
+            Vec::new()
+        };
+        write_result_set(&mut buf, warning_rows);
+        send_response(&cb, req_id, buf);
+    });
+}
+
+/// Executes several `;`-separated statements using the MySQL Text Protocol.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_exec_multi(
+    pool_ptr: *mut MysqlPool,
+    query: *const c_char,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
+    let pool_ref = unsafe { &*pool_ptr };
+    reject_if_read_only!(pool_ref.read_only, query_str, cb, req_id);
+    let pool = pool_ref.pool();
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        let mut result = unwrap_or_return!(conn.query_iter(query_str).await, cb, req_id);
+        let mut results = Vec::new();
+        while !result.is_empty() {
+            let rows = unwrap_or_return!(result.collect::<Row>().await, cb, req_id);
+            results.push((
+                rows,
+                result.affected_rows(),
+                result.last_insert_id().unwrap_or(0),
+            ));
+        }
+        drop(result);
+        send_response(&cb, req_id, serialize_multi_result(results));
+    });
+}
+
+/// Executes `;`-separated statements, streaming rows in chunks of at most `chunk_rows`.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_query_stream(
+    pool_ptr: *mut MysqlPool,
+    query: *const c_char,
+    chunk_rows: c_uint,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    if chunk_rows == 0 {
+        send_error(&cb, req_id, "Chunk size must be positive");
+        return;
+    }
+    let query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
+    let pool_ref = unsafe { &*pool_ptr };
+    reject_if_read_only!(pool_ref.read_only, query_str, cb, req_id);
+    let pool = pool_ref.pool();
+    let step = pool_ref.default_timeout;
+    spawn_with_timeout(None, cb.clone(), req_id, async move {
+        let mut conn = unwrap_or_return!(timed(step, pool.get_conn()).await, cb, req_id);
+        let mut result =
+            unwrap_or_return!(timed(step, conn.query_iter(query_str)).await, cb, req_id);
+        let mut encoder = RowChunkEncoder::default();
+        let mut total_affected = 0;
+        let mut last_id = 0;
+        while !result.is_empty() {
+            if let Some(columns) = result.columns().filter(|cols| !cols.is_empty()) {
+                encoder.set_columns(columns);
+            }
+            while let Some(row) = unwrap_or_return!(timed(step, result.next()).await, cb, req_id) {
+                encoder.push(&row);
+                if encoder.pending() >= chunk_rows
+                    && let Some(frame) = encoder.take_frame()
+                {
+                    send_response(&cb, req_id, frame);
+                }
+            }
+            if let Some(frame) = encoder.take_frame() {
+                send_response(&cb, req_id, frame);
+            }
+            total_affected += result.affected_rows();
+            if let Some(id) = result.last_insert_id().filter(|id| *id > 0) {
+                last_id = id;
+            }
+        }
+        drop(result);
+        send_response(
+            &cb,
+            req_id,
+            serialize_result(Vec::new(), total_affected, last_id),
+        );
+    });
+}
+
+/// Streams the DDL and then the rows of `table` in chunks of at most `batch_size`.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_dump_table(
+    pool_ptr: *mut MysqlPool,
+    table: *const c_char,
+    batch_size: c_uint,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    if batch_size == 0 {
+        send_error(&cb, req_id, "Batch size must be positive");
+        return;
+    }
+    let table = quote_identifier(&unwrap_or_return!(ptr_to_string(table), cb, req_id));
+    let pool_ref = unsafe { &*pool_ptr };
+    let pool = pool_ref.pool();
+    let step = pool_ref.default_timeout;
+    spawn_with_timeout(None, cb.clone(), req_id, async move {
+        let mut conn = unwrap_or_return!(timed(step, pool.get_conn()).await, cb, req_id);
+        let create: Option<Row> = unwrap_or_return!(
+            timed(
+                step,
+                conn.query_first(format!("SHOW CREATE TABLE {}", table))
+            )
+            .await,
+            cb,
+            req_id
+        );
+        let ddl = create.and_then(|row| row.get::<Vec<u8>, _>(1));
+        let ddl = unwrap_or_return!(ddl, cb, req_id, "SHOW CREATE TABLE returned no DDL");
+        send_response(&cb, req_id, encode_ddl_chunk(&ddl));
+        let mut result = unwrap_or_return!(
+            timed(step, conn.query_iter(format!("SELECT * FROM {}", table))).await,
+            cb,
+            req_id
+        );
+        let mut encoder = RowChunkEncoder::default();
+        if let Some(columns) = result.columns() {
+            encoder.set_columns(columns);
+        }
+        let mut dumped = 0;
+        while let Some(row) = unwrap_or_return!(timed(step, result.next()).await, cb, req_id) {
+            encoder.push(&row);
+            dumped += 1;
+            if encoder.pending() >= batch_size
+                && let Some(frame) = encoder.take_frame()
+            {
+                send_response(&cb, req_id, frame);
+            }
+        }
+        if let Some(frame) = encoder.take_frame() {
+            send_response(&cb, req_id, frame);
+        }
+        drop(result);
+        send_response(&cb, req_id, serialize_result(Vec::new(), dumped, 0));
+    });
+}
+
+/// Executes a statement, typically a `CALL`, counting the result sets it produced.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_count_result_sets(
+    pool_ptr: *mut MysqlPool,
+    call_statement: *const c_char,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let query_str = unwrap_or_return!(ptr_to_string(call_statement), cb, req_id);
+    let pool_ref = unsafe { &*pool_ptr };
+    reject_if_read_only!(pool_ref.read_only, query_str, cb, req_id);
+    let pool = pool_ref.pool();
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        let mut result = unwrap_or_return!(conn.query_iter(query_str).await, cb, req_id);
+        let mut count = 0u64;
+        while !result.is_empty() {
+            if !result.columns_ref().is_empty() {
+                count += 1;
+            }
+            unwrap_or_return!(result.for_each(|_| ()).await, cb, req_id);
+        }
+        drop(result);
+        send_response(&cb, req_id, serialize_result(Vec::new(), count, 0));
+    });
+}
+
+/// Normalizes a transaction isolation level such as `read committed` or
+/// `REPEATABLE_READ` to its SQL spelling, or returns `None` if it is not one.
+fn isolation_level_name(level: &str) -> Option<&'static str> {
+    let words: Vec<String> = level
+        .split(|c: char| c.is_whitespace() || c == '_' || c == '-')
+        .filter(|w| !w.is_empty())
+        .map(str::to_ascii_uppercase)
+        .collect();
+    match words.join(" ").as_str() {
+        "READ UNCOMMITTED" => Some("READ UNCOMMITTED"),
+        "READ COMMITTED" => Some("READ COMMITTED"),
+        "REPEATABLE READ" => Some("REPEATABLE READ"),
+        "SERIALIZABLE" => Some("SERIALIZABLE"),
+        _ => None,
+    }
+}
+
+/// Returns `true` if `name` is a bare identifier, optionally schema-qualified when `qualified`.
+fn is_identifier(name: &str, qualified: bool) -> bool {
+    !name.is_empty()
+        && name.split('.').count() <= if qualified { 2 } else { 1 }
+        && name.split('.').all(|part| {
+            !part.is_empty()
+                && part
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'$')
+        })
+}
+
+/// Calls a procedure, returning its result sets and the OUT parameters in `out_names`.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_call_full(
+    pool_ptr: *mut MysqlPool,
+    procedure: *const c_char,
+    in_params_ptr: *const c_uchar,
+    in_params_len: c_int,
+    out_names: *const c_char,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let procedure = unwrap_or_return!(ptr_to_string(procedure), cb, req_id);
+    if !is_identifier(&procedure, true) {
+        send_error(&cb, req_id, "Invalid procedure name");
+        return;
+    }
+    let out_names = if out_names.is_null() {
+        String::new()
+    } else {
+        unwrap_or_return!(ptr_to_string(out_names), cb, req_id)
+    };
+    let out_vars: Vec<String> = out_names
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| format!("@{}", name))
+        .collect();
+    if out_vars.iter().any(|var| !is_identifier(&var[1..], false)) {
+        send_error(&cb, req_id, "Invalid OUT parameter name");
+        return;
+    }
+    let params_owned = ptr_to_vec(in_params_ptr, in_params_len);
+    let pool_ref = unsafe { &*pool_ptr };
+    // A procedure may write, which the statement text cannot reveal.
+    if pool_ref.read_only {
+        send_error(&cb, req_id, READ_ONLY_ERROR);
+        return;
+    }
+    let pool = pool_ref.pool();
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let params_pos = parse_params!(params_owned, cb, req_id);
+        let in_count = match &params_pos {
+            Params::Positional(values) => values.len(),
+            _ => 0,
+        };
+        let args: Vec<&str> = std::iter::repeat_n("?", in_count)
+            .chain(out_vars.iter().map(String::as_str))
+            .collect();
+        let call_query = format!("CALL {}({})", procedure, args.join(", "));
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        let mut result =
+            unwrap_or_return!(conn.exec_iter(call_query, params_pos).await, cb, req_id);
+        let mut results = Vec::new();
+        while !result.is_empty() {
+            let has_columns = !result.columns_ref().is_empty();
+            let rows = unwrap_or_return!(result.collect::<Row>().await, cb, req_id);
+            if has_columns {
+                results.push((
+                    rows,
+                    result.affected_rows(),
+                    result.last_insert_id().unwrap_or(0),
+                ));
+            }
+        }
+        drop(result);
+        let out_rows = if out_vars.is_empty() {
+            Vec::new()
+        } else {
+            let out_query = format!("SELECT {}", out_vars.join(", "));
+            unwrap_or_return!(conn.query::<Row, _>(out_query).await, cb, req_id)
+        };
+        let mut buf = serialize_multi_result(results);
+        write_result_set(&mut buf, out_rows);
+        send_response(&cb, req_id, buf);
+    });
+}
+
+/// Executes one page of a parameterized SELECT, optionally with the unpaged total.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_query_page(
+    pool_ptr: *mut MysqlPool,
+    query: *const c_char,
+    params_ptr: *const c_uchar,
+    params_len: c_int,
+    page: c_int,
+    page_size: c_int,
+    with_total: bool,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    if page < 0 || page_size <= 0 {
+        send_error(&cb, req_id, "Invalid page or page size");
+        return;
+    }
+    let query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
+    let base_query = trim_statement(&query_str).to_string();
+    if contains_keyword(&base_query, "LIMIT") {
+        send_error(&cb, req_id, "Paged query must not contain a LIMIT clause");
+        return;
+    }
+    let params_owned = ptr_to_vec(params_ptr, params_len);
+    let pool_ref = unsafe { &*pool_ptr };
+    reject_if_read_only!(pool_ref.read_only, base_query, cb, req_id);
+    let pool = pool_ref.pool();
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let params_pos = parse_params!(params_owned, cb, req_id);
+        let offset = page as u64 * page_size as u64;
+        let paged_query = format!("{} LIMIT {} OFFSET {}", base_query, page_size, offset);
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        let rows = unwrap_or_return!(conn.exec(paged_query, params_pos.clone()).await, cb, req_id);
+        let affected = conn.affected_rows();
+        let last_id = conn.last_insert_id().unwrap_or(0);
+        let total = if with_total {
+            let count_query = format!("SELECT COUNT(*) FROM ({}) AS __page_total", base_query);
+            let count: Option<u64> =
+                unwrap_or_return!(conn.exec_first(count_query, params_pos).await, cb, req_id);
+            Some(count.unwrap_or(0))
+        } else {
+            None
+        };
+        send_response(&cb, req_id, serialize_page(rows, affected, last_id, total));
+    });
+}
+
+/// Executes a write in its own transaction, rolled back unless it affected exactly one row.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_exec_one(
+    pool_ptr: *mut MysqlPool,
+    query: *const c_char,
+    params_ptr: *const c_uchar,
+    params_len: c_int,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
+    let params_owned = ptr_to_vec(params_ptr, params_len);
+    let pool_ref = unsafe { &*pool_ptr };
+    reject_if_read_only!(pool_ref.read_only, query_str, cb, req_id);
+    let pool = pool_ref.pool();
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let params_pos = parse_params!(params_owned, cb, req_id);
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        unwrap_or_return!(conn.query_drop("START TRANSACTION").await, cb, req_id);
+        if let Err(e) = conn.exec_drop(query_str, params_pos).await {
+            let _ = conn.query_drop("ROLLBACK").await;
+            send_categorized_error(&cb, req_id, &e);
+            return;
+        }
+        let (affected_rows, last_insert_id) =
+            (conn.affected_rows(), conn.last_insert_id().unwrap_or(0));
+        if affected_rows != 1 {
+            unwrap_or_return!(conn.query_drop("ROLLBACK").await, cb, req_id);
+            report_error(
+                &cb,
+                req_id,
+                ERROR_ROW_COUNT,
+                0,
+                &format!(
+                    "Expected exactly one affected row, got {}; rolled back",
+                    affected_rows
+                ),
+            );
+            return;
+        }
+        unwrap_or_return!(conn.query_drop("COMMIT").await, cb, req_id);
+        send_response(
+            &cb,
+            req_id,
+            serialize_result(Vec::new(), affected_rows, last_insert_id),
+        );
+    });
+}
+
+/// Runs a statement list in one transaction, honouring the per-statement row requirements.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_run_transaction(
+    pool_ptr: *mut MysqlPool,
+    statements_ptr: *const c_uchar,
+    statements_len: c_int,
+    required_ptr: *const c_uchar,
+    required_len: c_int,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let data = ptr_to_vec(statements_ptr, statements_len);
+    let statements = unwrap_or_return!(parse_statement_list(&data), cb, req_id);
+    let required = ptr_to_vec(required_ptr, required_len);
+    let pool_ref = unsafe { &*pool_ptr };
+    for (query, _) in &statements {
+        reject_if_read_only!(pool_ref.read_only, query, cb, req_id);
+    }
+    let pool = pool_ref.pool();
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        unwrap_or_return!(conn.query_drop("START TRANSACTION").await, cb, req_id);
+        let mut outcomes = Vec::with_capacity(statements.len());
+        for (index, (query, params)) in statements.into_iter().enumerate() {
+            let params = if params.is_empty() {
+                Params::Empty
+            } else {
+                Params::Positional(params)
+            };
+            if let Err(e) = conn.exec_drop(query, params).await {
+                let _ = conn.query_drop("ROLLBACK").await;
+                report_error(
+                    &cb,
+                    req_id,
+                    crate::utils::ErrorCategory::category(&e),
+                    crate::utils::ErrorCategory::code(&e),
+                    &format!("Statement {}: {}; rolled back", index, e),
+                );
+                return;
+            }
+            let affected_rows = conn.affected_rows();
+            if affected_rows == 0 && required.get(index).is_some_and(|&r| r != 0) {
+                unwrap_or_return!(conn.query_drop("ROLLBACK").await, cb, req_id);
+                report_error(
+                    &cb,
+                    req_id,
+                    ERROR_ROW_COUNT,
+                    0,
+                    &format!("Statement {} affected no rows; rolled back", index),
+                );
+                return;
+            }
+            outcomes.push((affected_rows, conn.last_insert_id().unwrap_or(0)));
+        }
+        unwrap_or_return!(conn.query_drop("COMMIT").await, cb, req_id);
+        let mut buf = serialize_result(Vec::new(), 0, 0);
+        buf.write_u32(outcomes.len() as u32);
+        for (affected_rows, last_insert_id) in outcomes {
+            buf.write_u64(affected_rows);
+            buf.write_u64(last_insert_id);
+        }
+        send_response(&cb, req_id, buf);
+    });
+}
+
+/// Executes a parameterized query, writing the frame into `out_buf` when it fits; that frame must
+/// not be passed to `mysql_buffer_free`, and `out_buf` must outlive the callback.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_query_into(
+    pool_ptr: *mut MysqlPool,
+    query: *const c_char,
+    params_ptr: *const c_uchar,
+    params_len: c_int,
+    out_buf: *mut c_uchar,
+    out_cap: c_int,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() || out_buf.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
+    let params_owned = ptr_to_vec(params_ptr, params_len);
+    let pool_ref = unsafe { &*pool_ptr };
+    reject_if_read_only!(pool_ref.read_only, query_str, cb, req_id);
+    let out_buf = out_buf as usize;
+    let out_cap = out_cap.max(0) as usize;
+    let pool = pool_ref.pool();
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let params_pos = parse_params!(params_owned, cb, req_id);
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        let result = unwrap_or_return!(conn.exec_iter(query_str, params_pos).await, cb, req_id);
+        let mut buf = unwrap_or_return!(encode_query_result(result).await, cb, req_id);
+        finish_result(
+            &mut buf,
+            conn.affected_rows(),
+            conn.last_insert_id().unwrap_or(0),
+        );
+        send_response_into(&cb, req_id, buf, out_buf, out_cap);
+    });
+}
+
+/// Executes a parameterized query, then `SELECT FOUND_ROWS()` on the same connection.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_query_with_found_rows(
+    pool_ptr: *mut MysqlPool,
+    query: *const c_char,
+    params_ptr: *const c_uchar,
+    params_len: c_int,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
+    let params_owned = ptr_to_vec(params_ptr, params_len);
+    let pool_ref = unsafe { &*pool_ptr };
+    reject_if_read_only!(pool_ref.read_only, query_str, cb, req_id);
+    let pool = pool_ref.pool();
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let params_pos = parse_params!(params_owned, cb, req_id);
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        let result = unwrap_or_return!(conn.exec_iter(query_str, params_pos).await, cb, req_id);
+        let mut buf = unwrap_or_return!(encode_query_result(result).await, cb, req_id);
+        finish_result(
+            &mut buf,
+            conn.affected_rows(),
+            conn.last_insert_id().unwrap_or(0),
+        );
+        let found_rows: Option<u64> =
+            unwrap_or_return!(conn.query_first("SELECT FOUND_ROWS()").await, cb, req_id);
+        buf.write_u64(found_rows.unwrap_or(0));
+        send_response(&cb, req_id, buf);
+    });
+}
+
+/// Executes an INSERT, returning the inserted rows with their generated defaults.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_insert_get_defaults(
+    pool_ptr: *mut MysqlPool,
+    query: *const c_char,
+    params_ptr: *const c_uchar,
+    params_len: c_int,
+    table: *const c_char,
+    lookup_key: *const c_char,
+    lookup_params_ptr: *const c_uchar,
+    lookup_params_len: c_int,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
+    let table_str = unwrap_or_return!(ptr_to_string(table), cb, req_id);
+    let key_str = unwrap_or_return!(ptr_to_string(lookup_key), cb, req_id);
+    let params_owned = ptr_to_vec(params_ptr, params_len);
+    let lookup_owned = ptr_to_vec(lookup_params_ptr, lookup_params_len);
+    let pool_ref = unsafe { &*pool_ptr };
+    if pool_ref.read_only {
+        send_error(&cb, req_id, READ_ONLY_ERROR);
+        return;
+    }
+    let flavor_cache = pool_ref.flavor.clone();
+    let pool = pool_ref.pool();
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let params_pos = parse_params!(params_owned, cb, req_id);
+        let lookup_pos = parse_params!(lookup_owned, cb, req_id);
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        let flavor = unwrap_or_return!(detect_flavor(&flavor_cache, &mut conn).await, cb, req_id);
+        let (rows, affected_rows, last_insert_id) = if flavor == FLAVOR_MARIADB {
+            let returning = format!("{} RETURNING *", trim_statement(&query_str));
+            let rows: Vec<Row> =
+                unwrap_or_return!(conn.exec(returning, params_pos).await, cb, req_id);
+            (
+                rows,
+                conn.affected_rows(),
+                conn.last_insert_id().unwrap_or(0),
+            )
+        } else {
+            let columns: Vec<&str> = key_str
+                .split(',')
+                .map(str::trim)
+                .filter(|c| !c.is_empty())
+                .collect();
+            let lookup_count = match &lookup_pos {
+                Params::Positional(values) => values.len(),
+                _ => 0,
+            };
+            if columns.is_empty() || columns.len() != lookup_count {
+                send_error(
+                    &cb,
+                    req_id,
+                    "lookup_key must name one column per lookup parameter",
+                );
+                return;
+            }
+            unwrap_or_return!(conn.exec_drop(query_str, params_pos).await, cb, req_id);
+            let (affected_rows, last_insert_id) =
+                (conn.affected_rows(), conn.last_insert_id().unwrap_or(0));
+            let conditions: Vec<String> = columns
+                .iter()
+                .map(|c| format!("{} = ?", quote_identifier(c)))
+                .collect();
+            let select = format!(
+                "SELECT * FROM {} WHERE {}",
+                quote_identifier(&table_str),
+                conditions.join(" AND ")
+            );
+            let rows: Vec<Row> = unwrap_or_return!(conn.exec(select, lookup_pos).await, cb, req_id);
+            (rows, affected_rows, last_insert_id)
+        };
+        send_response(
+            &cb,
+            req_id,
+            serialize_result(rows, affected_rows, last_insert_id),
+        );
+    });
+}
+
+/// Executes a parameterized query, returning hashes of its result and of each row.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_query_hashed(
+    pool_ptr: *mut MysqlPool,
+    query: *const c_char,
+    params_ptr: *const c_uchar,
+    params_len: c_int,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
+    let params_owned = ptr_to_vec(params_ptr, params_len);
+    let pool_ref = unsafe { &*pool_ptr };
+    reject_if_read_only!(pool_ref.read_only, query_str, cb, req_id);
+    let pool = pool_ref.pool();
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let params_pos = parse_params!(params_owned, cb, req_id);
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        let result = unwrap_or_return!(conn.exec_iter(query_str, params_pos).await, cb, req_id);
+        let mut buf = begin_result();
+        let block_start = buf.len();
+        let mut row_hashes = Vec::new();
+        let mut encoder = ResultSetEncoder::new(&mut buf);
+        unwrap_or_return!(
+            result
+                .for_each_and_drop(|row| row_hashes.push(encoder.push_hashed(row)))
+                .await,
+            cb,
+            req_id
+        );
+        encoder.finish();
+        let result_hash = stable_hash(&buf[block_start..]);
+        finish_result(
+            &mut buf,
+            conn.affected_rows(),
+            conn.last_insert_id().unwrap_or(0),
+        );
+        buf.write_u64(result_hash);
+        buf.write_u32(row_hashes.len() as u32);
+        for hash in row_hashes {
+            buf.write_u64(hash);
+        }
+        send_response(&cb, req_id, buf);
+    });
+}
+
+/// Writes the first value of a single-column query to the file descriptor `fd` (Unix only).
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_blob_to_fd(
+    pool_ptr: *mut MysqlPool,
+    query: *const c_char,
+    params_ptr: *const c_uchar,
+    params_len: c_int,
+    fd: c_int,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    if fd < 0 {
+        send_error(&cb, req_id, "Invalid file descriptor");
+        return;
+    }
+    let query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
+    let params_owned = ptr_to_vec(params_ptr, params_len);
+    let pool_ref = unsafe { &*pool_ptr };
+    reject_if_read_only!(pool_ref.read_only, query_str, cb, req_id);
+    let pool = pool_ref.pool();
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let params_pos = parse_params!(params_owned, cb, req_id);
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        let row: Option<Row> =
+            unwrap_or_return!(conn.exec_first(query_str, params_pos).await, cb, req_id);
+        let mut row = unwrap_or_return!(row, cb, req_id, "Query returned no rows");
+        if row.len() != 1 {
+            send_error(&cb, req_id, "Query must return a single column");
+            return;
+        }
+        let bytes = match row.take::<mysql_async::Value, _>(0) {
+            Some(mysql_async::Value::Bytes(bytes)) => bytes,
+            Some(mysql_async::Value::NULL) | None => Vec::new(),
+            Some(_) => {
+                send_error(&cb, req_id, "Column value is not a string or BLOB");
+                return;
+            }
+        };
+        let written = tokio::task::spawn_blocking(move || write_to_fd(fd, &bytes)).await;
+        let written = unwrap_or_return!(written.ok(), cb, req_id, "Write task failed");
+        let written = unwrap_or_return!(written, cb, req_id);
+        let mut buf = serialize_result(Vec::new(), 0, 0);
+        buf.write_u64(written);
+        send_response(&cb, req_id, buf);
+    });
+}
+
+/// Writes all of `bytes` to a caller-owned file descriptor without closing it.
+#[cfg(unix)]
+fn write_to_fd(fd: c_int, bytes: &[u8]) -> std::io::Result<u64> {
+    use std::io::Write;
+    use std::os::fd::FromRawFd;
+
+    let mut file = std::mem::ManuallyDrop::new(unsafe { std::fs::File::from_raw_fd(fd) });
+    // write_all retries partial and interrupted writes.
+    file.write_all(bytes)?;
+    Ok(bytes.len() as u64)
+}
+
+#[cfg(not(unix))]
+fn write_to_fd(_fd: c_int, _bytes: &[u8]) -> std::io::Result<u64> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "Writing to a file descriptor is only supported on Unix",
+    ))
+}
+
+/// Executes a parameterized query, returning its first result set as RFC 4180 CSV.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_query_csv(
+    pool_ptr: *mut MysqlPool,
+    query: *const c_char,
+    params_ptr: *const c_uchar,
+    params_len: c_int,
+    include_header: bool,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
+    let params_owned = ptr_to_vec(params_ptr, params_len);
+    let pool_ref = unsafe { &*pool_ptr };
+    reject_if_read_only!(pool_ref.read_only, query_str, cb, req_id);
+    let pool = pool_ref.pool();
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let params_pos = parse_params!(params_owned, cb, req_id);
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        let mut result = unwrap_or_return!(conn.exec_iter(query_str, params_pos).await, cb, req_id);
+        let mut csv = Vec::new();
+        if include_header && let Some(cols) = result.columns() {
+            write_csv_header(&mut csv, &cols);
+        }
+        while let Some(row) = unwrap_or_return!(result.next().await, cb, req_id) {
+            write_csv_row(&mut csv, &row);
+        }
+        unwrap_or_return!(result.drop_result().await, cb, req_id);
+        let mut buf = serialize_result(
+            Vec::new(),
+            conn.affected_rows(),
+            conn.last_insert_id().unwrap_or(0),
+        );
+        buf.write_blob(&csv);
+        send_response(&cb, req_id, buf);
+    });
+}
+
+/// Executes a parameterized query, encoding each row as a `message_name` message.
+#[cfg(feature = "protobuf")]
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_query_protobuf(
+    pool_ptr: *mut MysqlPool,
+    query: *const c_char,
+    params_ptr: *const c_uchar,
+    params_len: c_int,
+    descriptor_ptr: *const c_uchar,
+    descriptor_len: c_int,
+    message_name: *const c_char,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    use crate::protobuf::{MessageEncoder, parse_descriptor};
+
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
+    let params_owned = ptr_to_vec(params_ptr, params_len);
+    let message_str = unwrap_or_return!(ptr_to_string(message_name), cb, req_id);
+    let message = unwrap_or_return!(
+        parse_descriptor(&ptr_to_vec(descriptor_ptr, descriptor_len), &message_str),
+        cb,
+        req_id
+    );
+    let pool_ref = unsafe { &*pool_ptr };
+    reject_if_read_only!(pool_ref.read_only, query_str, cb, req_id);
+    let pool = pool_ref.pool();
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let params_pos = parse_params!(params_owned, cb, req_id);
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        let mut result = unwrap_or_return!(conn.exec_iter(query_str, params_pos).await, cb, req_id);
+        let mut stream = Vec::new();
+        let mut count = 0u32;
+        if let Some(cols) = result.columns() {
+            let mut encoder = unwrap_or_return!(MessageEncoder::new(&message, &cols), cb, req_id);
+            while let Some(row) = unwrap_or_return!(result.next().await, cb, req_id) {
+                unwrap_or_return!(encoder.write_delimited(&mut stream, &row), cb, req_id);
+                count += 1;
+            }
+        }
+        unwrap_or_return!(result.drop_result().await, cb, req_id);
+        let mut buf = serialize_result(
+            Vec::new(),
+            conn.affected_rows(),
+            conn.last_insert_id().unwrap_or(0),
+        );
+        buf.write_u32(count);
+        buf.write_blob(&stream);
+        send_response(&cb, req_id, buf);
+    });
+}
+
+/// Executes a parameterized query, returning every value as text.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_query_text(
+    pool_ptr: *mut MysqlPool,
+    query: *const c_char,
+    params_ptr: *const c_uchar,
+    params_len: c_int,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
+    let params_owned = ptr_to_vec(params_ptr, params_len);
+    let pool_ref = unsafe { &*pool_ptr };
+    reject_if_read_only!(pool_ref.read_only, query_str, cb, req_id);
+    let pool = pool_ref.pool();
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let params_pos = parse_params!(params_owned, cb, req_id);
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        let mut result = unwrap_or_return!(conn.exec_iter(query_str, params_pos).await, cb, req_id);
+        let cols = result.columns().unwrap_or_default();
+        let mut cells = Vec::new();
+        let mut row_count = 0u32;
+        while let Some(row) = unwrap_or_return!(result.next().await, cb, req_id) {
+            write_text_row(&mut cells, &row);
+            row_count += 1;
+        }
+        unwrap_or_return!(result.drop_result().await, cb, req_id);
+        let mut buf = serialize_result(
+            Vec::new(),
+            conn.affected_rows(),
+            conn.last_insert_id().unwrap_or(0),
+        );
+        buf.write_u32(cols.len() as u32);
+        for col in cols.iter() {
+            buf.write_blob(col.name_ref());
+        }
+        buf.write_u32(row_count);
+        buf.extend_from_slice(&cells);
+        send_response(&cb, req_id, buf);
+    });
+}
+
+/// Executes a parameterized query, keeping rows whose `filter_col` matches `filter_regex`.
+#[cfg(feature = "regex")]
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_query_filtered(
+    pool_ptr: *mut MysqlPool,
+    query: *const c_char,
+    filter_col: *const c_char,
+    filter_regex: *const c_char,
+    params_ptr: *const c_uchar,
+    params_len: c_int,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
+    let column = unwrap_or_return!(ptr_to_string(filter_col), cb, req_id);
+    let pattern = unwrap_or_return!(ptr_to_string(filter_regex), cb, req_id);
+    let regex = match regex::Regex::new(&pattern) {
+        Ok(regex) => regex,
+        Err(e) => {
+            send_error(&cb, req_id, &format!("Invalid filter regex: {}", e));
+            return;
+        }
+    };
+    let params_owned = ptr_to_vec(params_ptr, params_len);
+    let pool_ref = unsafe { &*pool_ptr };
+    reject_if_read_only!(pool_ref.read_only, query_str, cb, req_id);
+    let pool = pool_ref.pool();
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let params_pos = parse_params!(params_owned, cb, req_id);
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        let mut result = unwrap_or_return!(conn.exec_iter(query_str, params_pos).await, cb, req_id);
+        let mut buf = begin_result();
+        let mut encoder = ResultSetEncoder::new(&mut buf);
+        if let Some(cols) = result.columns() {
+            let index = unwrap_or_return!(
+                cols.iter().position(|c| c.name_str() == column),
+                cb,
+                req_id,
+                &format!("Result has no column {}", column)
+            );
+            while let Some(row) = unwrap_or_return!(result.next().await, cb, req_id) {
+                let matched = match row.as_ref(index) {
+                    None | Some(mysql_async::Value::NULL) => false,
+                    Some(val) => regex.is_match(&String::from_utf8_lossy(&crate::utils::csv_text(
+                        val,
+                        &cols[index],
+                    ))),
+                };
+                if matched {
+                    encoder.push(row);
+                }
+            }
+        }
+        encoder.finish();
+        unwrap_or_return!(result.drop_result().await, cb, req_id);
+        finish_result(
+            &mut buf,
+            conn.affected_rows(),
+            conn.last_insert_id().unwrap_or(0),
+        );
+        send_response(&cb, req_id, buf);
+    });
+}
+
+/// Reads `VERSION()` and `@@version_comment`.
+async fn server_version(conn: &mut Conn) -> mysql_async::Result<(String, String)> {
+    let row: Option<(String, Option<String>)> = conn
+        .query_first("SELECT VERSION(), @@version_comment")
+        .await?;
+    Ok(row
+        .map(|(version, comment)| (version, comment.unwrap_or_default()))
+        .unwrap_or_default())
+}
+
+/// Returns the pool's server flavor, querying it on `conn` the first time.
+async fn detect_flavor(cache: &OnceLock<u8>, conn: &mut Conn) -> mysql_async::Result<u8> {
+    if let Some(flavor) = cache.get() {
+        return Ok(*flavor);
+    }
+    let (version, comment) = server_version(conn).await?;
+    Ok(*cache.get_or_init(|| server_flavor(&version, &comment)))
+}
+
+/// MySQL error raised on the transaction chosen as a deadlock victim.
+const ER_LOCK_DEADLOCK: u16 = 1213;
+
+/// Appends the InnoDB report of the latest deadlock to a deadlock error when
+/// `enabled`.
+///
+/// The report is read with `SHOW ENGINE INNODB STATUS` on the same connection,
+/// which needs the `PROCESS` privilege. Other errors, and deadlocks whose report
+/// cannot be read, are returned unchanged.
+async fn with_deadlock_report(
+    conn: &mut Conn,
+    err: mysql_async::Error,
+    enabled: bool,
+) -> mysql_async::Error {
+    let mut e = match err {
+        mysql_async::Error::Server(e) if enabled && e.code == ER_LOCK_DEADLOCK => e,
+        err => return err,
+    };
+    let status: Option<(Vec<u8>, Vec<u8>, Vec<u8>)> = conn
+        .query_first("SHOW ENGINE INNODB STATUS")
+        .await
+        .ok()
+        .flatten();
+    if let Some((_, _, status)) = status
+        && let Some(report) = latest_deadlock(&String::from_utf8_lossy(&status))
+    {
+        e.message = format!("{}\n{}", e.message, report);
+    }
+    mysql_async::Error::Server(e)
+}
+
+/// MySQL error raised for SQL the server cannot parse.
+const ER_PARSE_ERROR: u16 = 1064;
+
+/// Reports how many seconds the server lags behind its replication source.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_replication_lag(
+    pool_ptr: *mut MysqlPool,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let pool_ref = unsafe { &*pool_ptr };
+    let pool = pool_ref.pool();
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        let rows: Vec<Row> = match conn.query("SHOW REPLICA STATUS").await {
+            Err(mysql_async::Error::Server(e)) if e.code == ER_PARSE_ERROR => {
+                unwrap_or_return!(conn.query("SHOW SLAVE STATUS").await, cb, req_id)
+            }
+            result => unwrap_or_return!(result, cb, req_id),
+        };
+        let lag = rows
+            .iter()
+            .map(|row| {
+                let cols = row.columns_ref();
+                let index = cols.iter().position(|c| {
+                    matches!(
+                        c.name_ref(),
+                        b"Seconds_Behind_Source" | b"Seconds_Behind_Master"
+                    )
+                })?;
+                row.get_opt::<Option<i64>, _>(index)?.ok().flatten()
+            })
+            .try_fold(None, |max: Option<i64>, lag| {
+                lag.map(|lag| Some(max.map_or(lag, |max| max.max(lag))))
+            })
+            .flatten();
+        let mut buf = serialize_result(Vec::new(), 0, 0);
+        match lag {
+            Some(lag) => {
+                buf.write_u8(1);
+                buf.write_i64(lag);
+            }
+            None => buf.write_u8(0),
+        }
+        send_response(&cb, req_id, buf);
+    });
+}
+
+/// Identifies the server product and version.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_server_flavor(
+    pool_ptr: *mut MysqlPool,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let pool_ref = unsafe { &*pool_ptr };
+    let flavor_cache = pool_ref.flavor.clone();
+    let pool = pool_ref.pool();
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        let (version, comment) = unwrap_or_return!(server_version(&mut conn).await, cb, req_id);
+        let flavor = *flavor_cache.get_or_init(|| server_flavor(&version, &comment));
+        let (major, minor, patch) = parse_version(&version);
+        let mut buf = serialize_result(Vec::new(), 0, 0);
+        buf.write_u8(flavor);
+        buf.write_u16(major);
+        buf.write_u16(minor);
+        buf.write_u16(patch);
+        buf.write_blob(version.as_bytes());
+        send_response(&cb, req_id, buf);
+    });
+}
+
+/// Looks up the next `AUTO_INCREMENT` value of `table` without inserting.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_next_auto_increment(
+    pool_ptr: *mut MysqlPool,
+    table: *const c_char,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let table_str = unwrap_or_return!(ptr_to_string(table), cb, req_id);
+    let (schema, table_name) = match table_str.split_once('.') {
+        Some((schema, table_name)) => (Some(schema.to_string()), table_name.to_string()),
+        None => (None, table_str),
+    };
+    let pool = unsafe { &*pool_ptr }.pool();
+    spawn_with_timeout(
+        unsafe { &*pool_ptr }.default_timeout,
+        cb.clone(),
+        req_id,
+        async move {
+            let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+            let query = "SELECT /*+ SET_VAR(information_schema_stats_expiry = 0) */ AUTO_INCREMENT \
+                     FROM information_schema.TABLES \
+                     WHERE TABLE_SCHEMA = COALESCE(?, DATABASE()) AND TABLE_NAME = ?";
+            let row: Option<(Option<u64>,)> = unwrap_or_return!(
+                conn.exec_first(query, (schema, table_name)).await,
+                cb,
+                req_id
+            );
+            let (next,) = unwrap_or_return!(row, cb, req_id, "Table not found");
+            let mut buf = serialize_result(Vec::new(), 0, 0);
+            match next {
+                Some(next) => {
+                    buf.write_u8(1);
+                    buf.write_u64(next);
+                }
+                None => buf.write_u8(0),
+            }
+            send_response(&cb, req_id, buf);
+        },
+    );
+}
+
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_prepare(
+    pool_ptr: *mut MysqlPool,
+    query: *const c_char,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
+    let pool_ref = unsafe { &*pool_ptr };
+    reject_if_read_only!(pool_ref.read_only, query_str, cb, req_id);
+    let default_timeout = pool_ref.default_timeout;
+    let pool = pool_ref.pool();
+    spawn_with_timeout(default_timeout, cb.clone(), req_id, async move {
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        let stmt = unwrap_or_return!(conn.prep(query_str).await, cb, req_id);
+        let ptr = Box::into_raw(Box::new(MysqlPreparedStatement {
+            conn: Arc::new(Mutex::new(Some(conn))),
+            stmt,
+            last_error: Arc::default(),
+            default_timeout,
+        }));
+        send_response(&cb, req_id, serialize_result(Vec::new(), ptr as u64, 0));
+    });
+}
+
+/// Rows a cursor's reader task buffers ahead of the caller's fetches.
+const CURSOR_BUFFER_ROWS: usize = 1024;
+
+/// Trailing state byte of a `mysql_cursor_fetch` frame: more rows may follow.
+const CURSOR_MORE: u8 = 0;
+/// Trailing state byte: the cursor has delivered all of its rows.
+const CURSOR_EXHAUSTED: u8 = 1;
+/// Trailing state byte: the fetch timed out; the cursor remains usable.
+const CURSOR_TIMED_OUT: u8 = 2;
+
+/// Opens a cursor over a query's rows, holding its connection until the cursor is done.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_cursor_open(
+    pool_ptr: *mut MysqlPool,
+    query: *const c_char,
+    params_ptr: *const c_uchar,
+    params_len: c_int,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
+    let params_owned = ptr_to_vec(params_ptr, params_len);
+    let pool_ref = unsafe { &*pool_ptr };
+    reject_if_read_only!(pool_ref.read_only, query_str, cb, req_id);
+    let pool = pool_ref.pool();
+    let step = pool_ref.default_timeout;
+    spawn_with_timeout(None, cb.clone(), req_id, async move {
+        let params_pos = parse_params!(params_owned, cb, req_id);
+        let mut conn = unwrap_or_return!(timed(step, pool.get_conn()).await, cb, req_id);
+        let mut result = unwrap_or_return!(
+            timed(step, conn.exec_iter(query_str, params_pos)).await,
+            cb,
+            req_id
+        );
+        let (tx, rx) = tokio::sync::mpsc::channel(CURSOR_BUFFER_ROWS);
+        let ptr = Box::into_raw(Box::new(MysqlCursor {
+            state: Arc::new(Mutex::new(CursorState {
+                rx,
+                encoder: RowChunkEncoder::default(),
+            })),
+        }));
+        send_response(&cb, req_id, serialize_result(Vec::new(), ptr as u64, 0));
+
+        // Sending fails once the cursor is destroyed, which stops reading.
+        while !result.is_empty() {
+            if let Some(columns) = result.columns().filter(|cols| !cols.is_empty())
+                && tx.send(CursorEvent::Columns(columns)).await.is_err()
+            {
+                return;
+            }
+            loop {
+                let event = match timed(step, result.next()).await {
+                    Ok(Some(row)) => CursorEvent::Row(row),
+                    Ok(None) => break,
+                    Err(e) => {
+                        let _ = tx.send(CursorEvent::Error(e)).await;
+                        return;
+                    }
+                };
+                if tx.send(event).await.is_err() {
+                    return;
+                }
+            }
+        }
+    });
+}
+
+/// Fetches up to `max_rows` rows from a cursor, waiting at most `timeout_ms` (0 for no limit).
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_cursor_fetch(
+    cursor_ptr: *mut MysqlCursor,
+    max_rows: c_uint,
+    timeout_ms: c_uint,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if cursor_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid cursor pointer");
+        return;
+    }
+    if max_rows == 0 {
+        send_error(&cb, req_id, "Fetch size must be positive");
+        return;
+    }
+    let state = unsafe { &*cursor_ptr }.state.clone();
+    unwrap_or_return!(get_runtime(), cb, req_id).spawn(async move {
+        let deadline = (timeout_ms > 0)
+            .then(|| tokio::time::Instant::now() + Duration::from_millis(timeout_ms as u64));
+        let mut state = state.lock().await;
+        let CursorState { rx, encoder } = &mut *state;
+        let mut cursor_state = CURSOR_MORE;
+        while encoder.pending() < max_rows {
+            let event = match deadline {
+                Some(deadline) => match tokio::time::timeout_at(deadline, rx.recv()).await {
+                    Ok(event) => event,
+                    Err(_) => {
+                        cursor_state = CURSOR_TIMED_OUT;
+                        break;
+                    }
+                },
+                None => rx.recv().await,
+            };
+            match event {
+                Some(CursorEvent::Columns(columns)) => encoder.set_columns(columns),
+                Some(CursorEvent::Row(row)) => encoder.push(&row),
+                Some(CursorEvent::Error(e)) => {
+                    send_categorized_error(&cb, req_id, &e);
+                    return;
+                }
+                None => {
+                    cursor_state = CURSOR_EXHAUSTED;
+                    break;
+                }
+            }
+        }
+        let mut buf = encoder.flush();
+        buf.write_u8(cursor_state);
+        send_response(&cb, req_id, buf);
+        send_response(&cb, req_id, serialize_result(Vec::new(), 0, 0));
+    });
+}
+
+/// Destroys a cursor, discarding its unread rows and releasing its connection.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_cursor_destroy(cursor_ptr: *mut MysqlCursor) {
+    if !cursor_ptr.is_null() {
+        unsafe {
+            let _ = Box::from_raw(cursor_ptr);
+        }
+    }
+}
+
+/// Opens up to `count` connections at once and returns them to the pool.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_warmup(
+    pool_ptr: *mut MysqlPool,
+    count: c_uint,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let pool_ref = unsafe { &*pool_ptr };
+    let count = (count as usize).min(pool_ref.opts().pool_opts().constraints().max());
+    let pool = Arc::new(pool_ref.pool());
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let mut pending = tokio::task::JoinSet::new();
+        for _ in 0..count {
+            let pool = pool.clone();
+            pending.spawn(async move { pool.get_conn().await });
+        }
+        // Connections are held until all are open so each one is distinct.
+        let mut conns = Vec::with_capacity(count);
+        while let Some(joined) = pending.join_next().await {
+            let conn = unwrap_or_return!(joined.ok(), cb, req_id, "Warmup task failed");
+            conns.push(unwrap_or_return!(conn, cb, req_id));
+        }
+        let opened = conns.len() as u64;
+        drop(conns);
+        send_response(&cb, req_id, serialize_result(Vec::new(), opened, 0));
+    });
+}
+
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_begin_transaction(
+    pool_ptr: *mut MysqlPool,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let pool_ref = unsafe { &*pool_ptr };
+    let read_only = pool_ref.read_only;
+    let default_timeout = pool_ref.default_timeout;
+    let deadlock_diagnostics = pool_ref.deadlock_diagnostics;
+    let pool = pool_ref.pool();
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        unwrap_or_return!(conn.query_drop("START TRANSACTION").await, cb, req_id);
+
+        let ptr = Box::into_raw(Box::new(MysqlConnection {
+            conn: Arc::new(Mutex::new(Some(conn))),
+            read_only,
+            last_error: Arc::default(),
+            default_timeout,
+            deadlock_diagnostics,
+        }));
+
+        send_response(&cb, req_id, serialize_result(Vec::new(), ptr as u64, 0));
+    });
+}
+
+/// Begins a `REPEATABLE READ` transaction with a consistent snapshot.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_begin_snapshot(
+    pool_ptr: *mut MysqlPool,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let pool_ref = unsafe { &*pool_ptr };
+    let read_only = pool_ref.read_only;
+    let default_timeout = pool_ref.default_timeout;
+    let deadlock_diagnostics = pool_ref.deadlock_diagnostics;
+    let pool = pool_ref.pool();
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        // Applies to the next transaction only, so the pooled session keeps
+        // its configured isolation level afterwards.
+        unwrap_or_return!(
+            conn.query_drop("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ")
+                .await,
+            cb,
+            req_id
+        );
+        unwrap_or_return!(
+            conn.query_drop("START TRANSACTION WITH CONSISTENT SNAPSHOT")
+                .await,
+            cb,
+            req_id
+        );
+
+        let ptr = Box::into_raw(Box::new(MysqlConnection {
+            conn: Arc::new(Mutex::new(Some(conn))),
+            read_only,
+            last_error: Arc::default(),
+            default_timeout,
+            deadlock_diagnostics,
+        }));
+
+        send_response(&cb, req_id, serialize_result(Vec::new(), ptr as u64, 0));
+    });
+}
+
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_get_connection(
+    pool_ptr: *mut MysqlPool,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let pool_ref = unsafe { &*pool_ptr };
+    let read_only = pool_ref.read_only;
+    let default_timeout = pool_ref.default_timeout;
+    let deadlock_diagnostics = pool_ref.deadlock_diagnostics;
+    let pool = pool_ref.pool();
+    spawn_with_timeout(default_timeout, cb.clone(), req_id, async move {
+        let conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+
+        let ptr = Box::into_raw(Box::new(MysqlConnection {
             conn: Arc::new(Mutex::new(Some(conn))),
+            read_only,
+            last_error: Arc::default(),
+            default_timeout,
+            deadlock_diagnostics,
         }));
 
-        let mut buf = Vec::new();
-        buf.write_u8(1);
-        buf.write_u64(ptr as u64);
-        buf.write_u64(0);
-        buf.write_u32(0);
-        buf.write_u32(0);
+        send_response(&cb, req_id, serialize_result(Vec::new(), ptr as u64, 0));
+    });
+}
+
+/// Checks out a connection for consecutive queries, released with `mysql_conn_release`.
+#[unsafe(no_mangle)]
+pub extern "C" fn mysql_pool_session_begin(
+    pool_ptr: *mut MysqlPool,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    mysql_pool_get_connection(pool_ptr, req_id, callback);
+}
+
+/// Returns a session's connection to the pool; the pointer must not be used afterwards.
+#[unsafe(no_mangle)]
+pub extern "C" fn mysql_conn_release(conn_ptr: *mut MysqlConnection) {
+    mysql_conn_destroy(conn_ptr);
+}
+
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_conn_query_raw(
+    conn_ptr: *mut MysqlConnection,
+    query: *const c_char,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if conn_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid connection pointer");
+        return;
+    }
+    let cb = cb.with_last_error(&unsafe { &*conn_ptr }.last_error);
+    let query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
+    let conn_ref = unsafe { &*conn_ptr };
+    reject_if_read_only!(conn_ref.read_only, query_str, cb, req_id);
+    let conn_arc = conn_ref.conn.clone();
+    let deadlock_diagnostics = conn_ref.deadlock_diagnostics;
+
+    spawn_with_timeout(conn_ref.default_timeout, cb.clone(), req_id, async move {
+        let mut lock = conn_arc.lock().await;
+        if let Some(conn) = lock.as_mut() {
+            let result = match conn.query_iter(query_str).await {
+                Err(e) => Err(with_deadlock_report(conn, e, deadlock_diagnostics).await),
+                result => result,
+            };
+            let result = unwrap_or_close!(result, lock, cb, req_id);
+            let mut buf = unwrap_or_close!(encode_query_result(result).await, lock, cb, req_id);
+            finish_result(
+                &mut buf,
+                conn.affected_rows(),
+                conn.last_insert_id().unwrap_or(0),
+            );
+            send_response(&cb, req_id, buf);
+        } else {
+            send_error(&cb, req_id, "Connection is closed");
+        }
+    });
+}
+
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_conn_query(
+    conn_ptr: *mut MysqlConnection,
+    query: *const c_char,
+    params_ptr: *const c_uchar,
+    params_len: c_int,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if conn_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid connection pointer");
+        return;
+    }
+    let cb = cb.with_last_error(&unsafe { &*conn_ptr }.last_error);
+    let query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
+    let params_owned = ptr_to_vec(params_ptr, params_len);
+    let conn_ref = unsafe { &*conn_ptr };
+    reject_if_read_only!(conn_ref.read_only, query_str, cb, req_id);
+    let conn_arc = conn_ref.conn.clone();
+    let deadlock_diagnostics = conn_ref.deadlock_diagnostics;
+
+    spawn_with_timeout(conn_ref.default_timeout, cb.clone(), req_id, async move {
+        let params_pos = parse_params!(params_owned, cb, req_id);
+        let mut lock = conn_arc.lock().await;
+        if let Some(conn) = lock.as_mut() {
+            let stmt = unwrap_or_close!(conn.prep(query_str).await, lock, cb, req_id);
+            let result = match conn.exec_iter(&stmt, params_pos).await {
+                Err(e) => Err(with_deadlock_report(conn, e, deadlock_diagnostics).await),
+                result => result,
+            };
+            let result = unwrap_or_close!(result, lock, cb, req_id);
+            let mut buf = unwrap_or_close!(encode_query_result(result).await, lock, cb, req_id);
+            finish_result(
+                &mut buf,
+                conn.affected_rows(),
+                conn.last_insert_id().unwrap_or(0),
+            );
+            unwrap_or_close!(release_statement(conn, stmt).await, lock, cb, req_id);
+            send_response(&cb, req_id, buf);
+        } else {
+            send_error(&cb, req_id, "Connection is closed");
+        }
+    });
+}
+
+const LOCK_FOR_UPDATE: c_int = 0;
+const LOCK_FOR_UPDATE_NOWAIT: c_int = 1;
+const LOCK_FOR_UPDATE_SKIP_LOCKED: c_int = 2;
+
+/// Creates a statement prepared on any pooled connection; destroy it before its pool.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_stmt_create(
+    pool_ptr: *mut MysqlPool,
+    query: *const c_char,
+) -> *mut MysqlPoolStatement {
+    if pool_ptr.is_null() {
+        return std::ptr::null_mut();
+    }
+    let query = match ptr_to_string(query) {
+        Ok(query) if !query.trim().is_empty() => query,
+        _ => return std::ptr::null_mut(),
+    };
+    if unsafe { &*pool_ptr }.read_only && is_write_statement(&query) {
+        return std::ptr::null_mut();
+    }
+    Box::into_raw(Box::new(MysqlPoolStatement {
+        pool: pool_ptr,
+        query,
+        prepared: Arc::default(),
+    }))
+}
+
+/// Executes a pool statement on any available connection.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_stmt_exec(
+    stmt_ptr: *mut MysqlPoolStatement,
+    params_ptr: *const c_uchar,
+    params_len: c_int,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if stmt_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid statement pointer");
+        return;
+    }
+    let stmt_ref = unsafe { &*stmt_ptr };
+    let pool_ref = unsafe { &*stmt_ref.pool };
+    let query = stmt_ref.query.clone();
+    let prepared = stmt_ref.prepared.clone();
+    let params_owned = ptr_to_vec(params_ptr, params_len);
+    let pool = pool_ref.pool();
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let params_pos = parse_params!(params_owned, cb, req_id);
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        let stmt = unwrap_or_return!(conn.prep(query).await, cb, req_id);
+        prepared
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .record(conn.id(), stmt.id());
+        let result = unwrap_or_return!(conn.exec_iter(&stmt, params_pos).await, cb, req_id);
+        let mut buf = unwrap_or_return!(encode_query_result(result).await, cb, req_id);
+        finish_result(
+            &mut buf,
+            conn.affected_rows(),
+            conn.last_insert_id().unwrap_or(0),
+        );
+        unwrap_or_return!(release_statement(&mut conn, stmt).await, cb, req_id);
         send_response(&cb, req_id, buf);
     });
 }
 
+/// Returns how many times the statement has been prepared on the server.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_stmt_prepare_count(stmt_ptr: *mut MysqlPoolStatement) -> u64 {
+    if stmt_ptr.is_null() {
+        return 0;
+    }
+    unsafe { &*stmt_ptr }
+        .prepared
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .count()
+}
+
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_stmt_destroy(stmt_ptr: *mut MysqlPoolStatement) {
+    if !stmt_ptr.is_null() {
+        unsafe {
+            let _ = Box::from_raw(stmt_ptr);
+        }
+    }
+}
+
+/// Closes a statement prepared on a long-lived connection when the statement cache is disabled,
+/// since the driver would otherwise keep it allocated on the server until the
+/// connection closes.
+async fn release_statement(conn: &mut Conn, stmt: Statement) -> mysql_async::Result<()> {
+    if conn.opts().stmt_cache_size() == 0 {
+        conn.close(stmt).await?;
+    }
+    Ok(())
+}
+
+fn in_transaction(conn: &Conn) -> bool {
+    conn.last_ok_packet()
+        .map(|ok| {
+            ok.status_flags()
+                .contains(StatusFlags::SERVER_STATUS_IN_TRANS)
+        })
+        .unwrap_or(false)
+}
+
+/// Executes a SELECT `FOR UPDATE` (0), with `NOWAIT` (1) or with `SKIP LOCKED` (2).
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_conn_query_locking(
+    conn_ptr: *mut MysqlConnection,
+    query: *const c_char,
+    params_ptr: *const c_uchar,
+    params_len: c_int,
+    lock_mode: c_int,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if conn_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid connection pointer");
+        return;
+    }
+    let cb = cb.with_last_error(&unsafe { &*conn_ptr }.last_error);
+    let query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
+    reject_if_read_only!(unsafe { &*conn_ptr }.read_only, query_str, cb, req_id);
+    let lock_clause = match lock_mode {
+        LOCK_FOR_UPDATE => "FOR UPDATE",
+        LOCK_FOR_UPDATE_NOWAIT => "FOR UPDATE NOWAIT",
+        LOCK_FOR_UPDATE_SKIP_LOCKED => "FOR UPDATE SKIP LOCKED",
+        _ => {
+            send_error(&cb, req_id, "Invalid lock mode");
+            return;
+        }
+    };
+    if !is_select_statement(&query_str) {
+        send_error(&cb, req_id, "Locking reads require a SELECT statement");
+        return;
+    }
+    let locking_query = format!("{} {}", trim_statement(&query_str), lock_clause);
+    let params_owned = ptr_to_vec(params_ptr, params_len);
+    let conn_arc = unsafe { &*conn_ptr }.conn.clone();
+
+    spawn_with_timeout(
+        unsafe { &*conn_ptr }.default_timeout,
+        cb.clone(),
+        req_id,
+        async move {
+            let params_pos = parse_params!(params_owned, cb, req_id);
+            let mut lock = conn_arc.lock().await;
+            if let Some(conn) = lock.as_mut() {
+                if !in_transaction(conn) {
+                    send_error(&cb, req_id, "Locking reads require an open transaction");
+                    return;
+                }
+                let stmt = unwrap_or_close!(conn.prep(locking_query).await, lock, cb, req_id);
+                let result =
+                    unwrap_or_close!(conn.exec_iter(&stmt, params_pos).await, lock, cb, req_id);
+                let mut buf = unwrap_or_close!(encode_query_result(result).await, lock, cb, req_id);
+                finish_result(
+                    &mut buf,
+                    conn.affected_rows(),
+                    conn.last_insert_id().unwrap_or(0),
+                );
+                unwrap_or_close!(release_statement(conn, stmt).await, lock, cb, req_id);
+                send_response(&cb, req_id, buf);
+            } else {
+                send_error(&cb, req_id, "Connection is closed");
+            }
+        },
+    );
+}
+
+/// Runs statements on a transaction connection, each in its own savepoint.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_conn_batch_statements(
+    conn_ptr: *mut MysqlConnection,
+    statements_ptr: *const c_uchar,
+    statements_len: c_int,
+    stop_on_error: bool,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if conn_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid connection pointer");
+        return;
+    }
+    let cb = cb.with_last_error(&unsafe { &*conn_ptr }.last_error);
+    let data = ptr_to_vec(statements_ptr, statements_len);
+    let statements = unwrap_or_return!(parse_statement_list(&data), cb, req_id);
+    let conn_ref = unsafe { &*conn_ptr };
+    for (query, _) in &statements {
+        reject_if_read_only!(conn_ref.read_only, query, cb, req_id);
+    }
+    let conn_arc = conn_ref.conn.clone();
+
+    spawn_with_timeout(conn_ref.default_timeout, cb.clone(), req_id, async move {
+        let mut lock = conn_arc.lock().await;
+        let Some(conn) = lock.as_mut() else {
+            send_error(&cb, req_id, "Connection is closed");
+            return;
+        };
+        if !in_transaction(conn) {
+            send_error(&cb, req_id, "Statement batches require an open transaction");
+            return;
+        }
+        let mut results = Vec::with_capacity(statements.len());
+        for (index, (query, params)) in statements.into_iter().enumerate() {
+            let savepoint = format!("__turbo_batch_{}", index);
+            unwrap_or_return!(
+                conn.query_drop(format!("SAVEPOINT {}", savepoint)).await,
+                cb,
+                req_id
+            );
+            let params = if params.is_empty() {
+                Params::Empty
+            } else {
+                Params::Positional(params)
+            };
+            match conn.exec(query, params).await {
+                Ok(rows) => results.push(Ok((
+                    rows,
+                    conn.affected_rows(),
+                    conn.last_insert_id().unwrap_or(0),
+                ))),
+                Err(e) => {
+                    unwrap_or_return!(
+                        conn.query_drop(format!("ROLLBACK TO SAVEPOINT {}", savepoint))
+                            .await,
+                        cb,
+                        req_id
+                    );
+                    results.push(Err(e.to_string()));
+                }
+            }
+            unwrap_or_return!(
+                conn.query_drop(format!("RELEASE SAVEPOINT {}", savepoint))
+                    .await,
+                cb,
+                req_id
+            );
+            if stop_on_error && results.last().is_some_and(Result::is_err) {
+                break;
+            }
+        }
+        send_response(&cb, req_id, serialize_statement_results(results));
+    });
+}
+
+/// Toggles the foreign key, unique and, outside transactions, binary log checks.
 #[unsafe(no_mangle)]
-pub extern "C" fn mysql_pool_get_connection(
-    pool_ptr: *mut MysqlPool,
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_conn_set_import_mode(
+    conn_ptr: *mut MysqlConnection,
+    enabled: bool,
     req_id: c_longlong,
     callback: CallbackType,
 ) {
-    let cb = CallbackWrapper(callback);
-    if pool_ptr.is_null() {
-        send_error(&cb, req_id, "Invalid pointers");
+    let cb = CallbackWrapper::new(callback);
+    if conn_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid connection pointer");
         return;
     }
-    let pool = unsafe { &*pool_ptr }.pool.clone();
-    get_runtime().spawn(async move {
-        let conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
-
-        let ptr = Box::into_raw(Box::new(MysqlConnection {
-            conn: Arc::new(Mutex::new(Some(conn))),
-        }));
-
-        let mut buf = Vec::new();
-        buf.write_u8(1);
-        buf.write_u64(ptr as u64);
-        buf.write_u64(0);
-        buf.write_u32(0);
-        buf.write_u32(0);
-        send_response(&cb, req_id, buf);
-    });
+    let cb = cb.with_last_error(&unsafe { &*conn_ptr }.last_error);
+    if unsafe { &*conn_ptr }.read_only {
+        send_error(&cb, req_id, READ_ONLY_ERROR);
+        return;
+    }
+    let conn_arc = unsafe { &*conn_ptr }.conn.clone();
+    let checks = if enabled { 0 } else { 1 };
+    spawn_with_timeout(
+        unsafe { &*conn_ptr }.default_timeout,
+        cb.clone(),
+        req_id,
+        async move {
+            let mut lock = conn_arc.lock().await;
+            if let Some(conn) = lock.as_mut() {
+                let mut query = format!(
+                    "SET SESSION foreign_key_checks = {}, SESSION unique_checks = {}",
+                    checks, checks
+                );
+                if !in_transaction(conn) {
+                    query.push_str(&format!(", SESSION sql_log_bin = {}", checks));
+                }
+                unwrap_or_return!(conn.query_drop(query).await, cb, req_id);
+                send_response(&cb, req_id, serialize_result(Vec::new(), 0, 0));
+            } else {
+                send_error(&cb, req_id, "Connection is closed");
+            }
+        },
+    );
 }
 
+/// Clears the connection's statement cache with `COM_RESET_CONNECTION`, outside transactions.
 #[unsafe(no_mangle)]
-pub extern "C" fn mysql_conn_query_raw(
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_conn_reset_stmt_cache(
     conn_ptr: *mut MysqlConnection,
-    query: *const c_char,
     req_id: c_longlong,
     callback: CallbackType,
 ) {
-    let cb = CallbackWrapper(callback);
+    let cb = CallbackWrapper::new(callback);
     if conn_ptr.is_null() {
         send_error(&cb, req_id, "Invalid connection pointer");
         return;
     }
-    let query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
+    let cb = cb.with_last_error(&unsafe { &*conn_ptr }.last_error);
     let conn_arc = unsafe { &*conn_ptr }.conn.clone();
-
-    get_runtime().spawn(async move {
-        let mut lock = conn_arc.lock().await;
-        if let Some(conn) = lock.as_mut() {
-            let rows = unwrap_or_return!(conn.query(query_str).await, cb, req_id);
-            send_response(
-                &cb,
-                req_id,
-                serialize_result(
-                    rows,
-                    conn.affected_rows(),
-                    conn.last_insert_id().unwrap_or(0),
-                ),
-            );
-        } else {
-            send_error(&cb, req_id, "Connection is closed");
-        }
-    });
+    spawn_with_timeout(
+        unsafe { &*conn_ptr }.default_timeout,
+        cb.clone(),
+        req_id,
+        async move {
+            let mut lock = conn_arc.lock().await;
+            if let Some(conn) = lock.as_mut() {
+                if in_transaction(conn) {
+                    send_error(
+                        &cb,
+                        req_id,
+                        "Cannot reset the statement cache inside a transaction",
+                    );
+                    return;
+                }
+                if !unwrap_or_return!(conn.reset().await, cb, req_id) {
+                    send_error(&cb, req_id, "Server does not support COM_RESET_CONNECTION");
+                    return;
+                }
+                send_response(&cb, req_id, serialize_result(Vec::new(), 0, 0));
+            } else {
+                send_error(&cb, req_id, "Connection is closed");
+            }
+        },
+    );
 }
 
+/// Resets the connection's session, rolling back any open transaction.
 #[unsafe(no_mangle)]
-pub extern "C" fn mysql_conn_query(
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_conn_reset(
     conn_ptr: *mut MysqlConnection,
-    query: *const c_char,
-    params_ptr: *const c_uchar,
-    params_len: c_int,
     req_id: c_longlong,
     callback: CallbackType,
 ) {
-    let cb = CallbackWrapper(callback);
+    let cb = CallbackWrapper::new(callback);
     if conn_ptr.is_null() {
         send_error(&cb, req_id, "Invalid connection pointer");
         return;
     }
-    let query_str = unwrap_or_return!(ptr_to_string(query), cb, req_id);
-    let params_owned = ptr_to_vec(params_ptr, params_len);
+    let cb = cb.with_last_error(&unsafe { &*conn_ptr }.last_error);
     let conn_arc = unsafe { &*conn_ptr }.conn.clone();
-
-    get_runtime().spawn(async move {
-        let params_pos = parse_params!(params_owned);
-        let mut lock = conn_arc.lock().await;
-        if let Some(conn) = lock.as_mut() {
-            let rows = unwrap_or_return!(conn.exec(query_str, params_pos).await, cb, req_id);
-            send_response(
-                &cb,
-                req_id,
-                serialize_result(
-                    rows,
-                    conn.affected_rows(),
-                    conn.last_insert_id().unwrap_or(0),
-                ),
-            );
-        } else {
-            send_error(&cb, req_id, "Connection is closed");
-        }
-    });
+    spawn_with_timeout(
+        unsafe { &*conn_ptr }.default_timeout,
+        cb.clone(),
+        req_id,
+        async move {
+            let mut lock = conn_arc.lock().await;
+            if let Some(conn) = lock.as_mut() {
+                if !unwrap_or_close!(conn.reset().await, lock, cb, req_id) {
+                    unwrap_or_close!(
+                        conn.change_user(ChangeUserOpts::default()).await,
+                        lock,
+                        cb,
+                        req_id
+                    );
+                }
+                send_response(&cb, req_id, serialize_result(Vec::new(), 0, 0));
+            } else {
+                send_error(&cb, req_id, "Connection is closed");
+            }
+        },
+    );
 }
 
 #[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub extern "C" fn mysql_conn_commit(
     conn_ptr: *mut MysqlConnection,
     req_id: c_longlong,
     callback: CallbackType,
 ) {
-    let cb = CallbackWrapper(callback);
+    let cb = CallbackWrapper::new(callback);
     if conn_ptr.is_null() {
         send_error(&cb, req_id, "Invalid connection pointer");
         return;
     }
+    let cb = cb.with_last_error(&unsafe { &*conn_ptr }.last_error);
     let conn_arc = unsafe { &*conn_ptr }.conn.clone();
-    get_runtime().spawn(async move {
-        let mut lock = conn_arc.lock().await;
-        if let Some(conn) = lock.as_mut() {
-            unwrap_or_return!(conn.query_drop("COMMIT").await, cb, req_id);
-            send_response(&cb, req_id, serialize_result(Vec::new(), 0, 0));
-        } else {
-            send_error(&cb, req_id, "Connection is closed");
-        }
-    });
+    spawn_with_timeout(
+        unsafe { &*conn_ptr }.default_timeout,
+        cb.clone(),
+        req_id,
+        async move {
+            let mut lock = conn_arc.lock().await;
+            if let Some(conn) = lock.as_mut() {
+                unwrap_or_close!(conn.query_drop("COMMIT").await, lock, cb, req_id);
+                send_response(&cb, req_id, serialize_result(Vec::new(), 0, 0));
+            } else {
+                send_error(&cb, req_id, "Connection is closed");
+            }
+        },
+    );
 }
 
 #[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub extern "C" fn mysql_conn_rollback(
     conn_ptr: *mut MysqlConnection,
     req_id: c_longlong,
     callback: CallbackType,
 ) {
-    let cb = CallbackWrapper(callback);
+    let cb = CallbackWrapper::new(callback);
     if conn_ptr.is_null() {
         send_error(&cb, req_id, "Invalid connection pointer");
         return;
     }
+    let cb = cb.with_last_error(&unsafe { &*conn_ptr }.last_error);
     let conn_arc = unsafe { &*conn_ptr }.conn.clone();
-    get_runtime().spawn(async move {
-        let mut lock = conn_arc.lock().await;
-        if let Some(conn) = lock.as_mut() {
-            unwrap_or_return!(conn.query_drop("ROLLBACK").await, cb, req_id);
-            send_response(&cb, req_id, serialize_result(Vec::new(), 0, 0));
-        } else {
-            send_error(&cb, req_id, "Connection is closed");
-        }
-    });
+    spawn_with_timeout(
+        unsafe { &*conn_ptr }.default_timeout,
+        cb.clone(),
+        req_id,
+        async move {
+            let mut lock = conn_arc.lock().await;
+            if let Some(conn) = lock.as_mut() {
+                unwrap_or_close!(conn.query_drop("ROLLBACK").await, lock, cb, req_id);
+                send_response(&cb, req_id, serialize_result(Vec::new(), 0, 0));
+            } else {
+                send_error(&cb, req_id, "Connection is closed");
+            }
+        },
+    );
+}
+
+/// Batch execution modes accepted by `mysql_pool_batch_insert` and
+/// `mysql_pool_batch_upsert`.
+const BATCH_MODE_EXTENDED: c_uchar = 0;
+const BATCH_MODE_PREPARED: c_uchar = 1;
+
+/// Maps a batch execution mode to [`BatchOptions::per_row`].
+fn batch_per_row(mode: c_uchar) -> Result<bool, String> {
+    match mode {
+        BATCH_MODE_EXTENDED => Ok(false),
+        BATCH_MODE_PREPARED => Ok(true),
+        _ => Err(format!("Unknown batch mode {}", mode)),
+    }
+}
+
+/// Reads an optional comma-separated column list, empty for a NULL pointer.
+fn optional_column_list(ptr: *const c_char) -> Result<Vec<String>, String> {
+    if ptr.is_null() {
+        return Ok(Vec::new());
+    }
+    Ok(ptr_to_string(ptr)?
+        .split(',')
+        .filter(|c| !c.trim().is_empty())
+        .map(str::to_owned)
+        .collect())
+}
+
+/// Reads the insert options of a batch call, NULL for the defaults.
+fn read_batch_insert_options(
+    options: *const MysqlBatchInsertOptions,
+) -> Result<BatchOptions, String> {
+    let options = read_call_options(options)?;
+    Ok(BatchOptions {
+        dedup_key: optional_column_list(options.dedup_columns)?,
+        default_on_null: optional_column_list(options.default_columns)?,
+        type_hints: parse_type_hints(&ptr_to_vec(options.type_hints, options.type_hints_len))?,
+        per_row: batch_per_row(options.mode)?,
+        ..BatchOptions::default()
+    })
 }
 
+const BATCH_UPSERT: BatchOptions = BatchOptions {
+    on_duplicate: true,
+    stream: false,
+    dedup_key: Vec::new(),
+    type_hints: Vec::new(),
+    per_row: false,
+    default_on_null: Vec::new(),
+    step_timeout: None,
+};
+
 pub(crate) async fn internal_conn_batch_execute(
     conn_arc: Arc<Mutex<Option<mysql_async::Conn>>>,
     table_str: String,
@@ -401,30 +4266,31 @@ pub(crate) async fn internal_conn_batch_execute(
     data: Vec<u8>,
     req_id: c_longlong,
     cb: CallbackWrapper,
-    on_duplicate: bool,
+    opts: BatchOptions,
 ) {
     let mut lock = conn_arc.lock().await;
     if let Some(conn) = lock.as_mut() {
-        execute_batch!(conn, table_str, columns_str, data, req_id, cb, on_duplicate);
+        execute_batch!(conn, table_str, columns_str, data, req_id, cb, opts);
     } else {
         send_error(&cb, req_id, "Connection is closed");
     }
 }
 
 pub(crate) async fn internal_pool_batch_execute(
-    pool: Pool,
+    pool: PoolHandle,
     table_str: String,
     columns_str: String,
     data: Vec<u8>,
     req_id: c_longlong,
     cb: CallbackWrapper,
-    on_duplicate: bool,
+    opts: BatchOptions,
 ) {
     let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
-    execute_batch!(conn, table_str, columns_str, data, req_id, cb, on_duplicate);
+    execute_batch!(conn, table_str, columns_str, data, req_id, cb, opts);
 }
 
 #[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub extern "C" fn mysql_conn_batch_insert(
     conn_ptr: *mut MysqlConnection,
     table: *const c_char,
@@ -434,22 +4300,37 @@ pub extern "C" fn mysql_conn_batch_insert(
     req_id: c_longlong,
     callback: CallbackType,
 ) {
-    let cb = CallbackWrapper(callback);
+    let cb = CallbackWrapper::new(callback);
     if conn_ptr.is_null() {
         send_error(&cb, req_id, "Invalid connection pointer");
         return;
     }
+    let cb = cb.with_last_error(&unsafe { &*conn_ptr }.last_error);
     let table_str = unwrap_or_return!(ptr_to_string(table), cb, req_id);
     let columns_str = unwrap_or_return!(ptr_to_string(columns), cb, req_id);
     let data = ptr_to_vec(data_ptr, data_len);
-    let conn_arc = unsafe { &*conn_ptr }.conn.clone();
-    get_runtime().spawn(async move {
-        internal_conn_batch_execute(conn_arc, table_str, columns_str, data, req_id, cb, false)
-            .await;
+    let conn_ref = unsafe { &*conn_ptr };
+    if conn_ref.read_only {
+        send_error(&cb, req_id, READ_ONLY_ERROR);
+        return;
+    }
+    let conn_arc = conn_ref.conn.clone();
+    spawn_with_timeout(conn_ref.default_timeout, cb.clone(), req_id, async move {
+        internal_conn_batch_execute(
+            conn_arc,
+            table_str,
+            columns_str,
+            data,
+            req_id,
+            cb,
+            BatchOptions::default(),
+        )
+        .await;
     });
 }
 
 #[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub extern "C" fn mysql_conn_batch_upsert(
     conn_ptr: *mut MysqlConnection,
     table: *const c_char,
@@ -459,69 +4340,329 @@ pub extern "C" fn mysql_conn_batch_upsert(
     req_id: c_longlong,
     callback: CallbackType,
 ) {
-    let cb = CallbackWrapper(callback);
+    let cb = CallbackWrapper::new(callback);
     if conn_ptr.is_null() {
         send_error(&cb, req_id, "Invalid connection pointer");
         return;
     }
+    let cb = cb.with_last_error(&unsafe { &*conn_ptr }.last_error);
     let table_str = unwrap_or_return!(ptr_to_string(table), cb, req_id);
     let columns_str = unwrap_or_return!(ptr_to_string(columns), cb, req_id);
     let data = ptr_to_vec(data_ptr, data_len);
-    let conn_arc = unsafe { &*conn_ptr }.conn.clone();
-    get_runtime().spawn(async move {
-        internal_conn_batch_execute(conn_arc, table_str, columns_str, data, req_id, cb, true).await;
+    let conn_ref = unsafe { &*conn_ptr };
+    if conn_ref.read_only {
+        send_error(&cb, req_id, READ_ONLY_ERROR);
+        return;
+    }
+    let conn_arc = conn_ref.conn.clone();
+    spawn_with_timeout(conn_ref.default_timeout, cb.clone(), req_id, async move {
+        internal_conn_batch_execute(
+            conn_arc,
+            table_str,
+            columns_str,
+            data,
+            req_id,
+            cb,
+            BATCH_UPSERT,
+        )
+        .await;
     });
 }
 
+/// Inserts many rows with the per-call `options`, NULL for the defaults.
 #[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub extern "C" fn mysql_pool_batch_insert(
     pool_ptr: *mut MysqlPool,
     table: *const c_char,
     columns: *const c_char,
+    options: *const MysqlBatchInsertOptions,
     data_ptr: *const c_uchar,
     data_len: c_int,
     req_id: c_longlong,
     callback: CallbackType,
 ) {
-    let cb = CallbackWrapper(callback);
+    let cb = CallbackWrapper::new(callback);
     if pool_ptr.is_null() {
         send_error(&cb, req_id, "Invalid pointers");
         return;
     }
     let table_str = unwrap_or_return!(ptr_to_string(table), cb, req_id);
     let columns_str = unwrap_or_return!(ptr_to_string(columns), cb, req_id);
+    let opts = unwrap_or_return!(read_batch_insert_options(options), cb, req_id);
     let data = ptr_to_vec(data_ptr, data_len);
-    let pool = unsafe { &*pool_ptr }.pool.clone();
-    get_runtime().spawn(async move {
-        internal_pool_batch_execute(pool, table_str, columns_str, data, req_id, cb, false).await;
+    let pool_ref = unsafe { &*pool_ptr };
+    if pool_ref.read_only {
+        send_error(&cb, req_id, READ_ONLY_ERROR);
+        return;
+    }
+    let pool = pool_ref.pool();
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        internal_pool_batch_execute(pool, table_str, columns_str, data, req_id, cb, opts).await;
     });
 }
 
+/// Upserts many rows like `mysql_pool_batch_insert`, without dedup or default columns.
 #[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub extern "C" fn mysql_pool_batch_upsert(
     pool_ptr: *mut MysqlPool,
     table: *const c_char,
     columns: *const c_char,
+    options: *const MysqlBatchInsertOptions,
+    data_ptr: *const c_uchar,
+    data_len: c_int,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let table_str = unwrap_or_return!(ptr_to_string(table), cb, req_id);
+    let columns_str = unwrap_or_return!(ptr_to_string(columns), cb, req_id);
+    let options = unwrap_or_return!(read_call_options(options), cb, req_id);
+    if !options.dedup_columns.is_null() || !options.default_columns.is_null() {
+        send_error(
+            &cb,
+            req_id,
+            "dedup_columns and default_columns are not supported by upserts",
+        );
+        return;
+    }
+    let type_hints = unwrap_or_return!(
+        parse_type_hints(&ptr_to_vec(options.type_hints, options.type_hints_len)),
+        cb,
+        req_id
+    );
+    let per_row = unwrap_or_return!(batch_per_row(options.mode), cb, req_id);
+    let data = ptr_to_vec(data_ptr, data_len);
+    let pool_ref = unsafe { &*pool_ptr };
+    if pool_ref.read_only {
+        send_error(&cb, req_id, READ_ONLY_ERROR);
+        return;
+    }
+    let pool = pool_ref.pool();
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        internal_pool_batch_execute(
+            pool,
+            table_str,
+            columns_str,
+            data,
+            req_id,
+            cb,
+            BatchOptions {
+                type_hints,
+                per_row,
+                ..BATCH_UPSERT
+            },
+        )
+        .await;
+    });
+}
+
+/// Updates many rows to distinct values with one `CASE` statement per chunk.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_batch_update(
+    pool_ptr: *mut MysqlPool,
+    table: *const c_char,
+    key_column: *const c_char,
+    value_column: *const c_char,
+    data_ptr: *const c_uchar,
+    data_len: c_int,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let table_str = unwrap_or_return!(ptr_to_string(table), cb, req_id);
+    let key_str = unwrap_or_return!(ptr_to_string(key_column), cb, req_id);
+    let value_str = unwrap_or_return!(ptr_to_string(value_column), cb, req_id);
+    let data = ptr_to_vec(data_ptr, data_len);
+    let pool_ref = unsafe { &*pool_ptr };
+    if pool_ref.read_only {
+        send_error(&cb, req_id, READ_ONLY_ERROR);
+        return;
+    }
+    let pool = pool_ref.pool();
+    spawn_with_timeout(pool_ref.default_timeout, cb.clone(), req_id, async move {
+        let mut reader = crate::utils::BinaryReader::new(&data);
+        let num_pairs =
+            unwrap_or_return!(reader.read_u32(), cb, req_id, "Failed to read pair count") as usize;
+        let mut pairs = Vec::with_capacity(num_pairs.min(data.len()));
+        for index in 0..num_pairs * 2 {
+            match crate::utils::try_parse_value(&mut reader) {
+                Ok(value) => pairs.push(value),
+                Err(message) => {
+                    let err = crate::utils::ParamError {
+                        index: index as u32,
+                        message,
+                    };
+                    send_param_error(&cb, req_id, &err);
+                    return;
+                }
+            }
+        }
+        if pairs.is_empty() {
+            send_response(&cb, req_id, serialize_result(Vec::new(), 0, 0));
+            return;
+        }
+
+        let mut conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        let pairs_per_chunk = 60000 / 3;
+        let mut total_affected = 0;
+        for chunk in pairs.chunks(pairs_per_chunk * 2) {
+            let chunk_pairs = chunk.len() / 2;
+            let mut params = Vec::with_capacity(chunk_pairs * 3);
+            params.extend_from_slice(chunk);
+            params.extend(chunk.iter().step_by(2).cloned());
+            let chunk_query = format!(
+                "UPDATE {} SET {} = CASE {} {} END WHERE {} IN ({})",
+                table_str,
+                value_str,
+                key_str,
+                vec!["WHEN ? THEN ?"; chunk_pairs].join(" "),
+                key_str,
+                vec!["?"; chunk_pairs].join(",")
+            );
+            unwrap_or_return!(
+                conn.exec_drop(chunk_query, Params::Positional(params))
+                    .await,
+                cb,
+                req_id
+            );
+            total_affected += conn.affected_rows();
+        }
+        send_response(&cb, req_id, serialize_result(Vec::new(), total_affected, 0));
+    });
+}
+
+/// Inserts many rows like `mysql_pool_batch_insert`, reporting each chunk's first id
+/// and row count as it completes.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_batch_insert_stream(
+    pool_ptr: *mut MysqlPool,
+    table: *const c_char,
+    columns: *const c_char,
+    options: *const MysqlBatchInsertOptions,
     data_ptr: *const c_uchar,
     data_len: c_int,
     req_id: c_longlong,
     callback: CallbackType,
 ) {
-    let cb = CallbackWrapper(callback);
+    let cb = CallbackWrapper::new(callback);
     if pool_ptr.is_null() {
         send_error(&cb, req_id, "Invalid pointers");
         return;
     }
     let table_str = unwrap_or_return!(ptr_to_string(table), cb, req_id);
     let columns_str = unwrap_or_return!(ptr_to_string(columns), cb, req_id);
+    let opts = unwrap_or_return!(read_batch_insert_options(options), cb, req_id);
     let data = ptr_to_vec(data_ptr, data_len);
-    let pool = unsafe { &*pool_ptr }.pool.clone();
-    get_runtime().spawn(async move {
-        internal_pool_batch_execute(pool, table_str, columns_str, data, req_id, cb, true).await;
+    let pool_ref = unsafe { &*pool_ptr };
+    if pool_ref.read_only {
+        send_error(&cb, req_id, READ_ONLY_ERROR);
+        return;
+    }
+    let pool = pool_ref.pool();
+    let opts = BatchOptions {
+        stream: true,
+        step_timeout: pool_ref.default_timeout,
+        ..opts
+    };
+    spawn_with_timeout(None, cb.clone(), req_id, async move {
+        internal_pool_batch_execute(pool, table_str, columns_str, data, req_id, cb, opts).await;
+    });
+}
+
+/// MySQL error raised when the account lacks a required global privilege.
+#[cfg(feature = "binlog")]
+const ER_SPECIFIC_ACCESS_DENIED: u16 = 1227;
+
+/// Streams binlog events as replica `server_id` until the server or `token` ends it.
+#[cfg(feature = "binlog")]
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_pool_binlog_stream(
+    pool_ptr: *mut MysqlPool,
+    server_id: c_uint,
+    start_file: *const c_char,
+    start_pos: std::os::raw::c_ulonglong,
+    token: *mut MysqlCancelToken,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    use futures_util::StreamExt;
+    use mysql_async::BinlogStreamRequest;
+
+    let cb = CallbackWrapper::new(callback);
+    if pool_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid pointers");
+        return;
+    }
+    let start_file = if start_file.is_null() {
+        String::new()
+    } else {
+        unwrap_or_return!(ptr_to_string(start_file), cb, req_id)
+    };
+    let token = if token.is_null() {
+        Arc::default()
+    } else {
+        unsafe { &*token }.0.clone()
+    };
+    let pool = unsafe { &*pool_ptr }.pool();
+    unwrap_or_return!(get_runtime(), cb, req_id).spawn(async move {
+        let conn = unwrap_or_return!(pool.get_conn().await, cb, req_id);
+        let request = BinlogStreamRequest::new(server_id)
+            .with_filename(start_file.as_bytes())
+            .with_pos(start_pos);
+        let mut stream = match conn.get_binlog_stream(request).await {
+            Ok(stream) => stream,
+            Err(mysql_async::Error::Server(e)) if e.code == ER_SPECIFIC_ACCESS_DENIED => {
+                report_error(
+                    &cb,
+                    req_id,
+                    ERROR_SERVER,
+                    e.code,
+                    &format!(
+                        "Binlog stream requires the REPLICATION SLAVE and REPLICATION CLIENT privileges: {}",
+                        e.message
+                    ),
+                );
+                return;
+            }
+            Err(e) => {
+                send_categorized_error(&cb, req_id, &e);
+                return;
+            }
+        };
+        let mut forwarded = 0u64;
+        while let Some(next) = token.run(stream.next()).await {
+            let Some(event) = next else {
+                break;
+            };
+            let event = unwrap_or_return!(event, cb, req_id);
+            if let Some(frame) =
+                unwrap_or_return!(encode_binlog_event(&stream, &event), cb, req_id)
+            {
+                send_response(&cb, req_id, frame);
+                forwarded += 1;
+            }
+        }
+        // Best effort: the connection is closed either way.
+        let _ = stream.close().await;
+        send_response(&cb, req_id, serialize_result(Vec::new(), forwarded, 0));
     });
 }
 
 #[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub extern "C" fn mysql_stmt_execute(
     stmt_ptr: *mut MysqlPreparedStatement,
     params_ptr: *const c_uchar,
@@ -529,36 +4670,142 @@ pub extern "C" fn mysql_stmt_execute(
     req_id: c_longlong,
     callback: CallbackType,
 ) {
-    let cb = CallbackWrapper(callback);
+    let cb = CallbackWrapper::new(callback);
     if stmt_ptr.is_null() {
         send_error(&cb, req_id, "Invalid statement pointer");
         return;
     }
+    let cb = cb.with_last_error(&unsafe { &*stmt_ptr }.last_error);
     let stmt_ref = unsafe { &*stmt_ptr };
     let conn_arc = stmt_ref.conn.clone();
     let stmt = stmt_ref.stmt.clone();
     let params_owned = ptr_to_vec(params_ptr, params_len);
-    get_runtime().spawn(async move {
-        let params_pos = parse_params!(params_owned);
+    spawn_with_timeout(stmt_ref.default_timeout, cb.clone(), req_id, async move {
+        let params_pos = parse_params!(params_owned, cb, req_id);
         let mut lock = conn_arc.lock().await;
         if let Some(conn) = lock.as_mut() {
-            let rows = unwrap_or_return!(conn.exec(stmt, params_pos).await, cb, req_id);
-            send_response(
-                &cb,
-                req_id,
-                serialize_result(
-                    rows,
-                    conn.affected_rows(),
-                    conn.last_insert_id().unwrap_or(0),
-                ),
+            let result = unwrap_or_return!(conn.exec_iter(stmt, params_pos).await, cb, req_id);
+            let mut buf = unwrap_or_return!(encode_query_result(result).await, cb, req_id);
+            finish_result(
+                &mut buf,
+                conn.affected_rows(),
+                conn.last_insert_id().unwrap_or(0),
             );
+            send_response(&cb, req_id, buf);
         } else {
             send_error(&cb, req_id, "Connection is closed");
         }
     });
 }
 
+/// Executes a prepared statement, streaming rows in chunks of at most `batch_size`.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_stmt_execute_stream(
+    stmt_ptr: *mut MysqlPreparedStatement,
+    params_ptr: *const c_uchar,
+    params_len: c_int,
+    batch_size: c_uint,
+    req_id: c_longlong,
+    callback: CallbackType,
+) {
+    let cb = CallbackWrapper::new(callback);
+    if stmt_ptr.is_null() {
+        send_error(&cb, req_id, "Invalid statement pointer");
+        return;
+    }
+    let cb = cb.with_last_error(&unsafe { &*stmt_ptr }.last_error);
+    if batch_size == 0 {
+        send_error(&cb, req_id, "Batch size must be positive");
+        return;
+    }
+    let stmt_ref = unsafe { &*stmt_ptr };
+    let conn_arc = stmt_ref.conn.clone();
+    let stmt = stmt_ref.stmt.clone();
+    let params_owned = ptr_to_vec(params_ptr, params_len);
+    let step = stmt_ref.default_timeout;
+    spawn_with_timeout(None, cb.clone(), req_id, async move {
+        let params_pos = parse_params!(params_owned, cb, req_id);
+        let mut lock = conn_arc.lock().await;
+        let Some(conn) = lock.as_mut() else {
+            send_error(&cb, req_id, "Connection is closed");
+            return;
+        };
+        let mut result = unwrap_or_return!(
+            timed(step, conn.exec_iter(stmt, params_pos)).await,
+            cb,
+            req_id
+        );
+        let mut encoder = RowChunkEncoder::default();
+        if let Some(columns) = result.columns().filter(|cols| !cols.is_empty()) {
+            encoder.set_columns(columns);
+        }
+        while let Some(row) = unwrap_or_return!(timed(step, result.next()).await, cb, req_id) {
+            encoder.push(&row);
+            if encoder.pending() >= batch_size
+                && let Some(frame) = encoder.take_frame()
+            {
+                send_response(&cb, req_id, frame);
+            }
+        }
+        if let Some(frame) = encoder.take_frame() {
+            send_response(&cb, req_id, frame);
+        }
+        let affected = result.affected_rows();
+        let last_id = result.last_insert_id().unwrap_or(0);
+        unwrap_or_return!(timed(step, result.drop_result()).await, cb, req_id);
+        send_response(&cb, req_id, serialize_result(Vec::new(), affected, last_id));
+    });
+}
+
+/// Returns the last error message or NULL, released with `mysql_free_string`.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_conn_last_error(conn_ptr: *mut MysqlConnection) -> *mut c_char {
+    if conn_ptr.is_null() {
+        return std::ptr::null_mut();
+    }
+    unsafe { &*conn_ptr }
+        .last_error
+        .message()
+        .map_or(std::ptr::null_mut(), into_c_string)
+}
+
+/// Returns the last server error code, or 0.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_conn_last_error_code(conn_ptr: *mut MysqlConnection) -> c_uint {
+    if conn_ptr.is_null() {
+        return 0;
+    }
+    unsafe { &*conn_ptr }.last_error.code() as c_uint
+}
+
+/// Returns the last error message or NULL, released with `mysql_free_string`.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_stmt_last_error(stmt_ptr: *mut MysqlPreparedStatement) -> *mut c_char {
+    if stmt_ptr.is_null() {
+        return std::ptr::null_mut();
+    }
+    unsafe { &*stmt_ptr }
+        .last_error
+        .message()
+        .map_or(std::ptr::null_mut(), into_c_string)
+}
+
+/// Returns the last server error code, or 0.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_stmt_last_error_code(stmt_ptr: *mut MysqlPreparedStatement) -> c_uint {
+    if stmt_ptr.is_null() {
+        return 0;
+    }
+    unsafe { &*stmt_ptr }.last_error.code() as c_uint
+}
+
 #[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub extern "C" fn mysql_stmt_destroy(stmt_ptr: *mut MysqlPreparedStatement) {
     if !stmt_ptr.is_null() {
         unsafe {
@@ -568,10 +4815,11 @@ pub extern "C" fn mysql_stmt_destroy(stmt_ptr: *mut MysqlPreparedStatement) {
 }
 
 #[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub extern "C" fn mysql_conn_destroy(conn_ptr: *mut MysqlConnection) {
     if !conn_ptr.is_null() {
         unsafe {
             let _ = Box::from_raw(conn_ptr);
         }
     }
-}
\ No newline at end of file
+}
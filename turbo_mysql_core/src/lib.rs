@@ -2,26 +2,265 @@ pub mod types;
 #[macro_use]
 pub mod utils;
 pub mod ffi;
+#[cfg(feature = "protobuf")]
+pub mod protobuf;
 
 use mimalloc::MiMalloc;
-use std::os::raw::{c_int, c_uchar};
-use std::sync::OnceLock;
+use std::os::raw::{c_int, c_longlong, c_uchar, c_ulonglong};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Sender, channel};
+use std::sync::{OnceLock, PoisonError, RwLock};
+use std::time::Duration;
 use tokio::runtime::Runtime;
+use types::CallbackType;
 
 /// Global allocator using mimalloc for optimized memory management.
 #[global_allocator]
 static GLOBAL: MiMalloc = MiMalloc;
 
-/// Global storage for the Tokio asynchronous runtime.
-pub static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+/// Global storage for the Tokio asynchronous runtime, or the reason it could not be created.
+pub static RUNTIME: OnceLock<Result<Runtime, String>> = OnceLock::new();
+
+/// Makes [`get_runtime`] fail as if the runtime could not be created.
+#[cfg(feature = "test-hooks")]
+pub static SIMULATE_RUNTIME_FAILURE: AtomicBool = AtomicBool::new(false);
+
+/// Distinct threads that invoked a callback since the count was last taken.
+#[cfg(feature = "test-hooks")]
+static CALLBACK_THREADS: std::sync::Mutex<Vec<std::thread::ThreadId>> =
+    std::sync::Mutex::new(Vec::new());
+
+/// Connection attempts left to fail with a simulated connection-level error.
+#[cfg(feature = "test-hooks")]
+static SIMULATED_CONNECT_FAILURES: std::sync::atomic::AtomicU32 =
+    std::sync::atomic::AtomicU32::new(0);
+
+/// Consumes one simulated connect failure, if any are pending.
+#[cfg(feature = "test-hooks")]
+pub fn simulated_connect_failure() -> Option<mysql_async::Error> {
+    SIMULATED_CONNECT_FAILURES
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1))
+        .ok()?;
+    let err = std::io::Error::from(std::io::ErrorKind::ConnectionRefused);
+    Some(mysql_async::Error::Io(err.into()))
+}
+
+/// Records the current thread as one that invoked a callback.
+#[cfg(feature = "test-hooks")]
+pub fn record_callback_thread() {
+    let id = std::thread::current().id();
+    let mut threads = CALLBACK_THREADS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    if !threads.contains(&id) {
+        threads.push(id);
+    }
+}
 
 /// Retrieves the global Tokio runtime, initializing it if necessary.
-pub fn get_runtime() -> &'static Runtime {
-    RUNTIME.get_or_init(|| Runtime::new().expect("Failed to create Tokio runtime"))
+///
+/// Initialization failure is reported as an error instead of panicking, since
+/// a panic must never unwind across the FFI boundary.
+pub fn get_runtime() -> Result<&'static Runtime, &'static str> {
+    #[cfg(feature = "test-hooks")]
+    if SIMULATE_RUNTIME_FAILURE.load(Ordering::Relaxed) {
+        return Err("Tokio runtime unavailable: simulated failure");
+    }
+    RUNTIME
+        .get_or_init(|| Runtime::new().map_err(|e| format!("Tokio runtime unavailable: {}", e)))
+        .as_ref()
+        .map_err(String::as_str)
+}
+
+/// Work queued for the dispatcher thread.
+pub enum Dispatch {
+    /// A callback invocation.
+    Frame(CallbackType, c_longlong, utils::Frame),
+    /// Acknowledged once every invocation queued before it has run.
+    Barrier(Sender<()>),
+}
+
+/// Routes every callback through the dispatcher thread when set.
+pub static SINGLE_THREADED_DISPATCH: AtomicBool = AtomicBool::new(false);
+
+/// Queue of the dispatcher thread, or `None` if the thread could not be started.
+static DISPATCHER: OnceLock<Option<Sender<Dispatch>>> = OnceLock::new();
+
+/// Retrieves the queue of the callback dispatcher thread, starting it if necessary.
+pub fn get_dispatcher() -> Option<&'static Sender<Dispatch>> {
+    DISPATCHER
+        .get_or_init(|| {
+            let (tx, rx) = channel::<Dispatch>();
+            std::thread::Builder::new()
+                .name("turbo_mysql-callbacks".to_string())
+                .spawn(move || {
+                    for dispatch in rx {
+                        match dispatch {
+                            Dispatch::Frame(cb, req_id, frame) => {
+                                utils::invoke_callback(cb, req_id, frame)
+                            }
+                            Dispatch::Barrier(done) => {
+                                let _ = done.send(());
+                            }
+                        }
+                    }
+                })
+                .ok()
+                .map(|_| tx)
+        })
+        .as_ref()
+}
+
+/// Waits up to `timeout` for the dispatcher thread to invoke every callback
+/// queued so far. Returns `true` at once if the thread was never started.
+pub fn drain_dispatcher(timeout: Duration) -> bool {
+    let Some(Some(dispatcher)) = DISPATCHER.get() else {
+        return true;
+    };
+    let (done, drained) = channel();
+    dispatcher.send(Dispatch::Barrier(done)).is_ok() && drained.recv_timeout(timeout).is_ok()
+}
+
+/// Selects the thread callbacks are invoked on.
+///
+/// With `single_threaded` set, all callbacks are queued to one dedicated
+/// dispatcher thread, so the consumer always receives them on the same thread;
+/// otherwise they are invoked directly from the runtime's worker threads.
+/// Frames of one request keep their order in either mode, but switching modes
+/// while requests are in flight may reorder frames across the switch. Returns
+/// `false` if the dispatcher thread could not be started.
+#[unsafe(no_mangle)]
+pub extern "C" fn mysql_set_callback_dispatch(single_threaded: bool) -> bool {
+    if single_threaded && get_dispatcher().is_none() {
+        return false;
+    }
+    SINGLE_THREADED_DISPATCH.store(single_threaded, Ordering::Release);
+    true
+}
+
+/// Copies every response frame to the capture callback when set.
+pub static DEBUG_CAPTURE: AtomicBool = AtomicBool::new(false);
+
+/// Receiver of captured frames, registered with `mysql_set_debug_capture_callback`.
+static CAPTURE_CALLBACK: RwLock<Option<CallbackType>> = RwLock::new(None);
+
+/// Hands a copy of a response frame to the capture callback, if one is registered.
+pub fn capture_frame(req_id: c_longlong, frame: &[u8]) {
+    let callback = *CAPTURE_CALLBACK
+        .read()
+        .unwrap_or_else(PoisonError::into_inner);
+    if let Some(callback) = callback {
+        utils::invoke_callback(callback, req_id, utils::Frame::Owned(frame.into()));
+    }
+}
+
+/// Registers the callback receiving captured frames, or removes it with NULL.
+///
+/// Removing the callback also turns capturing off.
+#[unsafe(no_mangle)]
+pub extern "C" fn mysql_set_debug_capture_callback(callback: Option<CallbackType>) {
+    *CAPTURE_CALLBACK
+        .write()
+        .unwrap_or_else(PoisonError::into_inner) = callback;
+    if callback.is_none() {
+        DEBUG_CAPTURE.store(false, Ordering::Release);
+    }
+}
+
+/// Turns debug capture of response frames on or off.
+///
+/// While on, every frame sent to a response callback is first copied, byte for
+/// byte, to the capture callback with the same request id; compressed frames
+/// are captured compressed. The copy is owned by the consumer and freed with
+/// `mysql_buffer_free`. When off, responses only pay
+/// for one atomic load. Returns `false` when enabling without a registered
+/// capture callback.
+#[unsafe(no_mangle)]
+pub extern "C" fn mysql_set_debug_capture(enabled: bool) -> bool {
+    let registered = CAPTURE_CALLBACK
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .is_some();
+    if enabled && !registered {
+        return false;
+    }
+    DEBUG_CAPTURE.store(enabled, Ordering::Release);
+    true
+}
+
+/// Turns decoding `TINYINT(1)` columns as booleans on or off; off by default.
+#[unsafe(no_mangle)]
+pub extern "C" fn mysql_set_tinyint_as_bool(enabled: bool) {
+    utils::TINYINT_AS_BOOL.store(enabled, Ordering::Release);
+}
+
+/// Turns the column metadata cache on or off.
+///
+/// While on, result sets whose columns match an earlier result's names, types,
+/// charsets, flags and lengths reuse its encoded metadata block instead of
+/// deriving it again. Turning the cache off also empties it.
+#[unsafe(no_mangle)]
+pub extern "C" fn mysql_set_metadata_cache(enabled: bool) {
+    utils::METADATA_CACHE_ENABLED.store(enabled, Ordering::Release);
+    if !enabled {
+        utils::clear_metadata_cache();
+    }
+}
+
+/// Writes how many metadata blocks were served from the cache and how many
+/// were derived from the columns, counted since the library was loaded.
+/// Returns `false` if either pointer is NULL.
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn mysql_metadata_cache_stats(
+    hits: *mut c_ulonglong,
+    derivations: *mut c_ulonglong,
+) -> bool {
+    if hits.is_null() || derivations.is_null() {
+        return false;
+    }
+    unsafe {
+        *hits = utils::METADATA_CACHE_HITS.load(Ordering::Relaxed);
+        *derivations = utils::METADATA_DERIVATIONS.load(Ordering::Relaxed);
+    }
+    true
+}
+
+/// Returns the version of the response layout, which every frame carries as
+/// its first byte, so consumers can refuse a library they cannot decode.
+#[unsafe(no_mangle)]
+pub extern "C" fn mysql_protocol_version() -> c_int {
+    utils::PROTOCOL_VERSION as c_int
+}
+
+/// Makes every later call needing the runtime fail until disabled again.
+#[cfg(feature = "test-hooks")]
+#[unsafe(no_mangle)]
+pub extern "C" fn mysql_test_simulate_runtime_failure(enabled: bool) {
+    SIMULATE_RUNTIME_FAILURE.store(enabled, Ordering::Relaxed);
+}
+
+/// Makes the next `count` connection attempts of any pool fail as if the
+/// server refused them.
+#[cfg(feature = "test-hooks")]
+#[unsafe(no_mangle)]
+pub extern "C" fn mysql_test_fail_connects(count: std::os::raw::c_uint) {
+    SIMULATED_CONNECT_FAILURES.store(count, Ordering::Relaxed);
+}
+
+/// Returns how many distinct threads invoked a callback since the last call.
+#[cfg(feature = "test-hooks")]
+#[unsafe(no_mangle)]
+pub extern "C" fn mysql_test_take_callback_thread_count() -> c_int {
+    let mut threads = CALLBACK_THREADS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    std::mem::take(&mut *threads).len() as c_int
 }
 
 /// Frees a memory buffer allocated by the Rust FFI layer.
 #[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub extern "C" fn mysql_buffer_free(ptr: *mut c_uchar, len: c_int) {
     if !ptr.is_null() && len > 0 {
         unsafe {
@@ -0,0 +1,96 @@
+//! Protobuf encoding of result rows for `mysql_pool_query_protobuf`.
+//!
+//! Rows are encoded as dynamic messages of a descriptor supplied at runtime.
+//! Each result column fills the singular scalar or enum field of the same
+//! name; NULL values leave their field unset.
+
+use crate::utils::csv_text;
+use mysql_async::{Column, Row, Value as MySqlValue, from_value_opt};
+use prost_reflect::prost::Message;
+use prost_reflect::{
+    DescriptorPool, DynamicMessage, FieldDescriptor, Kind, MessageDescriptor, Value,
+};
+
+/// Looks up `message` in a serialized `FileDescriptorSet`.
+pub fn parse_descriptor(descriptor_set: &[u8], message: &str) -> Result<MessageDescriptor, String> {
+    let pool = DescriptorPool::decode(descriptor_set)
+        .map_err(|e| format!("Invalid descriptor set: {}", e))?;
+    pool.get_message_by_name(message)
+        .ok_or_else(|| format!("Descriptor set has no message {}", message))
+}
+
+/// Encodes the rows of one result set as messages of a descriptor.
+pub struct MessageEncoder {
+    message: MessageDescriptor,
+    /// The field filled from each column.
+    fields: Vec<FieldDescriptor>,
+}
+
+impl MessageEncoder {
+    /// Fails if a column has no field of its name, or that field is repeated
+    /// or holds a message.
+    pub fn new(message: &MessageDescriptor, cols: &[Column]) -> Result<Self, String> {
+        let fields = cols
+            .iter()
+            .map(|col| {
+                let field = message.get_field_by_name(&col.name_str()).ok_or_else(|| {
+                    format!(
+                        "Message {} has no field {}",
+                        message.full_name(),
+                        col.name_str()
+                    )
+                })?;
+                if field.is_list() || field.is_map() || matches!(field.kind(), Kind::Message(_)) {
+                    return Err(format!("Field {} is not a scalar field", field.full_name()));
+                }
+                Ok(field)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            message: message.clone(),
+            fields,
+        })
+    }
+
+    /// Appends `row` to `out` as a message prefixed with its varint length.
+    pub fn write_delimited(&mut self, out: &mut Vec<u8>, row: &Row) -> Result<(), String> {
+        let mut message = DynamicMessage::new(self.message.clone());
+        for (index, field) in self.fields.iter().enumerate() {
+            let val = row.as_ref(index).unwrap_or(&MySqlValue::NULL);
+            if *val == MySqlValue::NULL {
+                continue;
+            }
+            let value = field_value(field, val, &row.columns_ref()[index]).ok_or_else(|| {
+                format!(
+                    "Column {} cannot be encoded as protobuf field {}",
+                    field.name(),
+                    field.full_name()
+                )
+            })?;
+            message.set_field(field, value);
+        }
+        message
+            .encode_length_delimited(out)
+            .map_err(|e| format!("Failed to encode message: {}", e))
+    }
+}
+
+/// Converts a value to the field's type, or returns `None` if it does not fit.
+fn field_value(field: &FieldDescriptor, val: &MySqlValue, col: &Column) -> Option<Value> {
+    let signed = || from_value_opt::<i64>(val.clone()).ok();
+    let unsigned = || from_value_opt::<u64>(val.clone()).ok();
+    let float = || from_value_opt::<f64>(val.clone()).ok();
+    Some(match field.kind() {
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => Value::I64(signed()?),
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => Value::I32(signed()?.try_into().ok()?),
+        Kind::Uint64 | Kind::Fixed64 => Value::U64(unsigned()?),
+        Kind::Uint32 | Kind::Fixed32 => Value::U32(unsigned()?.try_into().ok()?),
+        Kind::Enum(_) => Value::EnumNumber(signed()?.try_into().ok()?),
+        Kind::Bool => Value::Bool(signed()? != 0),
+        Kind::Double => Value::F64(float()?),
+        Kind::Float => Value::F32(float()? as f32),
+        Kind::String => Value::String(String::from_utf8(csv_text(val, col).into_owned()).ok()?),
+        Kind::Bytes => Value::Bytes(csv_text(val, col).into_owned().into()),
+        Kind::Message(_) => return None,
+    })
+}
@@ -1,28 +1,817 @@
-use mysql_async::{Conn, Pool};
-use std::os::raw::{c_int, c_longlong, c_uchar};
-use std::sync::Arc;
-use tokio::sync::Mutex;
+use crate::utils::{RowChunkEncoder, is_connection_lost, is_server_busy, stable_hash};
+use mysql_async::{Column, Conn, Opts, OptsBuilder, Pool, Row};
+use std::collections::{HashMap, VecDeque};
+use std::ops::Deref;
+use std::os::raw::{c_char, c_int, c_longlong, c_uchar, c_uint};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, OnceLock, PoisonError, RwLock};
+use std::task::Poll;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::{Mutex, mpsc, watch};
 
 /// Represents a managed pool of MySQL connections.
 pub struct MysqlPool {
-    pub pool: Pool,
+    pool: Arc<RwLock<PoolSlot>>,
+    /// Options the pool was created with, used when the pool has to be rebuilt.
+    opts: RwLock<Opts>,
+    pub settings: PoolSettings,
+    /// Tasks spawned on behalf of this pool that have not finished yet.
+    in_flight: Arc<InFlight>,
+    gate: Arc<PauseGate>,
+    slow_query_log: RwLock<Option<Arc<SlowQueryLog>>>,
+}
+
+/// Behavior of a pool beyond its connection options, carried over as a whole
+/// to pools derived from it.
+#[derive(Default)]
+pub struct PoolSettings {
+    /// Rejects write statements before they are sent to the server.
+    pub read_only: bool,
+    pub connect_retry: ConnectRetry,
+    /// Applied to every operation issued against the pool and the connections
+    /// and statements obtained from it.
+    pub default_timeout: Option<Duration>,
+    /// Server flavor (`FLAVOR_*`), detected on first use.
+    pub flavor: Arc<OnceLock<u8>>,
+    /// Recently executed queries, when recording is enabled.
+    pub recent_queries: Option<Arc<RecentQueries>>,
+    /// Appends the InnoDB deadlock report to deadlock errors.
+    pub deadlock_diagnostics: bool,
+    /// Fails `mysql_pool_query` results carrying NULL in a column flagged `NOT NULL`.
+    pub strict_nullability: AtomicBool,
+    /// Appends execution timings to `mysql_pool_query` and `mysql_pool_query_raw` results.
+    pub report_timing: AtomicBool,
+}
+
+impl Clone for PoolSettings {
+    fn clone(&self) -> Self {
+        Self {
+            read_only: self.read_only,
+            connect_retry: self.connect_retry,
+            default_timeout: self.default_timeout,
+            flavor: self.flavor.clone(),
+            recent_queries: self.recent_queries.clone(),
+            deadlock_diagnostics: self.deadlock_diagnostics,
+            strict_nullability: AtomicBool::new(self.strict_nullability.load(Ordering::Relaxed)),
+            report_timing: AtomicBool::new(self.report_timing.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// Settings are read straight off the pool, e.g. `pool.read_only`.
+impl Deref for MysqlPool {
+    type Target = PoolSettings;
+
+    fn deref(&self) -> &PoolSettings {
+        &self.settings
+    }
+}
+
+impl MysqlPool {
+    pub fn new(opts: Opts) -> Self {
+        Self {
+            pool: Arc::new(RwLock::new(PoolSlot::new(opts.clone()))),
+            opts: RwLock::new(opts),
+            settings: PoolSettings::default(),
+            in_flight: Arc::default(),
+            gate: Arc::default(),
+            slow_query_log: RwLock::new(None),
+        }
+    }
+
+    /// Returns a pool with this pool's settings, session init statements and
+    /// slow query logger whose connections default to `db_name`.
+    ///
+    /// A driver pool is bound to the options it was built from, including the
+    /// database, so the new pool cannot share this pool's connections.
+    pub fn with_database(&self, db_name: &str) -> MysqlPool {
+        let with_db =
+            |opts: Opts| -> Opts { OptsBuilder::from_opts(opts).db_name(Some(db_name)).into() };
+        let current = self
+            .pool
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .opts
+            .clone();
+        MysqlPool {
+            pool: Arc::new(RwLock::new(PoolSlot::new(with_db(current)))),
+            opts: RwLock::new(with_db(self.opts())),
+            settings: self.settings.clone(),
+            in_flight: Arc::default(),
+            gate: Arc::default(),
+            slow_query_log: RwLock::new(self.slow_query_log()),
+        }
+    }
+
+    /// Returns the options the pool was created with, including rotated credentials.
+    pub fn opts(&self) -> Opts {
+        self.opts
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Holds back new connection requests until [`MysqlPool::resume`].
+    ///
+    /// Up to `max_queued` requests wait for the pool to resume; further ones
+    /// fail immediately. Connections already checked out are unaffected.
+    pub fn pause(&self, max_queued: usize) {
+        self.gate.max_queued.store(max_queued, Ordering::Relaxed);
+        self.gate.paused.send_replace(true);
+    }
+
+    /// Releases the requests queued by [`MysqlPool::pause`].
+    pub fn resume(&self) {
+        self.gate.paused.send_replace(false);
+    }
+
+    /// Returns the slow query logger, if one is installed.
+    pub fn slow_query_log(&self) -> Option<Arc<SlowQueryLog>> {
+        self.slow_query_log
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Installs or, with `None`, removes the slow query logger.
+    pub fn set_slow_query_log(&self, log: Option<SlowQueryLog>) {
+        *self
+            .slow_query_log
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = log.map(Arc::new);
+    }
+
+    /// Returns a handle to the current underlying pool for use by a spawned task.
+    ///
+    /// The task counts as in flight until the handle is dropped, so the handle
+    /// must be moved into the task and live until its callback has fired.
+    pub fn pool(&self) -> PoolHandle {
+        *self
+            .in_flight
+            .count
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) += 1;
+        PoolHandle {
+            pool: self
+                .pool
+                .read()
+                .unwrap_or_else(PoisonError::into_inner)
+                .pool
+                .clone(),
+            slot: self.pool.clone(),
+            retry: self.connect_retry,
+            in_flight: self.in_flight.clone(),
+            gate: self.gate.clone(),
+        }
+    }
+
+    /// Blocks until no task holds a handle from [`MysqlPool::pool`] and the
+    /// callbacks they queued for the dispatcher thread have run, or until
+    /// `timeout` elapses. Returns `true` if the pool became idle.
+    pub fn wait_idle(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut count = self
+            .in_flight
+            .count
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        while *count > 0 {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return false;
+            }
+            count = self
+                .in_flight
+                .idle
+                .wait_timeout(count, remaining)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+        drop(count);
+        crate::drain_dispatcher(deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Swaps in a new underlying pool built from `opts`.
+    ///
+    /// Connections already checked out of the previous pool keep working and are
+    /// closed once they are returned and its last handle is dropped.
+    pub fn replace_pool(&self, opts: Opts) {
+        *self.pool.write().unwrap_or_else(PoisonError::into_inner) = PoolSlot::new(opts);
+    }
+
+    /// Switches the pool to a new user and password, returning the previous
+    /// underlying pool so its connections can be drained.
+    ///
+    /// Both the creation options and those of the current pool are updated,
+    /// so later rebuilds keep the new credentials.
+    pub fn rotate_credentials(&self, user: Option<String>, pass: Option<String>) -> Pool {
+        let with_credentials = |opts: Opts| -> Opts {
+            OptsBuilder::from_opts(opts)
+                .user(user.clone())
+                .pass(pass.clone())
+                .into()
+        };
+        let mut base = self.opts.write().unwrap_or_else(PoisonError::into_inner);
+        *base = with_credentials(base.clone());
+        let mut slot = self.pool.write().unwrap_or_else(PoisonError::into_inner);
+        let next = PoolSlot::new(with_credentials(slot.opts.clone()));
+        std::mem::replace(&mut *slot, next).pool
+    }
+}
+
+/// The current underlying pool and the options it was built from.
+struct PoolSlot {
+    pool: Pool,
+    opts: Opts,
+}
+
+impl PoolSlot {
+    fn new(opts: Opts) -> Self {
+        Self {
+            pool: Pool::new(opts.clone()),
+            opts,
+        }
+    }
+}
+
+/// Options for `mysql_pool_create_with_config`, applied on top of the URL.
+///
+/// Zero, negative (`stmt_cache_size`) and NULL fields keep the URL's value or
+/// the driver default. New fields are only ever appended.
+#[repr(C)]
+pub struct MysqlPoolConfig {
+    /// Size of the struct as the caller knows it; fields past it keep their
+    /// defaults.
+    pub struct_size: usize,
+    pub url: *const c_char,
+    /// Connections the pool keeps open even when idle.
+    pub min_connections: c_uint,
+    /// Upper bound on open connections; must not be below `min_connections`.
+    pub max_connections: c_uint,
+    /// Age after which a connection is closed instead of being returned to the
+    /// pool.
+    pub max_conn_lifetime_ms: c_uint,
+    /// Idle time after which connections above `min_connections` are closed.
+    pub inactive_conn_ttl_ms: c_uint,
+    /// Per-connection statement cache size; 0 disables caching and makes the
+    /// pool reset connections on return so their statements are deallocated.
+    pub stmt_cache_size: c_int,
+    /// Retries after a connection-level failure to acquire a connection; each
+    /// retry rebuilds the pool so the host is resolved again.
+    pub connect_retries: c_uint,
+    /// Delay before the first retry, doubled for each later one.
+    pub connect_backoff_ms: c_uint,
+    /// Time limit for each query, statement execution, and batch; 0 disables it.
+    pub default_query_timeout_ms: c_uint,
+    /// PEM or DER root certificate to trust; setting it enables TLS, which
+    /// requires a TLS-enabled build of the driver.
+    pub tls_ca_path: *const c_char,
+    /// Connection character set, applied with `SET NAMES`.
+    pub charset: *const c_char,
+    /// Statements run on every connection before it is handed out.
+    pub init_sql: *const c_char,
+    /// Runs every session read-only and rejects write statements up front.
+    pub read_only: bool,
+    /// Lets idle connections live as long as the server's `interactive_timeout`
+    /// instead of `wait_timeout`, as `CLIENT_INTERACTIVE` clients do.
+    pub interactive: bool,
+    /// Number of recently executed queries kept for `mysql_pool_recent_queries`;
+    /// 0 disables recording.
+    pub recent_query_capacity: c_uint,
+    /// Appends the InnoDB report of the latest deadlock to deadlock errors;
+    /// requires the `PROCESS` privilege.
+    pub deadlock_diagnostics: bool,
+    /// Default transaction isolation level of every session, e.g. `READ
+    /// COMMITTED`; NULL keeps the server default.
+    pub isolation_level: *const c_char,
+}
+
+impl Default for MysqlPoolConfig {
+    fn default() -> Self {
+        // Every field is a number, a bool or a pointer, for which zero is the default.
+        let mut config: Self = unsafe { std::mem::zeroed() };
+        config.struct_size = std::mem::size_of::<Self>();
+        config
+    }
+}
+
+/// Per-call options of `mysql_pool_query`; a NULL pointer applies the
+/// defaults. New fields are only ever appended.
+#[repr(C)]
+pub struct MysqlQueryOptions {
+    /// Size of the struct as the caller knows it; fields past it keep their
+    /// defaults.
+    pub struct_size: usize,
+    /// Server-side execution limit, replacing the pool's default timeout for
+    /// the call: `max_statement_time` on MariaDB, otherwise a
+    /// `MAX_EXECUTION_TIME` hint, which only SELECT accepts. 0 disables it.
+    pub server_timeout_ms: c_uint,
+    /// Gzip-compresses a successful response into a `STATUS_COMPRESSED` frame.
+    pub compress: bool,
+    /// Reports ENUM columns as `SMALLINT` 1-based ordinals instead of their
+    /// labels, buffering the rows to look up the definitions.
+    pub enum_ordinals: bool,
+    /// Adds each column's SQL type name to the metadata block.
+    pub type_names: bool,
+}
+
+impl Default for MysqlQueryOptions {
+    fn default() -> Self {
+        Self {
+            struct_size: std::mem::size_of::<Self>(),
+            server_timeout_ms: 0,
+            compress: false,
+            enum_ordinals: false,
+            type_names: false,
+        }
+    }
+}
+
+/// Per-call options of `mysql_pool_batch_insert`, `mysql_pool_batch_insert_stream`
+/// and `mysql_pool_batch_upsert`; a NULL pointer applies the defaults. New fields are only ever appended.
+#[repr(C)]
+pub struct MysqlBatchInsertOptions {
+    /// Size of the struct as the caller knows it; fields past it keep their
+    /// defaults.
+    pub struct_size: usize,
+    /// Comma-separated columns whose repeated values drop a row; NULL keeps
+    /// every row. Inserts only.
+    pub dedup_columns: *const c_char,
+    /// Comma-separated columns whose NULL values become the column's
+    /// `DEFAULT`, each such row inserted on its own in its place in the batch;
+    /// NULL binds every NULL as a value. Inserts only.
+    pub default_columns: *const c_char,
+    /// Per-column type hints in the layout read by `parse_type_hints`.
+    pub type_hints: *const c_uchar,
+    pub type_hints_len: c_int,
+    /// `BATCH_MODE_EXTENDED` for multi-row `INSERT`s per chunk, or
+    /// `BATCH_MODE_PREPARED` for one prepared single-row `INSERT` per row.
+    pub mode: c_uchar,
+}
+
+impl Default for MysqlBatchInsertOptions {
+    fn default() -> Self {
+        Self {
+            struct_size: std::mem::size_of::<Self>(),
+            dedup_columns: std::ptr::null(),
+            default_columns: std::ptr::null(),
+            type_hints: std::ptr::null(),
+            type_hints_len: 0,
+            mode: 0,
+        }
+    }
+}
+
+/// Shortest delay before retrying a connection the server refused for being at
+/// its connection limit.
+const SERVER_BUSY_BACKOFF: Duration = Duration::from_millis(100);
+
+/// How often acquiring a connection is retried after a connection-level failure.
+#[derive(Clone, Copy, Default)]
+pub struct ConnectRetry {
+    /// Retries after the first attempt; 0 disables retrying.
+    pub attempts: u32,
+    /// Delay before the first retry, doubled for each later one.
+    pub backoff: Duration,
+}
+
+/// Maintenance switch checked before a connection is taken from the pool.
+struct PauseGate {
+    paused: watch::Sender<bool>,
+    max_queued: AtomicUsize,
+    queued: AtomicUsize,
+}
+
+impl Default for PauseGate {
+    fn default() -> Self {
+        Self {
+            paused: watch::Sender::new(false),
+            max_queued: AtomicUsize::new(0),
+            queued: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl PauseGate {
+    /// Waits while the pool is paused, or fails at once when the queue of
+    /// waiting requests is full.
+    async fn pass(&self) -> mysql_async::Result<()> {
+        let mut paused = self.paused.subscribe();
+        if !*paused.borrow_and_update() {
+            return Ok(());
+        }
+        let max_queued = self.max_queued.load(Ordering::Relaxed);
+        if self.queued.fetch_add(1, Ordering::AcqRel) >= max_queued {
+            self.queued.fetch_sub(1, Ordering::AcqRel);
+            return Err(mysql_async::Error::Other("Pool is paused".into()));
+        }
+        // Dequeues on resume as well as when the waiting task is cancelled.
+        struct Dequeue<'a>(&'a AtomicUsize);
+        impl Drop for Dequeue<'_> {
+            fn drop(&mut self) {
+                self.0.fetch_sub(1, Ordering::AcqRel);
+            }
+        }
+        let _dequeue = Dequeue(&self.queued);
+        // The sender lives as long as the gate, so this cannot fail.
+        let _ = paused.wait_for(|paused| !paused).await;
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct InFlight {
+    count: std::sync::Mutex<usize>,
+    idle: Condvar,
+}
+
+/// A pool handle held by a spawned task; the task stops counting as in flight
+/// when it is dropped.
+pub struct PoolHandle {
+    pool: Pool,
+    slot: Arc<RwLock<PoolSlot>>,
+    retry: ConnectRetry,
+    in_flight: Arc<InFlight>,
+    gate: Arc<PauseGate>,
+}
+
+impl PoolHandle {
+    /// Takes a connection from the pool, retrying connection-level failures as
+    /// configured by the pool's [`ConnectRetry`].
+    ///
+    /// Before each retry the pool is rebuilt, dropping idle connections that may
+    /// point at a failed-over endpoint, so the next attempt resolves the host
+    /// and connects afresh. A server at its connection limit is retried on the
+    /// same pool, at least once and no sooner than [`SERVER_BUSY_BACKOFF`].
+    /// Waits first while the pool is paused. Shadows
+    /// [`Pool::get_conn`] for every pool operation.
+    pub async fn get_conn(&self) -> mysql_async::Result<Conn> {
+        self.gate.pass().await?;
+        let mut pool = self.pool.clone();
+        let mut attempt = 0;
+        loop {
+            #[cfg(feature = "test-hooks")]
+            let result = match crate::simulated_connect_failure() {
+                Some(e) => Err(e),
+                None => pool.get_conn().await,
+            };
+            #[cfg(not(feature = "test-hooks"))]
+            let result = pool.get_conn().await;
+            match result {
+                Err(e) if attempt < self.retry.attempts.max(1) && is_server_busy(&e) => {
+                    let backoff = self.retry.backoff.max(SERVER_BUSY_BACKOFF);
+                    tokio::time::sleep(backoff.saturating_mul(1 << attempt.min(16))).await;
+                    attempt += 1;
+                }
+                Err(e) if attempt < self.retry.attempts && is_connection_lost(&e) => {
+                    let delay = self.retry.backoff.saturating_mul(1 << attempt.min(16));
+                    attempt += 1;
+                    pool = {
+                        let mut slot = self.slot.write().unwrap_or_else(PoisonError::into_inner);
+                        *slot = PoolSlot::new(slot.opts.clone());
+                        slot.pool.clone()
+                    };
+                    tokio::time::sleep(delay).await;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl Deref for PoolHandle {
+    type Target = Pool;
+
+    fn deref(&self) -> &Pool {
+        &self.pool
+    }
+}
+
+impl Drop for PoolHandle {
+    fn drop(&mut self) {
+        let mut count = self
+            .in_flight
+            .count
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        *count -= 1;
+        if *count == 0 {
+            self.in_flight.idle.notify_all();
+        }
+    }
+}
+
+/// A query recorded by [`RecentQueries`].
+pub struct QueryRecord {
+    /// Start time in microseconds since the Unix epoch.
+    pub started_at_us: u64,
+    pub duration: Duration,
+    pub query: String,
+}
+
+/// Ring buffer of the most recently executed queries of a pool.
+pub struct RecentQueries {
+    capacity: usize,
+    entries: std::sync::Mutex<VecDeque<QueryRecord>>,
+}
+
+impl RecentQueries {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: std::sync::Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    /// Records a query that started at `started` and has just completed,
+    /// evicting the oldest entry when full.
+    pub fn record(&self, started: Instant, query: &str) {
+        let duration = started.elapsed();
+        let started_at_us = SystemTime::now()
+            .checked_sub(duration)
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_micros() as u64);
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(QueryRecord {
+            started_at_us,
+            duration,
+            query: query.to_string(),
+        });
+    }
+
+    /// Runs `f` on the recorded queries, oldest first.
+    pub fn with_entries<R>(&self, f: impl FnOnce(&VecDeque<QueryRecord>) -> R) -> R {
+        f(&self.entries.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+/// Reports queries that take at least `threshold` to a dedicated callback.
+pub struct SlowQueryLog {
+    pub threshold: Duration,
+    /// Runs `EXPLAIN` on slow SELECT statements and includes the plan.
+    pub explain: bool,
+    pub callback: CallbackWrapper,
 }
 
 /// Represents a single, isolated MySQL connection.
 pub struct MysqlConnection {
     pub conn: Arc<Mutex<Option<Conn>>>,
+    /// Error of the most recent failed operation on this connection.
+    pub last_error: Arc<LastError>,
+    /// Inherited from the pool the connection was acquired from.
+    pub read_only: bool,
+    /// Inherited from the pool the connection was acquired from.
+    pub default_timeout: Option<Duration>,
+    /// Inherited from the pool the connection was acquired from.
+    pub deadlock_diagnostics: bool,
 }
 
 /// Represents a prepared statement bound to a specific connection.
 pub struct MysqlPreparedStatement {
     pub conn: Arc<Mutex<Option<Conn>>>,
     pub stmt: mysql_async::Statement,
+    /// Error of the most recent failed execution of this statement.
+    pub last_error: Arc<LastError>,
+    /// Inherited from the pool the statement was prepared on.
+    pub default_timeout: Option<Duration>,
+}
+
+/// A statement bound to a pool rather than a connection; each execution runs
+/// on any available connection, reusing that connection's cached preparation.
+pub struct MysqlPoolStatement {
+    /// Borrowed; the statement must be destroyed before its pool.
+    pub pool: *const MysqlPool,
+    pub query: String,
+    pub prepared: Arc<std::sync::Mutex<PrepareLog>>,
+}
+
+/// Counts server-side preparations of a statement from the ids it gets.
+#[derive(Default)]
+pub struct PrepareLog {
+    /// Latest statement id per connection id; a cache hit returns the same id.
+    by_conn: HashMap<u32, u32>,
+    count: u64,
+}
+
+impl PrepareLog {
+    pub fn record(&mut self, conn_id: u32, stmt_id: u32) {
+        if self.by_conn.insert(conn_id, stmt_id) != Some(stmt_id) {
+            self.count += 1;
+        }
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+}
+
+/// Item produced by the task reading a cursor's result sets.
+pub enum CursorEvent {
+    /// Columns of the result set whose rows follow.
+    Columns(Arc<[Column]>),
+    Row(Row),
+    Error(mysql_async::Error),
+}
+
+/// Cancellation signal shared by a `MysqlCancelToken` and the queries it was
+/// passed to.
+#[derive(Default)]
+pub struct CancelToken {
+    cancelled: AtomicBool,
+    notify: tokio::sync::Notify,
+}
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+        self.notify.notify_waiters();
+    }
+
+    /// Drives `fut` to completion unless the token is cancelled first, waking
+    /// as soon as it is. Returns `None` when cancelled.
+    pub async fn run<F: Future>(&self, fut: F) -> Option<F::Output> {
+        let mut fut = std::pin::pin!(fut);
+        let mut notified = std::pin::pin!(self.notify.notified());
+        notified.as_mut().enable();
+        if self.cancelled.load(Ordering::Acquire) {
+            return None;
+        }
+        std::future::poll_fn(|cx| {
+            if notified.as_mut().poll(cx).is_ready() {
+                return Poll::Ready(None);
+            }
+            fut.as_mut().poll(cx).map(Some)
+        })
+        .await
+    }
+}
+
+/// A cancellation token handed to the consumer by `mysql_cancel_token_create`.
+pub struct MysqlCancelToken(pub Arc<CancelToken>);
+
+/// A pull-based cursor over the rows of a running query.
+///
+/// A background task owns the connection and buffers rows in `rx`; fetches
+/// drain it into `encoder`, so a fetch that gives up early leaves the cursor usable.
+pub struct MysqlCursor {
+    pub state: Arc<Mutex<CursorState>>,
+}
+
+pub struct CursorState {
+    pub rx: mpsc::Receiver<CursorEvent>,
+    pub encoder: RowChunkEncoder,
+}
+
+/// Locates one TEXT or BLOB value by its row key, so `mysql_lob_read` fetches
+/// only the requested range from the server.
+pub struct MysqlLob {
+    /// Borrowed; the handle must be destroyed before its pool.
+    pub pool: *const MysqlPool,
+    /// Selects a range and the total length of the value, taking the 1-based
+    /// start and the length before the key values.
+    pub query: String,
+    pub key: Vec<mysql_async::Value>,
+}
+
+/// Several pools holding disjoint parts of the data, addressed by shard key.
+pub struct MysqlShardedPool {
+    /// Boxed so pointers handed out to callers stay valid.
+    pub pools: Vec<Box<MysqlPool>>,
+    pub strategy: ShardStrategy,
+}
+
+impl MysqlShardedPool {
+    /// Returns the index of the pool responsible for `key`.
+    pub fn shard_for(&self, key: &[u8]) -> usize {
+        match &self.strategy {
+            ShardStrategy::ConsistentHash(ring) => ring.lookup(stable_hash(key)),
+            ShardStrategy::Modulo => (stable_hash(key) % self.pools.len() as u64) as usize,
+        }
+    }
+}
+
+/// How shard keys are mapped to pools.
+pub enum ShardStrategy {
+    /// Keys move only between neighbouring shards when a pool is added or
+    /// removed, at the cost of a slightly uneven distribution.
+    ConsistentHash(HashRing),
+    /// Hash of the key modulo the pool count; evenly spread, but nearly every
+    /// key moves when the pool count changes.
+    Modulo,
+}
+
+/// Points placed on the ring for each shard; more points even out the load.
+const RING_POINTS_PER_SHARD: u32 = 160;
+
+/// A consistent hash ring over shard indexes.
+pub struct HashRing {
+    /// Point hashes with the shard owning them, sorted by hash.
+    points: Vec<(u64, usize)>,
+}
+
+impl HashRing {
+    pub fn new(shards: usize) -> Self {
+        let mut points = Vec::with_capacity(shards * RING_POINTS_PER_SHARD as usize);
+        for shard in 0..shards {
+            for point in 0..RING_POINTS_PER_SHARD {
+                let mut seed = [0u8; 12];
+                seed[..8].copy_from_slice(&(shard as u64).to_le_bytes());
+                seed[8..].copy_from_slice(&point.to_le_bytes());
+                points.push((stable_hash(&seed), shard));
+            }
+        }
+        points.sort_unstable();
+        Self { points }
+    }
+
+    /// Returns the shard owning the first point at or after `hash`, wrapping
+    /// around to the first point.
+    fn lookup(&self, hash: u64) -> usize {
+        let i = self.points.partition_point(|(point, _)| *point < hash);
+        self.points[i % self.points.len()].1
+    }
+}
+
+/// Behavior switches for a batch insert execution.
+#[derive(Clone, Default)]
+pub struct BatchOptions {
+    /// Appends `ON DUPLICATE KEY UPDATE` for every inserted column.
+    pub on_duplicate: bool,
+    /// Reports each executed chunk with its own progress frame.
+    pub stream: bool,
+    /// Columns forming a unique key; rows repeating an earlier row's key are
+    /// dropped before insertion. Empty disables deduplication.
+    pub dedup_key: Vec<String>,
+    /// Column type per inserted column that string values of that column are
+    /// bound as, as for `PARAM_TYPED` parameters. Empty disables coercion.
+    pub type_hints: Vec<Option<u8>>,
+    /// Prepares a single-row `INSERT` once and executes it per row instead of
+    /// sending multi-row `INSERT` statements.
+    pub per_row: bool,
+    /// Columns whose NULL values leave the column to its `DEFAULT`. Rows with
+    /// such a NULL are inserted one at a time with those columns omitted, as
+    /// a multi-row `INSERT` shares one column list. Empty disables this.
+    pub default_on_null: Vec<String>,
+    /// Limit on each statement of a streamed batch, whose total duration is
+    /// unbounded. `None` leaves statements unbounded.
+    pub step_timeout: Option<Duration>,
 }
 
 /// Function signature for the C callback used to send responses back to Dart.
 pub type CallbackType = extern "C" fn(c_longlong, *mut c_uchar, c_int);
 
+/// Last error reported by an operation on a connection or prepared statement.
+#[derive(Default)]
+pub struct LastError(std::sync::Mutex<Option<(u16, String)>>);
+
+impl LastError {
+    pub fn set(&self, code: u16, message: &str) {
+        let message = message.replace('\0', "");
+        *self.0.lock().unwrap_or_else(PoisonError::into_inner) = Some((code, message));
+    }
+
+    pub fn clear(&self) {
+        *self.0.lock().unwrap_or_else(PoisonError::into_inner) = None;
+    }
+
+    /// Returns a copy of the stored message, or `None` if the last operation succeeded.
+    pub fn message(&self) -> Option<String> {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()
+            .map(|(_, message)| message.clone())
+    }
+
+    /// Returns the server error code, or 0 for client-side errors and success.
+    pub fn code(&self) -> u16 {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()
+            .map_or(0, |(code, _)| *code)
+    }
+}
+
 /// A thread-safe wrapper around the C callback function pointer.
-pub struct CallbackWrapper(pub CallbackType);
+///
+/// When it carries a [`LastError`] slot, every response sent through it records
+/// the outcome there.
+#[derive(Clone)]
+pub struct CallbackWrapper(pub CallbackType, pub Option<Arc<LastError>>);
+
+impl CallbackWrapper {
+    pub fn new(callback: CallbackType) -> Self {
+        Self(callback, None)
+    }
+
+    /// Records outcomes sent through this callback in `slot`.
+    pub fn with_last_error(self, slot: &Arc<LastError>) -> Self {
+        Self(self.0, Some(slot.clone()))
+    }
+}
 unsafe impl Send for CallbackWrapper {}
 unsafe impl Sync for CallbackWrapper {}
@@ -1,24 +1,126 @@
-use crate::types::CallbackWrapper;
-use mysql_async::{Row, Value as MySqlValue};
+use crate::types::{CallbackType, CallbackWrapper};
+use mysql_async::consts::{ColumnFlags, ColumnType};
+use mysql_async::{Column, Row, Value as MySqlValue};
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::ffi::CStr;
 use std::os::raw::{c_char, c_int, c_longlong, c_uchar};
 use std::slice;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::SendError;
+use std::sync::{Arc, LazyLock, PoisonError, RwLock};
 
 const STATUS_ERROR: u8 = 0;
 const STATUS_OK: u8 = 1;
+/// Intermediate frame of a streamed response; more frames follow for the same request.
+const STATUS_CHUNK: u8 = 2;
+/// A parameter could not be decoded; carries the parameter index and a message.
+const STATUS_PARAM_ERROR: u8 = 3;
+/// A successful response compressed with gzip: the uncompressed length as a
+/// u32, then the gzip stream of the original frame, status byte included.
+const STATUS_COMPRESSED: u8 = 4;
+/// The response did not fit the buffer supplied by the consumer; carries the
+/// required length as a u64.
+const STATUS_BUFFER_TOO_SMALL: u8 = 5;
+
+/// Version of the response layout, sent as the first byte of every frame,
+/// ahead of the status. Bumped whenever the layout of any frame changes.
+pub const PROTOCOL_VERSION: u8 = 1;
+
+/// Error categories carried by error frames, so callers can decide whether to retry.
+pub const ERROR_OTHER: u8 = 0;
+pub const ERROR_IO: u8 = 1;
+pub const ERROR_DRIVER: u8 = 2;
+pub const ERROR_SERVER: u8 = 3;
+pub const ERROR_URL: u8 = 4;
+/// A statement affected a different number of rows than it required.
+pub const ERROR_ROW_COUNT: u8 = 5;
+/// The server refused a connection because it or the account is at its
+/// connection limit; retrying later may succeed.
+pub const ERROR_SERVER_BUSY: u8 = 6;
+/// The caller cancelled the operation before it completed.
+pub const ERROR_CANCELLED: u8 = 7;
+/// The server rejected the credentials; retrying with the same ones will fail.
+pub const ERROR_AUTH_FAILED: u8 = 8;
+/// The account's password has expired and must be changed before statements
+/// other than `SET PASSWORD`/`ALTER USER` are accepted.
+pub const ERROR_PASSWORD_EXPIRED: u8 = 9;
+/// A statement exceeded `max_allowed_packet`; sending less per statement, such
+/// as smaller batch chunks, may succeed.
+pub const ERROR_PACKET_TOO_LARGE: u8 = 10;
+
+/// Server error raised when `max_connections` is reached.
+const ER_CON_COUNT_ERROR: u16 = 1040;
+/// Server error raised when the account's `max_user_connections` is reached.
+const ER_TOO_MANY_USER_CONNECTIONS: u16 = 1203;
+/// Server error raised for a wrong user name or password.
+const ER_ACCESS_DENIED_ERROR: u16 = 1045;
+/// Server error raised when an account requires a password that was not given.
+const ER_ACCESS_DENIED_NO_PASSWORD_ERROR: u16 = 1698;
+/// Server error raised for statements on a session whose password has expired.
+const ER_MUST_CHANGE_PASSWORD: u16 = 1820;
+/// Server error raised when logging in with an expired password is refused.
+const ER_MUST_CHANGE_PASSWORD_LOGIN: u16 = 1862;
+/// Server error raised for a packet larger than `max_allowed_packet`.
+const ER_NET_PACKET_TOO_LARGE: u16 = 1153;
+
+const VALUE_NULL: u8 = 0;
+const VALUE_BYTES: u8 = 1;
+const VALUE_TIME: u8 = 2;
+/// A `TINYINT(1)` value, encoded as a single 0/1 byte.
+const VALUE_BOOL: u8 = 3;
+/// A spatial value: its SRID as a u32 (0 when unknown) followed by the WKB blob.
+const VALUE_GEOMETRY: u8 = 4;
+/// A `BIT(n)` value: the declared width `n` as a u8, then the bits as a u64.
+const VALUE_BIT: u8 = 5;
+
+/// Set in a metadata block's column count when each column carries its SQL
+/// type name after its charset.
+const COLUMNS_WITH_TYPE_NAMES: u32 = 0x8000_0000;
+
+/// Collation id of the `binary` character set.
+const CHARSET_BINARY: u16 = 63;
+
+/// Largest magnitude of a MySQL `TIME` value: 838:59:59.
+const TIME_MAX_HOURS: u32 = 838;
+
+#[cfg(feature = "binlog")]
+const BINLOG_TABLE_MAP: u8 = 1;
+#[cfg(feature = "binlog")]
+const BINLOG_WRITE_ROWS: u8 = 2;
+#[cfg(feature = "binlog")]
+const BINLOG_UPDATE_ROWS: u8 = 3;
+#[cfg(feature = "binlog")]
+const BINLOG_DELETE_ROWS: u8 = 4;
+#[cfg(feature = "binlog")]
+const BINLOG_ROTATE: u8 = 5;
 
 const PARAM_NULL: u8 = 0;
 const PARAM_INT: u8 = 1;
 const PARAM_FLOAT: u8 = 2;
 const PARAM_STRING: u8 = 3;
 const PARAM_BLOB: u8 = 4;
+/// An unsigned 64-bit integer, for `BIGINT UNSIGNED` values above `i64::MAX`.
+const PARAM_UINT: u8 = 5;
+const PARAM_TYPED: u8 = 10;
+/// A MySQL type byte, a flags byte and the value in binary protocol encoding,
+/// bound without conversion. See `parse_raw_value` for the supported types.
+const PARAM_RAW: u8 = 11;
+/// A date and time: year as a u16, then month, day, hour, minute and second
+/// as u8s and microseconds as a u32.
+const PARAM_DATETIME: u8 = 12;
+/// A time of day or duration: a sign byte (1 for negative), days as a u32, then
+/// hours, minutes and seconds as u8s and microseconds as a u32.
+const PARAM_TIME: u8 = 13;
+/// `PARAM_RAW` flag marking an integer value as unsigned, as in `COM_STMT_EXECUTE`.
+const RAW_FLAG_UNSIGNED: u8 = 0x80;
 
 macro_rules! unwrap_or_return {
     ($expr:expr, $cb:expr, $id:expr) => {
         match $expr {
             Ok(val) => val,
             Err(e) => {
-                crate::utils::send_error(&$cb, $id, &e.to_string());
+                crate::utils::send_categorized_error(&$cb, $id, &e);
                 return;
             }
         }
@@ -96,6 +198,15 @@ impl<'a> BinaryReader<'a> {
             None
         }
     }
+    pub fn read_u64(&mut self) -> Option<u64> {
+        if self.pos + 8 <= self.data.len() {
+            let bytes = self.data[self.pos..self.pos + 8].try_into().ok()?;
+            self.pos += 8;
+            Some(u64::from_le_bytes(bytes))
+        } else {
+            None
+        }
+    }
     pub fn read_f64(&mut self) -> Option<f64> {
         if self.pos + 8 <= self.data.len() {
             let bytes = self.data[self.pos..self.pos + 8].try_into().ok()?;
@@ -105,6 +216,15 @@ impl<'a> BinaryReader<'a> {
             None
         }
     }
+    pub fn read_u16(&mut self) -> Option<u16> {
+        if self.pos + 2 <= self.data.len() {
+            let bytes = self.data[self.pos..self.pos + 2].try_into().ok()?;
+            self.pos += 2;
+            Some(u16::from_le_bytes(bytes))
+        } else {
+            None
+        }
+    }
     pub fn read_u32(&mut self) -> Option<u32> {
         if self.pos + 4 <= self.data.len() {
             let bytes = self.data[self.pos..self.pos + 4].try_into().ok()?;
@@ -126,142 +246,1982 @@ impl<'a> BinaryReader<'a> {
     }
 }
 
+/// Starts a frame with the protocol version and `status`; `capacity` counts the
+/// status byte and the payload.
+pub fn new_frame(status: u8, capacity: usize) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(1 + capacity);
+    buf.write_u8(PROTOCOL_VERSION);
+    buf.write_u8(status);
+    buf
+}
+
+/// Starts a `STATUS_OK` frame whose payload does not begin with the counters.
+pub fn ok_frame(capacity: usize) -> Vec<u8> {
+    new_frame(STATUS_OK, capacity)
+}
+
 pub fn send_response(cb: &CallbackWrapper, req_id: c_longlong, data: Vec<u8>) {
-    let mut buf = data.into_boxed_slice();
-    let len = buf.len() as c_int;
-    let ptr = buf.as_mut_ptr();
-    std::mem::forget(buf);
-    (cb.0)(req_id, ptr, len);
+    if let Some(slot) = &cb.1
+        && matches!(data.get(1), Some(&(STATUS_OK | STATUS_COMPRESSED)))
+    {
+        slot.clear();
+    }
+    if crate::DEBUG_CAPTURE.load(Ordering::Relaxed) {
+        crate::capture_frame(req_id, &data);
+    }
+    dispatch(cb.0, req_id, Frame::Owned(data.into_boxed_slice()));
+}
+
+/// Like [`send_response`], but writes the frame into the consumer's buffer at
+/// `out_buf` when it fits in `out_cap` bytes.
+///
+/// The callback then receives `out_buf` itself and the frame length. A larger
+/// frame is not delivered; the callback instead gets an allocated
+/// `STATUS_BUFFER_TOO_SMALL` frame carrying the required length as a u64.
+pub fn send_response_into(
+    cb: &CallbackWrapper,
+    req_id: c_longlong,
+    data: Vec<u8>,
+    out_buf: usize,
+    out_cap: usize,
+) {
+    let frame_len = data.len();
+    if frame_len > out_cap {
+        let mut buf = new_frame(STATUS_BUFFER_TOO_SMALL, 9);
+        buf.write_u64(frame_len as u64);
+        send_response(cb, req_id, buf);
+        return;
+    }
+    let out = unsafe { std::slice::from_raw_parts_mut(out_buf as *mut u8, frame_len) };
+    out.copy_from_slice(&data);
+    if crate::DEBUG_CAPTURE.load(Ordering::Relaxed) {
+        crate::capture_frame(req_id, out);
+    }
+    dispatch(cb.0, req_id, Frame::Borrowed(out_buf, frame_len as c_int));
+}
+
+/// A response frame on its way to the consumer.
+pub enum Frame {
+    /// Allocated here; ownership passes to the consumer, which frees it with
+    /// `mysql_buffer_free`.
+    Owned(Box<[u8]>),
+    /// Already written into a buffer the consumer supplied, given by address and
+    /// frame length; the consumer keeps ownership.
+    Borrowed(usize, c_int),
+}
+
+/// Invokes the callback directly or through the dispatcher thread, as selected
+/// with `mysql_set_callback_dispatch`.
+fn dispatch(cb: CallbackType, req_id: c_longlong, frame: Frame) {
+    if crate::SINGLE_THREADED_DISPATCH.load(Ordering::Acquire)
+        && let Some(dispatcher) = crate::get_dispatcher()
+    {
+        if let Err(SendError(crate::Dispatch::Frame(cb, req_id, frame))) =
+            dispatcher.send(crate::Dispatch::Frame(cb, req_id, frame))
+        {
+            invoke_callback(cb, req_id, frame);
+        }
+        return;
+    }
+    invoke_callback(cb, req_id, frame);
+}
+
+/// Hands `frame` to the callback.
+pub fn invoke_callback(cb: CallbackType, req_id: c_longlong, frame: Frame) {
+    #[cfg(feature = "test-hooks")]
+    crate::record_callback_thread();
+    match frame {
+        Frame::Owned(mut buf) => {
+            let len = buf.len() as c_int;
+            let ptr = buf.as_mut_ptr();
+            std::mem::forget(buf);
+            cb(req_id, ptr, len);
+        }
+        Frame::Borrowed(ptr, len) => cb(req_id, ptr as *mut c_uchar, len),
+    }
+}
+
+/// Classifies an error into one of the `ERROR_*` categories.
+pub trait ErrorCategory: std::fmt::Display {
+    fn category(&self) -> u8 {
+        ERROR_OTHER
+    }
+
+    /// Server error code, or 0 when the error did not come from the server.
+    fn code(&self) -> u16 {
+        0
+    }
+}
+
+impl ErrorCategory for mysql_async::Error {
+    fn category(&self) -> u8 {
+        match self {
+            _ if is_server_busy(self) => ERROR_SERVER_BUSY,
+            _ if is_packet_too_large(self) => ERROR_PACKET_TOO_LARGE,
+            mysql_async::Error::Server(e)
+                if e.code == ER_ACCESS_DENIED_ERROR
+                    || e.code == ER_ACCESS_DENIED_NO_PASSWORD_ERROR =>
+            {
+                ERROR_AUTH_FAILED
+            }
+            mysql_async::Error::Server(e)
+                if e.code == ER_MUST_CHANGE_PASSWORD || e.code == ER_MUST_CHANGE_PASSWORD_LOGIN =>
+            {
+                ERROR_PASSWORD_EXPIRED
+            }
+            mysql_async::Error::Io(_) => ERROR_IO,
+            mysql_async::Error::Driver(_) => ERROR_DRIVER,
+            mysql_async::Error::Server(_) => ERROR_SERVER,
+            mysql_async::Error::Url(_) => ERROR_URL,
+            mysql_async::Error::Other(_) => ERROR_OTHER,
+        }
+    }
+
+    fn code(&self) -> u16 {
+        match self {
+            mysql_async::Error::Server(e) => e.code,
+            _ => 0,
+        }
+    }
+}
+
+/// Returns `true` if the server refused a connection for being at its
+/// connection limit.
+pub fn is_server_busy(err: &mysql_async::Error) -> bool {
+    matches!(
+        err,
+        mysql_async::Error::Server(e)
+            if e.code == ER_CON_COUNT_ERROR || e.code == ER_TOO_MANY_USER_CONNECTIONS
+    )
+}
+
+/// Returns `true` if a statement was refused for exceeding `max_allowed_packet`,
+/// by the server or already by the driver.
+pub fn is_packet_too_large(err: &mysql_async::Error) -> bool {
+    matches!(
+        err,
+        mysql_async::Error::Server(e) if e.code == ER_NET_PACKET_TOO_LARGE
+    ) || matches!(
+        err,
+        mysql_async::Error::Driver(mysql_async::DriverError::PacketTooLarge)
+    )
+}
+
+/// Approximates the size of the packet sending `query` with `params`, for
+/// reporting statements that exceed `max_allowed_packet`.
+pub fn statement_size(query: &str, params: &[MySqlValue]) -> usize {
+    query.len() + params.iter().map(encoded_value_size).sum::<usize>()
+}
+
+/// Returns `true` if the error means the connection itself is unusable, as
+/// opposed to a failed statement on a healthy connection.
+pub fn is_connection_lost(err: &mysql_async::Error) -> bool {
+    matches!(
+        err,
+        mysql_async::Error::Io(_)
+            | mysql_async::Error::Driver(mysql_async::DriverError::ConnectionClosed)
+    )
 }
 
+impl ErrorCategory for std::io::Error {
+    fn category(&self) -> u8 {
+        ERROR_IO
+    }
+}
+
+impl ErrorCategory for String {}
+impl ErrorCategory for &str {}
+
 pub fn send_error(cb: &CallbackWrapper, req_id: c_longlong, msg: &str) {
-    send_response(cb, req_id, encode_error(msg));
+    report_error(cb, req_id, ERROR_OTHER, 0, msg);
+}
+
+/// Sends an error frame tagged with the category of `err`.
+pub fn send_categorized_error<E: ErrorCategory>(cb: &CallbackWrapper, req_id: c_longlong, err: &E) {
+    report_error(cb, req_id, err.category(), err.code(), &err.to_string());
+}
+
+/// Records the error in the callback's last-error slot, if any, and sends an error frame.
+pub fn report_error(cb: &CallbackWrapper, req_id: c_longlong, category: u8, code: u16, msg: &str) {
+    if let Some(slot) = &cb.1 {
+        slot.set(code, msg);
+    }
+    send_response(cb, req_id, encode_error(category, msg));
 }
 
-pub fn encode_error(msg: &str) -> Vec<u8> {
-    let mut buf = Vec::new();
-    buf.write_u8(STATUS_ERROR);
+/// Encodes an error frame: status, error category, then the message.
+pub fn encode_error(category: u8, msg: &str) -> Vec<u8> {
+    let mut buf = new_frame(STATUS_ERROR, 0);
+    buf.write_u8(category);
     buf.write_blob(msg.as_bytes());
     buf
 }
 
+pub fn send_param_error(cb: &CallbackWrapper, req_id: c_longlong, err: &ParamError) {
+    if let Some(slot) = &cb.1 {
+        slot.set(0, &format!("Parameter {}: {}", err.index, err.message));
+    }
+    send_response(cb, req_id, encode_param_error(err));
+}
+
+pub fn encode_param_error(err: &ParamError) -> Vec<u8> {
+    let mut buf = new_frame(STATUS_PARAM_ERROR, 0);
+    buf.write_u32(err.index);
+    buf.write_blob(format!("Parameter {}: {}", err.index, err.message).as_bytes());
+    buf
+}
+
+/// Encodes the progress frame of one executed batch insert chunk.
+pub fn encode_insert_chunk(first_insert_id: u64, row_count: u64) -> Vec<u8> {
+    let mut buf = new_frame(STATUS_CHUNK, 17);
+    buf.write_u64(first_insert_id);
+    buf.write_u64(row_count);
+    buf
+}
+
+/// Encodes the DDL of a dumped table as a `STATUS_CHUNK` frame: status, marker
+/// 2, which [`RowChunkEncoder`] frames never carry, then the DDL as a blob.
+pub fn encode_ddl_chunk(ddl: &[u8]) -> Vec<u8> {
+    let mut buf = new_frame(STATUS_CHUNK, 6 + ddl.len());
+    buf.write_u8(2);
+    buf.write_blob(ddl);
+    buf
+}
+
+/// Encodes a binlog event as a `STATUS_CHUNK` frame.
+///
+/// Layout: status, event kind, timestamp, log position, then a kind-specific
+/// body. Table maps carry the table id, schema, table name and column count;
+/// rows events carry the table id and each row's images (before then after for
+/// updates), each as a value count followed by tagged values; rotations carry
+/// the next binlog file name and position. Returns `Ok(None)` for event kinds
+/// that are not forwarded.
+#[cfg(feature = "binlog")]
+pub fn encode_binlog_event(
+    stream: &mysql_async::BinlogStream,
+    event: &mysql_async::binlog::events::Event,
+) -> std::io::Result<Option<Vec<u8>>> {
+    use mysql_async::binlog::events::{EventData, RowsEventData};
+
+    let Some(data) = event.read_data()? else {
+        return Ok(None);
+    };
+    let header = event.header();
+    let mut buf = new_frame(STATUS_CHUNK, 0);
+    match data {
+        EventData::TableMapEvent(tme) => {
+            buf.write_u8(BINLOG_TABLE_MAP);
+            buf.write_u32(header.timestamp());
+            buf.write_u32(header.log_pos());
+            buf.write_u64(tme.table_id());
+            buf.write_blob(tme.database_name_raw());
+            buf.write_blob(tme.table_name_raw());
+            buf.write_u32(tme.columns_count() as u32);
+        }
+        EventData::RowsEvent(rows_event) => {
+            let kind = match rows_event {
+                RowsEventData::WriteRowsEvent(_) | RowsEventData::WriteRowsEventV1(_) => {
+                    BINLOG_WRITE_ROWS
+                }
+                RowsEventData::UpdateRowsEvent(_)
+                | RowsEventData::UpdateRowsEventV1(_)
+                | RowsEventData::PartialUpdateRowsEvent(_) => BINLOG_UPDATE_ROWS,
+                RowsEventData::DeleteRowsEvent(_) | RowsEventData::DeleteRowsEventV1(_) => {
+                    BINLOG_DELETE_ROWS
+                }
+            };
+            let Some(tme) = stream.get_tme(rows_event.table_id()) else {
+                return Ok(None);
+            };
+            let rows = rows_event.rows(tme).collect::<std::io::Result<Vec<_>>>()?;
+            buf.write_u8(kind);
+            buf.write_u32(header.timestamp());
+            buf.write_u32(header.log_pos());
+            buf.write_u64(rows_event.table_id());
+            buf.write_u32(rows.len() as u32);
+            for (before, after) in rows {
+                for image in [before, after].into_iter().flatten() {
+                    let values = image.unwrap();
+                    buf.write_u32(values.len() as u32);
+                    for value in values {
+                        let value = MySqlValue::try_from(value).map_err(|e| {
+                            std::io::Error::new(
+                                std::io::ErrorKind::InvalidData,
+                                format!("Unsupported binlog value: {}", e),
+                            )
+                        })?;
+                        write_value(&mut buf, &value);
+                    }
+                }
+            }
+        }
+        EventData::RotateEvent(rotate) => {
+            buf.write_u8(BINLOG_ROTATE);
+            buf.write_u32(header.timestamp());
+            buf.write_u32(header.log_pos());
+            buf.write_blob(rotate.name_raw());
+            buf.write_u64(rotate.position());
+        }
+        _ => return Ok(None),
+    }
+    Ok(Some(buf))
+}
+
+/// Identifies a parameter that could not be decoded from the Dart payload.
+pub struct ParamError {
+    /// Zero-based position of the offending parameter.
+    pub index: u32,
+    pub message: &'static str,
+}
+
 /// Parses a single parameter value from the binary stream sent by Dart.
-pub fn parse_value(reader: &mut BinaryReader) -> MySqlValue {
+pub fn try_parse_value(reader: &mut BinaryReader) -> Result<MySqlValue, &'static str> {
+    const TRUNCATED: &str = "Truncated parameter value";
     match reader.read_u8() {
-        Some(PARAM_NULL) => MySqlValue::NULL,
-        Some(PARAM_INT) => reader
-            .read_i64()
+        Some(PARAM_NULL) => Ok(MySqlValue::NULL),
+        Some(PARAM_INT) => reader.read_i64().map(MySqlValue::Int).ok_or(TRUNCATED),
+        Some(PARAM_UINT) => reader.read_u64().map(MySqlValue::UInt).ok_or(TRUNCATED),
+        Some(PARAM_FLOAT) => reader.read_f64().map(MySqlValue::Double).ok_or(TRUNCATED),
+        Some(PARAM_STRING) | Some(PARAM_BLOB) => {
+            reader.read_blob().map(MySqlValue::Bytes).ok_or(TRUNCATED)
+        }
+        Some(PARAM_TYPED) => match (reader.read_u8(), reader.read_blob()) {
+            (Some(type_hint), Some(bytes)) => Ok(parse_typed_value(type_hint, bytes)),
+            _ => Err(TRUNCATED),
+        },
+        Some(PARAM_RAW) => match (reader.read_u8(), reader.read_u8(), reader.read_blob()) {
+            (Some(type_byte), Some(flags), Some(bytes)) => {
+                parse_raw_value(type_byte, flags & RAW_FLAG_UNSIGNED != 0, bytes)
+            }
+            _ => Err(TRUNCATED),
+        },
+        Some(PARAM_DATETIME) => {
+            let year = reader.read_u16().ok_or(TRUNCATED)?;
+            let mut parts = [0u8; 5];
+            for part in &mut parts {
+                *part = reader.read_u8().ok_or(TRUNCATED)?;
+            }
+            let micros = reader.read_u32().ok_or(TRUNCATED)?;
+            let [month, day, hour, minute, second] = parts;
+            if year > 9999
+                || month > 12
+                || day > 31
+                || hour > 23
+                || minute > 59
+                || second > 59
+                || micros > 999_999
+            {
+                return Err("Datetime component out of range");
+            }
+            Ok(MySqlValue::Date(
+                year, month, day, hour, minute, second, micros,
+            ))
+        }
+        Some(PARAM_TIME) => {
+            let negative = reader.read_u8().ok_or(TRUNCATED)? != 0;
+            let days = reader.read_u32().ok_or(TRUNCATED)?;
+            let mut parts = [0u8; 3];
+            for part in &mut parts {
+                *part = reader.read_u8().ok_or(TRUNCATED)?;
+            }
+            let micros = reader.read_u32().ok_or(TRUNCATED)?;
+            let [hours, minutes, seconds] = parts;
+            // TIME spans at most 838:59:59, i.e. 34 days and 22 hours.
+            if days > 34 || hours > 23 || minutes > 59 || seconds > 59 || micros > 999_999 {
+                return Err("Time component out of range");
+            }
+            Ok(MySqlValue::Time(
+                negative, days, hours, minutes, seconds, micros,
+            ))
+        }
+        Some(_) => Err("Unknown parameter type tag"),
+        None => Err("Missing parameter value"),
+    }
+}
+
+/// Converts the textual value of a `PARAM_TYPED` parameter into the `MySqlValue`
+/// variant matching its MySQL column type hint.
+///
+/// Values that don't parse as the hinted type are bound as raw bytes and left
+/// to server-side coercion.
+pub fn parse_typed_value(type_hint: u8, bytes: Vec<u8>) -> MySqlValue {
+    use ColumnType::*;
+    let Ok(col_type) = ColumnType::try_from(type_hint) else {
+        return MySqlValue::Bytes(bytes);
+    };
+    let Ok(text) = std::str::from_utf8(&bytes) else {
+        return MySqlValue::Bytes(bytes);
+    };
+    let text = text.trim();
+    let parsed = match col_type {
+        MYSQL_TYPE_DATE
+        | MYSQL_TYPE_NEWDATE
+        | MYSQL_TYPE_DATETIME
+        | MYSQL_TYPE_DATETIME2
+        | MYSQL_TYPE_TIMESTAMP
+        | MYSQL_TYPE_TIMESTAMP2 => parse_datetime_str(text),
+        MYSQL_TYPE_TIME | MYSQL_TYPE_TIME2 => parse_time_str(text),
+        MYSQL_TYPE_TINY | MYSQL_TYPE_SHORT | MYSQL_TYPE_LONG | MYSQL_TYPE_INT24
+        | MYSQL_TYPE_LONGLONG | MYSQL_TYPE_YEAR => text
+            .parse::<i64>()
             .map(MySqlValue::Int)
-            .unwrap_or(MySqlValue::NULL),
-        Some(PARAM_FLOAT) => reader
-            .read_f64()
-            .map(MySqlValue::Double)
-            .unwrap_or(MySqlValue::NULL),
-        Some(PARAM_STRING) | Some(PARAM_BLOB) => reader
-            .read_blob()
-            .map(MySqlValue::Bytes)
-            .unwrap_or(MySqlValue::NULL),
-        _ => MySqlValue::NULL,
+            .or_else(|_| text.parse::<u64>().map(MySqlValue::UInt))
+            .ok(),
+        MYSQL_TYPE_FLOAT | MYSQL_TYPE_DOUBLE => text.parse::<f64>().map(MySqlValue::Double).ok(),
+        _ => None,
+    };
+    parsed.unwrap_or(MySqlValue::Bytes(bytes))
+}
+
+/// Builds the `MySqlValue` for a `PARAM_RAW` parameter from binary protocol bytes.
+///
+/// Supported type bytes and their encodings (little-endian):
+/// - `NULL` (6): no bytes.
+/// - `TINY` (1), `SHORT` (2), `YEAR` (13), `LONG` (3), `INT24` (9), `LONGLONG` (8):
+///   a 1, 2, 2, 4, 4 or 8 byte integer, read as unsigned when flagged.
+/// - `FLOAT` (4), `DOUBLE` (5): an IEEE 754 value of 4 or 8 bytes.
+/// - `DATE` (10), `DATETIME` (12), `TIMESTAMP` (7): a length byte (0, 4, 7 or 11),
+///   then year u16, month, day, hour, minute, second and microseconds u32.
+/// - `TIME` (11): a length byte (0, 8 or 12), then sign, days u32, hour, minute,
+///   second and microseconds u32.
+/// - String, blob, decimal, enum, set, bit, JSON and geometry types: the value
+///   bytes as-is.
+///
+/// The bytes must hold exactly one value of the given type.
+pub fn parse_raw_value(
+    type_byte: u8,
+    unsigned: bool,
+    bytes: Vec<u8>,
+) -> Result<MySqlValue, &'static str> {
+    use ColumnType::*;
+    const MALFORMED: &str = "Raw parameter bytes don't match the declared type";
+    let col_type = ColumnType::try_from(type_byte).map_err(|_| "Unknown raw parameter type")?;
+    let int = |width: usize| -> Result<MySqlValue, &'static str> {
+        if bytes.len() != width {
+            return Err(MALFORMED);
+        }
+        let mut le = [0u8; 8];
+        le[..width].copy_from_slice(&bytes);
+        if unsigned {
+            return Ok(MySqlValue::UInt(u64::from_le_bytes(le)));
+        }
+        if bytes[width - 1] & 0x80 != 0 {
+            le[width..].fill(0xff);
+        }
+        Ok(MySqlValue::Int(i64::from_le_bytes(le)))
+    };
+    match col_type {
+        MYSQL_TYPE_NULL if bytes.is_empty() => Ok(MySqlValue::NULL),
+        MYSQL_TYPE_TINY => int(1),
+        MYSQL_TYPE_SHORT | MYSQL_TYPE_YEAR => int(2),
+        MYSQL_TYPE_LONG | MYSQL_TYPE_INT24 => int(4),
+        MYSQL_TYPE_LONGLONG => int(8),
+        MYSQL_TYPE_FLOAT => <[u8; 4]>::try_from(bytes.as_slice())
+            .map(|b| MySqlValue::Float(f32::from_le_bytes(b)))
+            .map_err(|_| MALFORMED),
+        MYSQL_TYPE_DOUBLE => <[u8; 8]>::try_from(bytes.as_slice())
+            .map(|b| MySqlValue::Double(f64::from_le_bytes(b)))
+            .map_err(|_| MALFORMED),
+        MYSQL_TYPE_DATE | MYSQL_TYPE_DATETIME | MYSQL_TYPE_TIMESTAMP => {
+            let len = *bytes.first().ok_or(MALFORMED)?;
+            if !matches!(len, 0 | 4 | 7 | 11) || bytes.len() != 1 + len as usize {
+                return Err(MALFORMED);
+            }
+            let mut fields = [0u32; 7];
+            if len >= 4 {
+                fields[0] = u16::from_le_bytes([bytes[1], bytes[2]]) as u32;
+                fields[1] = bytes[3] as u32;
+                fields[2] = bytes[4] as u32;
+            }
+            if len >= 7 {
+                fields[3] = bytes[5] as u32;
+                fields[4] = bytes[6] as u32;
+                fields[5] = bytes[7] as u32;
+            }
+            if len == 11 {
+                fields[6] = u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]);
+            }
+            let [year, month, day, hour, minute, second, micros] = fields;
+            Ok(MySqlValue::Date(
+                year as u16,
+                month as u8,
+                day as u8,
+                hour as u8,
+                minute as u8,
+                second as u8,
+                micros,
+            ))
+        }
+        MYSQL_TYPE_TIME => {
+            let len = *bytes.first().ok_or(MALFORMED)?;
+            if !matches!(len, 0 | 8 | 12) || bytes.len() != 1 + len as usize {
+                return Err(MALFORMED);
+            }
+            if len == 0 {
+                return Ok(MySqlValue::Time(false, 0, 0, 0, 0, 0));
+            }
+            let micros = if len == 12 {
+                u32::from_le_bytes([bytes[9], bytes[10], bytes[11], bytes[12]])
+            } else {
+                0
+            };
+            Ok(MySqlValue::Time(
+                bytes[1] == 1,
+                u32::from_le_bytes([bytes[2], bytes[3], bytes[4], bytes[5]]),
+                bytes[6],
+                bytes[7],
+                bytes[8],
+                micros,
+            ))
+        }
+        MYSQL_TYPE_STRING
+        | MYSQL_TYPE_VAR_STRING
+        | MYSQL_TYPE_VARCHAR
+        | MYSQL_TYPE_BLOB
+        | MYSQL_TYPE_TINY_BLOB
+        | MYSQL_TYPE_MEDIUM_BLOB
+        | MYSQL_TYPE_LONG_BLOB
+        | MYSQL_TYPE_DECIMAL
+        | MYSQL_TYPE_NEWDECIMAL
+        | MYSQL_TYPE_ENUM
+        | MYSQL_TYPE_SET
+        | MYSQL_TYPE_BIT
+        | MYSQL_TYPE_JSON
+        | MYSQL_TYPE_GEOMETRY => Ok(MySqlValue::Bytes(bytes)),
+        MYSQL_TYPE_NULL => Err(MALFORMED),
+        _ => Err("Unsupported raw parameter type"),
+    }
+}
+
+/// Parses `YYYY-MM-DD` with an optional `[ T]HH:MM:SS[.ffffff]` time part.
+fn parse_datetime_str(text: &str) -> Option<MySqlValue> {
+    let (date, time) = match text.split_once([' ', 'T']) {
+        Some((date, time)) => (date, Some(time)),
+        None => (text, None),
+    };
+    let mut date_parts = date.splitn(3, '-');
+    let year = date_parts.next()?.parse::<u16>().ok()?;
+    let month = date_parts.next()?.parse::<u8>().ok()?;
+    let day = date_parts.next()?.parse::<u8>().ok()?;
+    if month > 12 || day > 31 {
+        return None;
+    }
+    let (hour, minute, second, micros) = match time {
+        Some(time) => {
+            let (hour, minute, second, micros) = parse_clock(time)?;
+            (
+                u8::try_from(hour).ok().filter(|h| *h < 24)?,
+                minute,
+                second,
+                micros,
+            )
+        }
+        None => (0, 0, 0, 0),
+    };
+    Some(MySqlValue::Date(
+        year, month, day, hour, minute, second, micros,
+    ))
+}
+
+/// Parses `[-]HHH:MM:SS[.ffffff]` into a `MySqlValue::Time`.
+fn parse_time_str(text: &str) -> Option<MySqlValue> {
+    let (neg, clock) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+    let (hours, minutes, seconds, micros) = parse_clock(clock)?;
+    if hours > TIME_MAX_HOURS {
+        return None;
     }
+    Some(MySqlValue::Time(
+        neg,
+        hours / 24,
+        (hours % 24) as u8,
+        minutes,
+        seconds,
+        micros,
+    ))
 }
 
-pub fn parse_params_list(ptr: *const c_uchar, len: c_int) -> Vec<MySqlValue> {
+/// Parses `H:MM:SS[.ffffff]`, returning hours, minutes, seconds and microseconds.
+fn parse_clock(text: &str) -> Option<(u32, u8, u8, u32)> {
+    let (clock, fraction) = match text.split_once('.') {
+        Some((clock, fraction)) => (clock, Some(fraction)),
+        None => (text, None),
+    };
+    let mut parts = clock.splitn(3, ':');
+    let hours = parts.next()?.parse::<u32>().ok()?;
+    let minutes = parts.next()?.parse::<u8>().ok().filter(|m| *m < 60)?;
+    let seconds = parts.next()?.parse::<u8>().ok().filter(|s| *s < 60)?;
+    let micros = match fraction {
+        Some(f) if !f.is_empty() && f.len() <= 6 && f.bytes().all(|b| b.is_ascii_digit()) => {
+            format!("{:0<6}", f).parse::<u32>().ok()?
+        }
+        Some(_) => return None,
+        None => 0,
+    };
+    Some((hours, minutes, seconds, micros))
+}
+
+/// Set in a parameter list's count when every value is a bare little-endian
+/// i64 without a type tag, for callers binding only integers.
+const PARAMS_RAW_INT64: u32 = 0x8000_0000;
+
+/// Decodes the parameter list sent by Dart: a u32 count followed by tagged values.
+///
+/// With `PARAMS_RAW_INT64` set in the count, the values are untagged i64s
+/// instead and decode to the same values as `PARAM_INT` ones.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub fn try_parse_params_list(
+    ptr: *const c_uchar,
+    len: c_int,
+) -> Result<Vec<MySqlValue>, ParamError> {
     if ptr.is_null() || len <= 0 {
-        return Vec::new();
+        return Ok(Vec::new());
     }
     let data = unsafe { slice::from_raw_parts(ptr, len as usize) };
     let mut reader = BinaryReader::new(data);
-    let count = reader.read_u32().unwrap_or(0);
-    let mut mysql_params = Vec::with_capacity(count as usize);
-    for _ in 0..count {
-        mysql_params.push(parse_value(&mut reader));
+    let count = reader.read_u32().ok_or(ParamError {
+        index: 0,
+        message: "Missing parameter count",
+    })?;
+    if count & PARAMS_RAW_INT64 != 0 {
+        let count = count & !PARAMS_RAW_INT64;
+        return (0..count)
+            .map(|index| {
+                reader.read_i64().map(MySqlValue::Int).ok_or(ParamError {
+                    index,
+                    message: "Truncated parameter value",
+                })
+            })
+            .collect();
+    }
+    let mut mysql_params = Vec::with_capacity((count as usize).min(data.len()));
+    for index in 0..count {
+        let value =
+            try_parse_value(&mut reader).map_err(|message| ParamError { index, message })?;
+        mysql_params.push(value);
+    }
+    Ok(mysql_params)
+}
+
+/// Decodes per-column type hints: a u32 count followed, per column, by a flag
+/// byte (0 for no hint, 1 otherwise) and a MySQL column type byte. Empty data
+/// means no hints.
+pub fn parse_type_hints(data: &[u8]) -> Result<Vec<Option<u8>>, String> {
+    if data.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut reader = BinaryReader::new(data);
+    let count = reader.read_u32().ok_or("Missing type hint count")?;
+    let mut hints = Vec::with_capacity((count as usize).min(data.len()));
+    for index in 0..count {
+        let (Some(flag), Some(hint)) = (reader.read_u8(), reader.read_u8()) else {
+            return Err(format!("Type hint {}: truncated", index));
+        };
+        hints.push((flag != 0).then_some(hint));
+    }
+    Ok(hints)
+}
+
+/// Decodes a statement list: a u32 count followed, per statement, by the query
+/// as a blob and its parameters in the same layout as a parameter list.
+pub fn parse_statement_list(data: &[u8]) -> Result<Vec<(String, Vec<MySqlValue>)>, String> {
+    let mut reader = BinaryReader::new(data);
+    let count = reader.read_u32().ok_or("Missing statement count")?;
+    let mut statements = Vec::with_capacity((count as usize).min(data.len()));
+    for statement in 0..count {
+        let query = reader
+            .read_blob()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .ok_or_else(|| format!("Statement {}: missing or invalid query", statement))?;
+        let param_count = reader
+            .read_u32()
+            .ok_or_else(|| format!("Statement {}: missing parameter count", statement))?;
+        let mut params = Vec::with_capacity((param_count as usize).min(data.len()));
+        for index in 0..param_count {
+            let value = try_parse_value(&mut reader).map_err(|message| {
+                format!("Statement {}, parameter {}: {}", statement, index, message)
+            })?;
+            params.push(value);
+        }
+        statements.push((query, params));
+    }
+    Ok(statements)
+}
+
+/// Decodes a name list: a u32 count followed by each name as a blob.
+pub fn parse_name_list(data: &[u8]) -> Result<Vec<String>, String> {
+    let mut reader = BinaryReader::new(data);
+    let count = reader.read_u32().ok_or("Missing name count")?;
+    let mut names = Vec::with_capacity((count as usize).min(data.len()));
+    for index in 0..count {
+        let name = reader
+            .read_blob()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .filter(|name| !name.is_empty())
+            .ok_or_else(|| format!("Name {}: missing or invalid", index))?;
+        names.push(name);
+    }
+    Ok(names)
+}
+
+/// Decodes a sort list into the body of an `ORDER BY` clause.
+///
+/// Layout: a u32 count followed, per sort key, by the column name as a blob and
+/// a direction byte (0 ascending, 1 descending). Every column must appear in
+/// `allowed`; names are backtick-quoted per dot-separated part.
+pub fn parse_sort_list(data: &[u8], allowed: &[&str]) -> Result<String, String> {
+    let mut reader = BinaryReader::new(data);
+    let count = reader.read_u32().ok_or("Missing sort column count")?;
+    let mut keys = Vec::with_capacity((count as usize).min(data.len()));
+    for index in 0..count {
+        let column = reader
+            .read_blob()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .ok_or_else(|| format!("Sort column {}: missing or invalid name", index))?;
+        if !allowed.contains(&column.as_str()) {
+            return Err(format!("Sort column {} is not allowed", column));
+        }
+        let direction = match reader.read_u8() {
+            Some(0) => "ASC",
+            Some(1) => "DESC",
+            _ => return Err(format!("Sort column {}: invalid direction", index)),
+        };
+        keys.push(format!("{} {}", quote_identifier(&column), direction));
+    }
+    Ok(keys.join(", "))
+}
+
+/// Drops rows of a flattened batch whose key columns repeat an earlier row.
+///
+/// `values` holds `num_cols` values per row and `key` the column indices that
+/// form the unique key. The first occurrence of each key is kept. Rows with a
+/// NULL key part are always kept, as NULLs never collide in a unique index.
+/// Returns the kept values and the number of rows removed.
+pub fn dedup_batch_rows(
+    values: Vec<MySqlValue>,
+    num_cols: usize,
+    key: &[usize],
+) -> (Vec<MySqlValue>, u64) {
+    let mut seen = std::collections::HashSet::new();
+    let mut kept = Vec::with_capacity(values.len());
+    let mut removed = 0;
+    for row in values.chunks(num_cols) {
+        let unique = key.iter().any(|&i| row[i] == MySqlValue::NULL)
+            || seen.insert(key.iter().map(|&i| row[i].as_sql(true)).collect::<Vec<_>>());
+        if unique {
+            kept.extend_from_slice(row);
+        } else {
+            removed += 1;
+        }
+    }
+    (kept, removed)
+}
+
+/// Quotes each dot-separated part of an identifier with backticks.
+pub fn quote_identifier(name: &str) -> String {
+    name.split('.')
+        .map(|part| format!("`{}`", part.replace('`', "``")))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Column type of the temporary table loading an IN list, chosen from its
+/// first non-NULL value so comparisons keep that value's semantics.
+pub fn in_list_column_type(values: &[MySqlValue]) -> &'static str {
+    match values.iter().find(|value| **value != MySqlValue::NULL) {
+        Some(MySqlValue::UInt(_)) => "BIGINT UNSIGNED",
+        Some(MySqlValue::Float(_) | MySqlValue::Double(_)) => "DOUBLE",
+        Some(MySqlValue::Bytes(_)) => "TEXT",
+        Some(MySqlValue::Date(..)) => "DATETIME(6)",
+        Some(MySqlValue::Time(..)) => "TIME(6)",
+        _ => "BIGINT",
+    }
+}
+
+/// Wraps a response frame in a `STATUS_COMPRESSED` frame.
+///
+/// Frames that fail to compress are returned unchanged.
+pub fn compress_response(data: Vec<u8>) -> Vec<u8> {
+    use flate2::{Compression, write::GzEncoder};
+    use std::io::Write;
+
+    let inner = &data[1..];
+    let mut buf = new_frame(STATUS_COMPRESSED, 5 + inner.len() / 4);
+    buf.write_u32(inner.len() as u32);
+    let mut encoder = GzEncoder::new(buf, Compression::fast());
+    match encoder.write_all(inner).and_then(|_| encoder.finish()) {
+        Ok(buf) => buf,
+        Err(_) => data,
+    }
+}
+
+pub const REDUCE_SUM: i32 = 0;
+pub const REDUCE_MIN: i32 = 1;
+pub const REDUCE_MAX: i32 = 2;
+pub const REDUCE_COUNT: i32 = 3;
+
+/// A number taken from a column value during a reduction.
+#[derive(Clone, Copy)]
+enum Number {
+    Int(i128),
+    Float(f64),
+}
+
+impl Number {
+    fn from_value(val: &MySqlValue) -> Result<Option<Self>, &'static str> {
+        Ok(Some(match val {
+            MySqlValue::NULL => return Ok(None),
+            MySqlValue::Int(v) => Number::Int(*v as i128),
+            MySqlValue::UInt(v) => Number::Int(*v as i128),
+            MySqlValue::Float(v) => Number::Float(*v as f64),
+            MySqlValue::Double(v) => Number::Float(*v),
+            MySqlValue::Bytes(b) => {
+                let text = std::str::from_utf8(b).map_err(|_| NON_NUMERIC)?.trim();
+                match text.parse::<i128>() {
+                    Ok(v) => Number::Int(v),
+                    Err(_) => Number::Float(text.parse::<f64>().map_err(|_| NON_NUMERIC)?),
+                }
+            }
+            _ => return Err(NON_NUMERIC),
+        }))
+    }
+
+    fn as_f64(self) -> f64 {
+        match self {
+            Number::Int(v) => v as f64,
+            Number::Float(v) => v,
+        }
+    }
+
+    fn into_value(self) -> MySqlValue {
+        match self {
+            Number::Int(v) => match (i64::try_from(v), u64::try_from(v)) {
+                (Ok(v), _) => MySqlValue::Int(v),
+                (_, Ok(v)) => MySqlValue::UInt(v),
+                _ => MySqlValue::Double(v as f64),
+            },
+            Number::Float(v) => MySqlValue::Double(v),
+        }
+    }
+}
+
+const NON_NUMERIC: &str = "Reduced column holds a non-numeric value";
+
+/// Folds the values of one column into a single SUM, MIN, MAX or COUNT.
+///
+/// NULLs are skipped. Integers are summed exactly; a floating point or decimal
+/// value switches the accumulator to `f64`.
+pub struct Reduction {
+    op: i32,
+    acc: Option<Number>,
+    count: u64,
+}
+
+impl Reduction {
+    /// Returns `None` for an unknown `REDUCE_*` operation.
+    pub fn new(op: i32) -> Option<Self> {
+        matches!(op, REDUCE_SUM | REDUCE_MIN | REDUCE_MAX | REDUCE_COUNT).then_some(Self {
+            op,
+            acc: None,
+            count: 0,
+        })
+    }
+
+    pub fn push(&mut self, val: &MySqlValue) -> Result<(), &'static str> {
+        if self.op == REDUCE_COUNT {
+            self.count += !matches!(val, MySqlValue::NULL) as u64;
+            return Ok(());
+        }
+        let Some(num) = Number::from_value(val)? else {
+            return Ok(());
+        };
+        self.acc = Some(match (self.acc, num) {
+            (None, num) => num,
+            (Some(Number::Int(a)), Number::Int(b)) => Number::Int(match self.op {
+                REDUCE_SUM => a.saturating_add(b),
+                REDUCE_MIN => a.min(b),
+                _ => a.max(b),
+            }),
+            (Some(a), b) => {
+                let (a, b) = (a.as_f64(), b.as_f64());
+                Number::Float(match self.op {
+                    REDUCE_SUM => a + b,
+                    REDUCE_MIN => a.min(b),
+                    _ => a.max(b),
+                })
+            }
+        });
+        Ok(())
+    }
+
+    /// Returns the result: the count, or NULL when no non-NULL value was seen.
+    pub fn finish(self) -> MySqlValue {
+        if self.op == REDUCE_COUNT {
+            return MySqlValue::UInt(self.count);
+        }
+        self.acc.map_or(MySqlValue::NULL, Number::into_value)
     }
-    mysql_params
 }
 
 /// Serializes query results into a binary payload for consumption by Dart.
 pub fn serialize_result(rows: Vec<Row>, affected_rows: u64, last_insert_id: u64) -> Vec<u8> {
-    let mut buf = Vec::with_capacity(20 + rows.len() * 64);
-    buf.write_u8(STATUS_OK);
+    let mut buf = new_frame(STATUS_OK, 17);
     buf.write_u64(affected_rows);
     buf.write_u64(last_insert_id);
+    write_result_set(&mut buf, rows);
+    buf
+}
 
-    if rows.is_empty() {
-        buf.write_u32(0);
-        buf.write_u32(0);
-        return buf;
-    }
-
-    let cols_meta: Vec<(Vec<u8>, u16, u16)> = {
-        let cols = rows[0].columns_ref();
-        cols.iter()
-            .map(|c| {
-                (
-                    c.name_str().as_bytes().to_vec(),
-                    c.column_type() as u16,
-                    c.character_set(),
-                )
-            })
-            .collect()
-    };
+/// Serializes one page of a paged query.
+///
+/// Same layout as [`serialize_result`], with the total row count inserted into
+/// the header ahead of the result set block as a presence byte and a u64.
+pub fn serialize_page(
+    rows: Vec<Row>,
+    affected_rows: u64,
+    last_insert_id: u64,
+    total: Option<u64>,
+) -> Vec<u8> {
+    let mut buf = new_frame(STATUS_OK, 26);
+    buf.write_u64(affected_rows);
+    buf.write_u64(last_insert_id);
+    buf.write_u8(total.is_some() as u8);
+    buf.write_u64(total.unwrap_or(0));
+    write_result_set(&mut buf, rows);
+    buf
+}
 
-    let cols_len = cols_meta.len();
-    buf.write_u32(cols_len as u32);
+/// Serializes a result keeping only the columns in `range`.
+///
+/// Same layout as [`serialize_result`], with the column metadata and every row
+/// restricted to the selected columns.
+pub fn serialize_column_range(
+    columns: &[Column],
+    rows: Vec<Row>,
+    range: std::ops::Range<usize>,
+    affected_rows: u64,
+    last_insert_id: u64,
+) -> Vec<u8> {
+    let mut buf = new_frame(STATUS_OK, 17 + estimate_result_set_size(&rows));
+    buf.write_u64(affected_rows);
+    buf.write_u64(last_insert_id);
+    let kinds = write_columns(&mut buf, &columns[range.clone()], &[], false, &[]);
+    buf.write_u32(rows.len() as u32);
+    for row in &rows {
+        for (i, kind) in range.clone().zip(&kinds) {
+            write_cell(&mut buf, row.as_ref(i).unwrap_or(&MySqlValue::NULL), *kind);
+        }
+    }
+    buf
+}
 
-    for (name, col_type, charset) in &cols_meta {
-        buf.write_blob(name);
-        buf.write_u16(*col_type);
-        buf.write_u16(*charset);
+/// Serializes rows grouped by the value of the column at `key_index`.
+///
+/// Layout: status, column metadata block, group count, then per group its key
+/// value, row count and rows. Groups appear in the order their key is first
+/// seen; rows keep their query order within a group.
+pub fn serialize_grouped_result(columns: &[Column], rows: Vec<Row>, key_index: usize) -> Vec<u8> {
+    let mut buf = new_frame(STATUS_OK, 9 + estimate_result_set_size(&rows));
+    let kinds = write_columns(&mut buf, columns, &[], false, &[]);
+    let mut groups: Vec<(Vec<u8>, u32, Vec<u8>)> = Vec::new();
+    let mut index_by_key = std::collections::HashMap::new();
+    for row in &rows {
+        let mut key = Vec::new();
+        let key_value = row.as_ref(key_index).unwrap_or(&MySqlValue::NULL);
+        write_cell(&mut key, key_value, kinds[key_index]);
+        let group = *index_by_key.entry(key.clone()).or_insert_with(|| {
+            groups.push((key, 0, Vec::new()));
+            groups.len() - 1
+        });
+        let (_, count, encoded) = &mut groups[group];
+        write_row(encoded, row, &kinds);
+        *count += 1;
+    }
+    buf.write_u32(groups.len() as u32);
+    for (key, count, encoded) in groups {
+        buf.extend_from_slice(&key);
+        buf.write_u32(count);
+        buf.extend_from_slice(&encoded);
     }
+    buf
+}
 
-    buf.write_u32(rows.len() as u32);
+/// Serializes the outcome of each statement of a multi-statement execution.
+///
+/// Layout: status, statement count, then per statement its affected rows,
+/// last insert id and result set block.
+pub fn serialize_multi_result(results: Vec<(Vec<Row>, u64, u64)>) -> Vec<u8> {
+    let mut buf = new_frame(STATUS_OK, 5 + results.len() * 16);
+    buf.write_u32(results.len() as u32);
+    for (rows, affected_rows, last_insert_id) in results {
+        buf.write_u64(affected_rows);
+        buf.write_u64(last_insert_id);
+        write_result_set(&mut buf, rows);
+    }
+    buf
+}
+
+/// Rows, affected rows and last insert id produced by one statement.
+pub type StatementOutput = (Vec<Row>, u64, u64);
+
+/// Serializes the outcome of each statement of a statement batch.
+///
+/// Layout: status, statement count, then per statement a success byte followed
+/// either by its affected rows, last insert id and result set block, or by the
+/// error message.
+pub fn serialize_statement_results(results: Vec<Result<StatementOutput, String>>) -> Vec<u8> {
+    let mut buf = new_frame(STATUS_OK, 5 + results.len() * 17);
+    buf.write_u32(results.len() as u32);
+    for result in results {
+        match result {
+            Ok((rows, affected_rows, last_insert_id)) => {
+                buf.write_u8(1);
+                buf.write_u64(affected_rows);
+                buf.write_u64(last_insert_id);
+                write_result_set(&mut buf, rows);
+            }
+            Err(message) => {
+                buf.write_u8(0);
+                buf.write_blob(message.as_bytes());
+            }
+        }
+    }
+    buf
+}
+
+/// Number of leading rows measured to estimate the average encoded row size.
+const SIZE_SAMPLE_ROWS: usize = 32;
+
+/// Estimates the encoded size of a result set block written by [`write_result_set`],
+/// which reserves it up front.
+///
+/// Column metadata is measured exactly; row data is extrapolated from the
+/// average encoded size of the first rows, so wide and narrow results both get
+/// a close pre-allocation instead of a fixed per-row guess.
+pub fn estimate_result_set_size(rows: &[Row]) -> usize {
+    let Some(first) = rows.first() else {
+        return 8;
+    };
+    let columns = first.columns_ref();
+    let meta: usize = columns.iter().map(|c| 8 + c.name_ref().len()).sum();
+    let sample = &rows[..rows.len().min(SIZE_SAMPLE_ROWS)];
+    let sampled: usize = sample
+        .iter()
+        .map(|row| {
+            (0..columns.len())
+                .map(|i| row.as_ref(i).map_or(1, encoded_value_size))
+                .sum::<usize>()
+        })
+        .sum();
+    let per_row = sampled.div_ceil(sample.len());
+    8 + meta + per_row * rows.len()
+}
 
+/// Returns the number of bytes [`write_value`] produces for `val`.
+fn encoded_value_size(val: &MySqlValue) -> usize {
+    match val {
+        MySqlValue::NULL => 1,
+        MySqlValue::Bytes(b) => 5 + b.len(),
+        MySqlValue::Int(_) | MySqlValue::UInt(_) | MySqlValue::Float(_) | MySqlValue::Double(_) => {
+            13
+        }
+        MySqlValue::Date(..) => 31,
+        MySqlValue::Time(..) => 12,
+    }
+}
+
+/// Writes the column metadata and row values of a single result set.
+pub fn write_result_set(buf: &mut Vec<u8>, rows: Vec<Row>) {
+    buf.reserve(estimate_result_set_size(&rows));
+    let mut encoder = ResultSetEncoder::new(buf);
     for row in rows {
-        for i in 0..cols_len {
-            let val = if i < row.len() { &row[i] } else { &MySqlValue::NULL };
-            match val {
-                MySqlValue::NULL => buf.write_u8(0),
-                MySqlValue::Int(v) => {
-                    buf.write_u8(1);
-                    buf.write_blob(&v.to_le_bytes());
+        encoder.push(row);
+    }
+    encoder.finish();
+}
+
+/// Incrementally encodes a result set block, one row at a time.
+///
+/// Column metadata is taken from the first row and the row count is patched in
+/// by [`ResultSetEncoder::finish`], so rows can be encoded and dropped as they
+/// are read instead of being buffered first.
+pub struct ResultSetEncoder<'a> {
+    buf: &'a mut Vec<u8>,
+    /// Writes column names, types and charsets; otherwise only the column count.
+    with_metadata: bool,
+    kinds: Vec<CellKind>,
+    enum_labels: EnumLabels,
+    type_names: bool,
+    aliases: Vec<String>,
+    count_pos: Option<usize>,
+    count: u32,
+}
+
+impl<'a> ResultSetEncoder<'a> {
+    pub fn new(buf: &'a mut Vec<u8>) -> Self {
+        Self {
+            buf,
+            with_metadata: true,
+            kinds: Vec::new(),
+            enum_labels: Vec::new(),
+            type_names: false,
+            aliases: Vec::new(),
+            count_pos: None,
+            count: 0,
+        }
+    }
+
+    /// Creates an encoder that replaces the column metadata with the bare
+    /// column count, for callers that already know the schema.
+    pub fn values_only(buf: &'a mut Vec<u8>) -> Self {
+        Self {
+            buf,
+            with_metadata: false,
+            kinds: Vec::new(),
+            enum_labels: Vec::new(),
+            type_names: false,
+            aliases: Vec::new(),
+            count_pos: None,
+            count: 0,
+        }
+    }
+
+    /// Reports the values of the columns that have labels as their 1-based
+    /// ordinal among them, typed as `SMALLINT`. Values matching no label,
+    /// like the empty string stored for invalid input, become 0.
+    pub fn with_enum_ordinals(mut self, labels: EnumLabels) -> Self {
+        self.enum_labels = labels;
+        self
+    }
+
+    /// Adds the SQL type name of every column to the metadata, see [`type_name`].
+    pub fn with_type_names(mut self, enabled: bool) -> Self {
+        self.type_names = enabled;
+        self
+    }
+
+    /// Reports the column at each position of `aliases` under that name
+    /// instead of its own, unless the alias is empty. Values are unaffected.
+    pub fn with_aliases(mut self, aliases: Vec<String>) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    /// Appends one row, writing the column metadata first if it is the first row.
+    pub fn push(&mut self, row: Row) {
+        let row = self.begin_row(row);
+        write_row(self.buf, &row, &self.kinds);
+        self.count += 1;
+    }
+
+    /// Appends one row like [`ResultSetEncoder::push`] and returns the
+    /// [`stable_hash`] of its encoded values.
+    pub fn push_hashed(&mut self, row: Row) -> u64 {
+        let row = self.begin_row(row);
+        let start = self.buf.len();
+        write_row(self.buf, &row, &self.kinds);
+        self.count += 1;
+        stable_hash(&self.buf[start..])
+    }
+
+    /// Applies the value substitutions to `row` and writes the column metadata
+    /// if it is the first row.
+    fn begin_row(&mut self, mut row: Row) -> Row {
+        for (i, labels) in self.enum_labels.iter().enumerate() {
+            if let Some(labels) = labels
+                && let Some(MySqlValue::Bytes(label)) = row.as_ref(i)
+            {
+                let ordinal = labels.iter().position(|l| l == label).map_or(0, |p| p + 1);
+                row.place(i, MySqlValue::Int(ordinal as i64));
+            }
+        }
+        if self.count_pos.is_none() {
+            self.kinds =
+                if self.with_metadata && self.enum_labels.is_empty() && self.aliases.is_empty() {
+                    write_columns_cached(self.buf, row.columns_ref(), self.type_names)
+                } else if self.with_metadata {
+                    write_columns(
+                        self.buf,
+                        row.columns_ref(),
+                        &self.enum_labels,
+                        self.type_names,
+                        &self.aliases,
+                    )
+                } else {
+                    let cols = row.columns_ref();
+                    self.buf.write_u32(cols.len() as u32);
+                    cols.iter().map(CellKind::of).collect()
+                };
+            self.count_pos = Some(self.buf.len());
+            self.buf.write_u32(0);
+        }
+        row
+    }
+
+    /// Returns the capacity of the buffer being written.
+    pub fn capacity(&self) -> usize {
+        self.buf.capacity()
+    }
+
+    /// Completes the block by writing the final row count.
+    pub fn finish(self) {
+        match self.count_pos {
+            Some(pos) => self.buf[pos..pos + 4].copy_from_slice(&self.count.to_le_bytes()),
+            None => {
+                self.buf.write_u32(0);
+                self.buf.write_u32(0);
+            }
+        }
+    }
+}
+
+/// Reuses encoded metadata blocks across result sets with the same columns,
+/// when enabled with `mysql_set_metadata_cache`.
+pub static METADATA_CACHE_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Upper bound on cached metadata blocks; the cache is emptied when reached.
+const METADATA_CACHE_CAPACITY: usize = 1024;
+
+/// A cached metadata block and the column signature it was derived from.
+struct CachedColumns {
+    signature: Vec<u8>,
+    block: Vec<u8>,
+    kinds: Vec<CellKind>,
+}
+
+static METADATA_CACHE: LazyLock<RwLock<HashMap<u64, Arc<CachedColumns>>>> =
+    LazyLock::new(RwLock::default);
+
+/// Metadata blocks served from the cache and derived from scratch.
+pub static METADATA_CACHE_HITS: AtomicU64 = AtomicU64::new(0);
+pub static METADATA_DERIVATIONS: AtomicU64 = AtomicU64::new(0);
+
+/// Empties the metadata cache.
+pub fn clear_metadata_cache() {
+    METADATA_CACHE
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clear();
+}
+
+/// Like [`write_columns`] without ENUM ordinals, copying the block from the
+/// metadata cache when it is enabled and holds one for the same columns.
+///
+/// Entries are keyed by a hash of every column property the block and the
+/// cell kinds depend on, and the full signature is compared on lookup, so a
+/// changed schema replaces the entry instead of reusing it.
+fn write_columns_cached(buf: &mut Vec<u8>, cols: &[Column], type_names: bool) -> Vec<CellKind> {
+    if !METADATA_CACHE_ENABLED.load(Ordering::Relaxed) {
+        METADATA_DERIVATIONS.fetch_add(1, Ordering::Relaxed);
+        return write_columns(buf, cols, &[], type_names, &[]);
+    }
+    let mut signature = Vec::with_capacity(cols.len() * 32 + 1);
+    signature.write_u8(type_names as u8);
+    signature.write_u8(TINYINT_AS_BOOL.load(Ordering::Relaxed) as u8);
+    for c in cols {
+        signature.write_blob(c.name_ref());
+        signature.write_u16(c.column_type() as u16);
+        signature.write_u16(c.character_set());
+        signature.write_u16(c.flags().bits());
+        signature.write_u32(c.column_length());
+    }
+    let key = stable_hash(&signature);
+    let cached = METADATA_CACHE
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(&key)
+        .filter(|entry| entry.signature == signature)
+        .cloned();
+    if let Some(entry) = cached {
+        METADATA_CACHE_HITS.fetch_add(1, Ordering::Relaxed);
+        buf.extend_from_slice(&entry.block);
+        return entry.kinds.clone();
+    }
+    METADATA_DERIVATIONS.fetch_add(1, Ordering::Relaxed);
+    let start = buf.len();
+    let kinds = write_columns(buf, cols, &[], type_names, &[]);
+    let entry = Arc::new(CachedColumns {
+        signature,
+        block: buf[start..].to_vec(),
+        kinds: kinds.clone(),
+    });
+    let mut cache = METADATA_CACHE
+        .write()
+        .unwrap_or_else(PoisonError::into_inner);
+    if cache.len() >= METADATA_CACHE_CAPACITY {
+        cache.clear();
+    }
+    cache.insert(key, entry);
+    kinds
+}
+
+/// Writes a column metadata block and returns how each column's values are encoded.
+///
+/// Columns with an entry in `enum_labels` are reported as binary `SMALLINT`
+/// columns holding ordinals. With `type_names` set, the column count carries
+/// `COLUMNS_WITH_TYPE_NAMES` and every column is followed by its SQL type
+/// name as a blob.
+fn write_columns(
+    buf: &mut Vec<u8>,
+    cols: &[Column],
+    enum_labels: &[Option<Vec<Vec<u8>>>],
+    type_names: bool,
+    aliases: &[String],
+) -> Vec<CellKind> {
+    let flag = if type_names {
+        COLUMNS_WITH_TYPE_NAMES
+    } else {
+        0
+    };
+    buf.write_u32(cols.len() as u32 | flag);
+    for (i, c) in cols.iter().enumerate() {
+        match aliases.get(i).filter(|alias| !alias.is_empty()) {
+            Some(alias) => buf.write_blob(alias.as_bytes()),
+            None => buf.write_blob(c.name_str().as_bytes()),
+        }
+        let ordinal = enum_labels.get(i).is_some_and(Option::is_some);
+        if ordinal {
+            buf.write_u16(ColumnType::MYSQL_TYPE_SHORT as u16);
+            buf.write_u16(CHARSET_BINARY);
+        } else {
+            buf.write_u16(c.column_type() as u16);
+            buf.write_u16(c.character_set());
+        }
+        if type_names {
+            let name = if ordinal {
+                "SMALLINT".into()
+            } else {
+                type_name(c)
+            };
+            buf.write_blob(name.as_bytes());
+        }
+    }
+    cols.iter().map(CellKind::of).collect()
+}
+
+/// Derives the SQL type name of a result column, e.g. `INT UNSIGNED` or
+/// `VARBINARY`, from its type code, flags and character set.
+///
+/// Result metadata reports every TEXT and BLOB size as the same type code, so
+/// those columns are named `TEXT` or `BLOB` without a size prefix.
+pub fn type_name(column: &Column) -> Cow<'static, str> {
+    use ColumnType::*;
+    let flags = column.flags();
+    let binary = column.character_set() == CHARSET_BINARY;
+    let numeric = |name: &'static str| -> Cow<'static, str> {
+        if flags.contains(ColumnFlags::UNSIGNED_FLAG) {
+            format!("{} UNSIGNED", name).into()
+        } else {
+            name.into()
+        }
+    };
+    let (text, bytes) = match column.column_type() {
+        MYSQL_TYPE_TINY => return numeric("TINYINT"),
+        MYSQL_TYPE_SHORT => return numeric("SMALLINT"),
+        MYSQL_TYPE_INT24 => return numeric("MEDIUMINT"),
+        MYSQL_TYPE_LONG => return numeric("INT"),
+        MYSQL_TYPE_LONGLONG => return numeric("BIGINT"),
+        MYSQL_TYPE_DECIMAL | MYSQL_TYPE_NEWDECIMAL => return numeric("DECIMAL"),
+        MYSQL_TYPE_FLOAT => return numeric("FLOAT"),
+        MYSQL_TYPE_DOUBLE => return numeric("DOUBLE"),
+        MYSQL_TYPE_NULL => ("NULL", "NULL"),
+        MYSQL_TYPE_TIMESTAMP | MYSQL_TYPE_TIMESTAMP2 => ("TIMESTAMP", "TIMESTAMP"),
+        MYSQL_TYPE_DATE | MYSQL_TYPE_NEWDATE => ("DATE", "DATE"),
+        MYSQL_TYPE_TIME | MYSQL_TYPE_TIME2 => ("TIME", "TIME"),
+        MYSQL_TYPE_DATETIME | MYSQL_TYPE_DATETIME2 => ("DATETIME", "DATETIME"),
+        MYSQL_TYPE_YEAR => ("YEAR", "YEAR"),
+        MYSQL_TYPE_BIT => ("BIT", "BIT"),
+        MYSQL_TYPE_JSON => ("JSON", "JSON"),
+        MYSQL_TYPE_GEOMETRY => ("GEOMETRY", "GEOMETRY"),
+        MYSQL_TYPE_VECTOR => ("VECTOR", "VECTOR"),
+        MYSQL_TYPE_ENUM => ("ENUM", "ENUM"),
+        MYSQL_TYPE_SET => ("SET", "SET"),
+        MYSQL_TYPE_STRING if flags.contains(ColumnFlags::ENUM_FLAG) => ("ENUM", "ENUM"),
+        MYSQL_TYPE_STRING if flags.contains(ColumnFlags::SET_FLAG) => ("SET", "SET"),
+        MYSQL_TYPE_STRING => ("CHAR", "BINARY"),
+        MYSQL_TYPE_VARCHAR | MYSQL_TYPE_VAR_STRING => ("VARCHAR", "VARBINARY"),
+        MYSQL_TYPE_TINY_BLOB => ("TINYTEXT", "TINYBLOB"),
+        MYSQL_TYPE_MEDIUM_BLOB => ("MEDIUMTEXT", "MEDIUMBLOB"),
+        MYSQL_TYPE_LONG_BLOB => ("LONGTEXT", "LONGBLOB"),
+        MYSQL_TYPE_BLOB => ("TEXT", "BLOB"),
+        _ => ("UNKNOWN", "UNKNOWN"),
+    };
+    if binary { bytes } else { text }.into()
+}
+
+/// Member labels of the ENUM columns of a result set, indexed by column; `None`
+/// for other columns.
+pub type EnumLabels = Vec<Option<Vec<Vec<u8>>>>;
+
+/// Parses the member labels of an `enum('a','b')` column type as reported by
+/// `information_schema.COLUMNS.COLUMN_TYPE`, where quotes are doubled.
+pub fn parse_enum_labels(column_type: &str) -> Option<Vec<Vec<u8>>> {
+    let prefix = column_type.get(..5)?;
+    if !prefix.eq_ignore_ascii_case("enum(") {
+        return None;
+    }
+    let body = column_type[5..].strip_suffix(')')?.as_bytes();
+    let mut labels = Vec::new();
+    let mut i = 0;
+    while i < body.len() {
+        if body[i] != b'\'' {
+            return None;
+        }
+        i += 1;
+        let mut label = Vec::new();
+        loop {
+            match body.get(i)? {
+                b'\'' if body.get(i + 1) == Some(&b'\'') => {
+                    label.push(b'\'');
+                    i += 2;
+                }
+                b'\'' => {
+                    i += 1;
+                    break;
                 }
-                MySqlValue::UInt(v) => {
-                    buf.write_u8(1);
-                    buf.write_blob(&v.to_le_bytes());
+                b => {
+                    label.push(*b);
+                    i += 1;
+                }
+            }
+        }
+        labels.push(label);
+        match body.get(i) {
+            Some(b',') => i += 1,
+            None => break,
+            Some(_) => return None,
+        }
+    }
+    Some(labels)
+}
+
+/// Writes one row's values, padding missing trailing values with NULL.
+fn write_row(buf: &mut Vec<u8>, row: &Row, kinds: &[CellKind]) {
+    for (i, kind) in kinds.iter().enumerate() {
+        let val = if i < row.len() { &row[i] } else { &MySqlValue::NULL };
+        write_cell(buf, val, *kind);
+    }
+}
+
+/// Writes one value in the encoding of its column kind.
+fn write_cell(buf: &mut Vec<u8>, val: &MySqlValue, kind: CellKind) {
+    match (kind, val) {
+        (CellKind::Bool, _) if let Some(flag) = bool_value(val) => {
+            buf.write_u8(VALUE_BOOL);
+            buf.write_u8(flag as u8);
+        }
+        // MySQL stores geometries as a little-endian SRID followed by the WKB.
+        (CellKind::Geometry, MySqlValue::Bytes(bytes)) if bytes.len() >= 4 => {
+            let (srid, wkb) = bytes.split_at(4);
+            buf.write_u8(VALUE_GEOMETRY);
+            buf.write_u32(u32::from_le_bytes([srid[0], srid[1], srid[2], srid[3]]));
+            buf.write_blob(wkb);
+        }
+        // BIT values arrive as big-endian bytes, at most 8 of them.
+        (CellKind::Bit(width), MySqlValue::Bytes(bytes)) if bytes.len() <= 8 => {
+            buf.write_u8(VALUE_BIT);
+            buf.write_u8(width);
+            buf.write_u64(bytes.iter().fold(0, |acc, b| (acc << 8) | *b as u64));
+        }
+        _ => write_value(buf, val),
+    }
+}
+
+/// Writes the column names of a result set as a CSV header record.
+pub fn write_csv_header(buf: &mut Vec<u8>, cols: &[Column]) {
+    write_csv_record(buf, cols.iter().map(|c| Cow::Borrowed(c.name_ref())));
+}
+
+/// Writes one row as a CSV record, formatting every value as text.
+///
+/// NULL becomes an empty field, dates use `YYYY-MM-DD[ HH:MM:SS[.ffffff]]` and
+/// times `[-]HH:MM:SS[.ffffff]`; strings, decimals and BLOBs are written as
+/// their bytes.
+pub fn write_csv_row(buf: &mut Vec<u8>, row: &Row) {
+    let cols = row.columns_ref();
+    write_csv_record(
+        buf,
+        (0..cols.len()).map(|i| csv_text(row.as_ref(i).unwrap_or(&MySqlValue::NULL), &cols[i])),
+    );
+}
+
+/// Writes one row as text cells: per value a presence byte, 0 for NULL and 1
+/// otherwise, followed by its text as a blob.
+///
+/// Values are formatted as for [`write_csv_row`], except that binary strings,
+/// BLOBs, BIT and spatial values are written as uppercase hex, like `HEX()`.
+pub fn write_text_row(buf: &mut Vec<u8>, row: &Row) {
+    let cols = row.columns_ref();
+    for (i, col) in cols.iter().enumerate() {
+        match row.as_ref(i).unwrap_or(&MySqlValue::NULL) {
+            MySqlValue::NULL => buf.write_u8(0),
+            MySqlValue::Bytes(bytes) if is_binary_string(col) => {
+                const DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+                let hex: Vec<u8> = bytes
+                    .iter()
+                    .flat_map(|b| [DIGITS[(b >> 4) as usize], DIGITS[(b & 0xF) as usize]])
+                    .collect();
+                buf.write_u8(1);
+                buf.write_blob(&hex);
+            }
+            val => {
+                buf.write_u8(1);
+                buf.write_blob(&csv_text(val, col));
+            }
+        }
+    }
+}
+
+/// Returns the first column of `row` flagged `NOT NULL` that nevertheless
+/// holds NULL, as an outer join can produce.
+pub fn null_in_not_null_column(row: &Row) -> Option<&Column> {
+    row.columns_ref().iter().enumerate().find_map(|(i, col)| {
+        (col.flags().contains(ColumnFlags::NOT_NULL_FLAG)
+            && matches!(row.as_ref(i), Some(MySqlValue::NULL)))
+        .then_some(col)
+    })
+}
+
+/// Returns `true` for columns holding raw bytes rather than text or numbers.
+fn is_binary_string(column: &Column) -> bool {
+    use ColumnType::*;
+    column.character_set() == CHARSET_BINARY
+        && matches!(
+            column.column_type(),
+            MYSQL_TYPE_TINY_BLOB
+                | MYSQL_TYPE_MEDIUM_BLOB
+                | MYSQL_TYPE_LONG_BLOB
+                | MYSQL_TYPE_BLOB
+                | MYSQL_TYPE_VARCHAR
+                | MYSQL_TYPE_VAR_STRING
+                | MYSQL_TYPE_STRING
+                | MYSQL_TYPE_BIT
+                | MYSQL_TYPE_GEOMETRY
+        )
+}
+
+/// Writes an RFC 4180 record: fields separated by commas, quoted when they
+/// contain a comma, quote or line break, and terminated by CRLF.
+fn write_csv_record<'a>(buf: &mut Vec<u8>, fields: impl Iterator<Item = Cow<'a, [u8]>>) {
+    for (i, field) in fields.enumerate() {
+        if i > 0 {
+            buf.push(b',');
+        }
+        if field
+            .iter()
+            .any(|b| matches!(b, b',' | b'"' | b'\r' | b'\n'))
+        {
+            buf.push(b'"');
+            for &b in field.iter() {
+                if b == b'"' {
+                    buf.push(b'"');
                 }
-                MySqlValue::Float(v) => {
-                    buf.write_u8(1);
-                    buf.write_blob(&(*v as f64).to_le_bytes());
+                buf.push(b);
+            }
+            buf.push(b'"');
+        } else {
+            buf.extend_from_slice(&field);
+        }
+    }
+    buf.extend_from_slice(b"\r\n");
+}
+
+/// Textual form of a value, as written to CSV and protobuf string fields.
+pub(crate) fn csv_text<'a>(val: &'a MySqlValue, column: &Column) -> Cow<'a, [u8]> {
+    let text = match val {
+        MySqlValue::NULL => return Cow::Borrowed(&[]),
+        MySqlValue::Bytes(b) => return Cow::Borrowed(b),
+        MySqlValue::Int(v) => v.to_string(),
+        MySqlValue::UInt(v) => v.to_string(),
+        MySqlValue::Float(v) => v.to_string(),
+        MySqlValue::Double(v) => v.to_string(),
+        MySqlValue::Date(y, mo, d, h, min, s, mic) => {
+            let date = format!("{:04}-{:02}-{:02}", y, mo, d);
+            if column.column_type() == ColumnType::MYSQL_TYPE_DATE {
+                date
+            } else if *mic > 0 {
+                format!("{} {:02}:{:02}:{:02}.{:06}", date, h, min, s, mic)
+            } else {
+                format!("{} {:02}:{:02}:{:02}", date, h, min, s)
+            }
+        }
+        MySqlValue::Time(neg, d, h, min, s, mic) => {
+            let sign = if *neg { "-" } else { "" };
+            let hours = *d as u64 * 24 + *h as u64;
+            if *mic > 0 {
+                format!("{}{:02}:{:02}:{:02}.{:06}", sign, hours, min, s, mic)
+            } else {
+                format!("{}{:02}:{:02}:{:02}", sign, hours, min, s)
+            }
+        }
+    };
+    Cow::Owned(text.into_bytes())
+}
+
+/// Returns `true` if two column lists have the same names, types and charsets.
+fn same_columns(a: &[Column], b: &[Column]) -> bool {
+    a.len() == b.len()
+        && a.iter().zip(b).all(|(x, y)| {
+            x.name_ref() == y.name_ref()
+                && x.column_type() == y.column_type()
+                && x.character_set() == y.character_set()
+        })
+}
+
+/// Encodes the rows of a streamed query as `STATUS_CHUNK` frames.
+///
+/// Frame layout: status, schema change marker, a column metadata block when the
+/// marker is 1, the row count, then the rows. The first frame always carries
+/// metadata; later result sets carry a new block only when their columns
+/// differ from the previous ones, so consumers switch decoders on the marker.
+#[derive(Default)]
+pub struct RowChunkEncoder {
+    columns: Option<Arc<[Column]>>,
+    kinds: Vec<CellKind>,
+    schema_changed: bool,
+    rows: Vec<u8>,
+    count: u32,
+}
+
+impl RowChunkEncoder {
+    /// Switches to the columns of the next result set.
+    pub fn set_columns(&mut self, columns: Arc<[Column]>) {
+        if self
+            .columns
+            .as_deref()
+            .is_some_and(|current| same_columns(current, &columns))
+        {
+            return;
+        }
+        self.kinds = columns.iter().map(CellKind::of).collect();
+        self.columns = Some(columns);
+        self.schema_changed = true;
+    }
+
+    pub fn push(&mut self, row: &Row) {
+        write_row(&mut self.rows, row, &self.kinds);
+        self.count += 1;
+    }
+
+    /// Number of rows waiting for the next frame.
+    pub fn pending(&self) -> u32 {
+        self.count
+    }
+
+    /// Returns the next frame, or `None` if there are no rows and no schema change to report.
+    pub fn take_frame(&mut self) -> Option<Vec<u8>> {
+        if self.count == 0 && !self.schema_changed {
+            return None;
+        }
+        Some(self.flush())
+    }
+
+    /// Returns the next frame even when it carries no rows.
+    pub fn flush(&mut self) -> Vec<u8> {
+        let mut buf = new_frame(STATUS_CHUNK, 10 + self.rows.len());
+        match self.columns.as_deref() {
+            Some(cols) if self.schema_changed => {
+                buf.write_u8(1);
+                write_columns(&mut buf, cols, &[], false, &[]);
+            }
+            _ => buf.write_u8(0),
+        }
+        buf.write_u32(self.count);
+        buf.append(&mut self.rows);
+        self.schema_changed = false;
+        self.count = 0;
+        buf
+    }
+}
+
+/// Encodes `TINYINT(1)` values as booleans, when enabled with
+/// `mysql_set_tinyint_as_bool`; otherwise they stay integers.
+pub static TINYINT_AS_BOOL: AtomicBool = AtomicBool::new(false);
+
+/// How the values of a column are encoded beyond their generic tag.
+#[derive(Clone, Copy)]
+enum CellKind {
+    Plain,
+    /// `TINYINT(1)`, the conventional boolean column type.
+    Bool,
+    /// Spatial columns, reported with their SRID.
+    Geometry,
+    /// `BIT(n)` columns, reported as integers with their width `n`.
+    Bit(u8),
+}
+
+impl CellKind {
+    fn of(column: &Column) -> Self {
+        match column.column_type() {
+            ColumnType::MYSQL_TYPE_TINY
+                if column.column_length() == 1 && TINYINT_AS_BOOL.load(Ordering::Relaxed) =>
+            {
+                CellKind::Bool
+            }
+            ColumnType::MYSQL_TYPE_GEOMETRY => CellKind::Geometry,
+            ColumnType::MYSQL_TYPE_BIT => CellKind::Bit(column.column_length().min(64) as u8),
+            _ => CellKind::Plain,
+        }
+    }
+}
+
+/// Interprets a `TINYINT(1)` value from either protocol as a boolean.
+fn bool_value(val: &MySqlValue) -> Option<bool> {
+    match val {
+        MySqlValue::Int(v) => Some(*v != 0),
+        MySqlValue::UInt(v) => Some(*v != 0),
+        MySqlValue::Bytes(b) => std::str::from_utf8(b)
+            .ok()?
+            .parse::<i64>()
+            .ok()
+            .map(|v| v != 0),
+        _ => None,
+    }
+}
+
+/// Starts a result payload whose affected rows and last insert id are filled
+/// in later by [`finish_result`].
+pub fn begin_result() -> Vec<u8> {
+    let mut buf = new_frame(STATUS_OK, 0);
+    buf.write_u64(0);
+    buf.write_u64(0);
+    buf
+}
+
+/// Fills in the header of a payload started with [`begin_result`].
+pub fn finish_result(buf: &mut [u8], affected_rows: u64, last_insert_id: u64) {
+    buf[2..10].copy_from_slice(&affected_rows.to_le_bytes());
+    buf[10..18].copy_from_slice(&last_insert_id.to_le_bytes());
+}
+
+/// Writes a single tagged column value.
+pub fn write_value(buf: &mut Vec<u8>, val: &MySqlValue) {
+    match val {
+        MySqlValue::NULL => buf.write_u8(VALUE_NULL),
+        MySqlValue::Int(v) => {
+            buf.write_u8(VALUE_BYTES);
+            buf.write_blob(&v.to_le_bytes());
+        }
+        MySqlValue::UInt(v) => {
+            buf.write_u8(VALUE_BYTES);
+            buf.write_blob(&v.to_le_bytes());
+        }
+        MySqlValue::Float(v) => {
+            buf.write_u8(VALUE_BYTES);
+            buf.write_blob(&(*v as f64).to_le_bytes());
+        }
+        MySqlValue::Double(v) => {
+            buf.write_u8(VALUE_BYTES);
+            buf.write_blob(&v.to_le_bytes());
+        }
+        MySqlValue::Bytes(b) => {
+            buf.write_u8(VALUE_BYTES);
+            buf.write_blob(b);
+        }
+        MySqlValue::Date(y, mo, d, h, min, s, mic) => {
+            let ds = format!(
+                "{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:06}",
+                y, mo, d, h, min, s, mic
+            );
+            buf.write_u8(VALUE_BYTES);
+            buf.write_blob(ds.as_bytes());
+        }
+        MySqlValue::Time(neg, d, h, m, s, mic) => {
+            write_time(buf, *neg, *d, *h, *m, *s, *mic);
+        }
+    }
+}
+
+/// Writes a `TIME` value as its structured components: sign, total hours,
+/// minutes, seconds and microseconds.
+///
+/// Values outside the documented `-838:59:59` to `838:59:59` range are clamped
+/// instead of overflowing the hour count.
+fn write_time(
+    buf: &mut Vec<u8>,
+    neg: bool,
+    days: u32,
+    hours: u8,
+    minutes: u8,
+    seconds: u8,
+    micros: u32,
+) {
+    let total_hours = days
+        .checked_mul(24)
+        .and_then(|h| h.checked_add(hours as u32))
+        .unwrap_or(u32::MAX);
+    let (hours, minutes, seconds, micros) = if total_hours > TIME_MAX_HOURS {
+        (TIME_MAX_HOURS, 59, 59, 0)
+    } else {
+        (
+            total_hours,
+            minutes.min(59),
+            seconds.min(59),
+            micros.min(999_999),
+        )
+    };
+    buf.write_u8(VALUE_TIME);
+    buf.write_u8(neg as u8);
+    buf.write_u32(hours);
+    buf.write_u8(minutes);
+    buf.write_u8(seconds);
+    buf.write_u32(micros);
+}
+
+/// Splits a SQL statement into its bare words, skipping quoted literals,
+/// quoted identifiers and comments so keywords inside them are never matched.
+/// Statement separators are kept as `;` entries.
+pub fn sql_words(sql: &str) -> Vec<&str> {
+    let bytes = sql.as_bytes();
+    let mut words = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        match b {
+            b'\'' | b'"' | b'`' => {
+                i += 1;
+                while i < bytes.len() {
+                    if bytes[i] == b'\\' && b != b'`' {
+                        i += 2;
+                        continue;
+                    }
+                    if bytes[i] == b {
+                        if i + 1 < bytes.len() && bytes[i + 1] == b {
+                            i += 2;
+                            continue;
+                        }
+                        break;
+                    }
+                    i += 1;
                 }
-                MySqlValue::Double(v) => {
-                    buf.write_u8(1);
-                    buf.write_blob(&v.to_le_bytes());
+                i += 1;
+            }
+            b'#' => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
                 }
-                MySqlValue::Bytes(b) => {
-                    buf.write_u8(1);
-                    buf.write_blob(b);
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
                 }
-                MySqlValue::Date(y, mo, d, h, min, s, mic) => {
-                    let ds = format!(
-                        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:06}",
-                        y, mo, d, h, min, s, mic
-                    );
-                    buf.write_u8(1);
-                    buf.write_blob(ds.as_bytes());
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i += 2;
+                while i + 1 < bytes.len() && !(bytes[i] == b'*' && bytes[i + 1] == b'/') {
+                    i += 1;
                 }
-                MySqlValue::Time(neg, d, h, m, s, mic) => {
-                    let sign = if *neg { "-" } else { "" };
-                    let ts = format!("{}{:02}:{:02}:{:02}:{:02}.{:06}", sign, d, h, m, s, mic);
-                    buf.write_u8(1);
-                    buf.write_blob(ts.as_bytes());
+                i += 2;
+            }
+            _ if b.is_ascii_alphanumeric() || b == b'_' || b == b'$' => {
+                let start = i;
+                while i < bytes.len()
+                    && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_' || bytes[i] == b'$')
+                {
+                    i += 1;
                 }
+                words.push(&sql[start..i]);
+            }
+            b';' => {
+                words.push(&sql[i..i + 1]);
+                i += 1;
             }
+            _ => i += 1,
         }
     }
+    words
+}
 
-    buf
+/// Returns `true` if `keyword` appears as a bare word anywhere in the statement.
+pub fn contains_keyword(sql: &str, keyword: &str) -> bool {
+    sql_words(sql)
+        .iter()
+        .any(|w| w.eq_ignore_ascii_case(keyword))
+}
+
+/// Returns `true` if any statement modifies data or schema (DML, DDL or privilege changes).
+pub fn is_write_statement(sql: &str) -> bool {
+    const WRITE_KEYWORDS: [&str; 13] = [
+        "INSERT", "UPDATE", "DELETE", "REPLACE", "CREATE", "ALTER", "DROP", "TRUNCATE", "RENAME",
+        "LOAD", "GRANT", "REVOKE", "IMPORT",
+    ];
+    let words = sql_words(sql);
+    words.split(|w| *w == ";").any(|statement| {
+        let Some(first) = statement.first() else {
+            return false;
+        };
+        if first.eq_ignore_ascii_case("WITH") {
+            return statement.iter().any(|w| {
+                ["INSERT", "UPDATE", "DELETE"]
+                    .iter()
+                    .any(|kw| w.eq_ignore_ascii_case(kw))
+            });
+        }
+        WRITE_KEYWORDS
+            .iter()
+            .any(|kw| first.eq_ignore_ascii_case(kw))
+    })
+}
+
+/// Returns `true` if the statement starts with the `SELECT` keyword.
+pub fn is_select_statement(sql: &str) -> bool {
+    sql_words(sql)
+        .first()
+        .is_some_and(|w| w.eq_ignore_ascii_case("SELECT"))
+}
+
+/// Inserts an optimizer hint comment directly after the leading `SELECT` keyword.
+///
+/// Returns `None` when the statement is not a SELECT.
+pub fn inject_select_hint(sql: &str, hint: &str) -> Option<String> {
+    let words = sql_words(sql);
+    let first = words.first().filter(|w| w.eq_ignore_ascii_case("SELECT"))?;
+    let end = first.as_ptr() as usize - sql.as_ptr() as usize + first.len();
+    Some(format!("{} /*+ {} */{}", &sql[..end], hint, &sql[end..]))
+}
+
+/// Makes the server abort `sql` after `timeout_ms`, in the syntax of `flavor`.
+///
+/// MariaDB takes `SET STATEMENT max_statement_time` (in seconds) for any
+/// statement; other servers take the `MAX_EXECUTION_TIME` hint, which only
+/// SELECT statements accept, so `None` is returned for anything else.
+pub fn with_server_timeout(sql: &str, flavor: u8, timeout_ms: u32) -> Option<String> {
+    if flavor == FLAVOR_MARIADB {
+        return Some(format!(
+            "SET STATEMENT max_statement_time = {}.{:03} FOR {}",
+            timeout_ms / 1000,
+            timeout_ms % 1000,
+            sql
+        ));
+    }
+    inject_select_hint(sql, &format!("MAX_EXECUTION_TIME({})", timeout_ms))
+}
+
+/// Extracts the `LATEST DETECTED DEADLOCK` section from the output of `SHOW
+/// ENGINE INNODB STATUS`, without its header, or `None` if no deadlock has been
+/// recorded since the server started.
+pub fn latest_deadlock(status: &str) -> Option<&str> {
+    let start = status.find("LATEST DETECTED DEADLOCK\n")?;
+    let body = status[start..].split_once('\n')?.1;
+    // Skip the dashes underlining the header; the section ends at the dashes
+    // above the next header.
+    let body = body.split_once('\n')?.1;
+    let end = body
+        .match_indices('\n')
+        .map(|(i, _)| i + 1)
+        .find(|&i| body[i..].starts_with("---"))
+        .unwrap_or(body.len());
+    Some(body[..end].trim_end())
 }
 
+/// Returns the statement with trailing whitespace and semicolons removed.
+pub fn trim_statement(sql: &str) -> &str {
+    sql.trim_end_matches(|c: char| c.is_whitespace() || c == ';')
+}
+
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub fn ptr_to_string(ptr: *const c_char) -> Result<String, String> {
     if ptr.is_null() {
         return Err("Null pointer".to_string());
@@ -272,10 +2232,61 @@ pub fn ptr_to_string(ptr: *const c_char) -> Result<String, String> {
         .map_err(|_| "Invalid UTF-8".to_string())
 }
 
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub fn ptr_to_vec(ptr: *const c_uchar, len: c_int) -> Vec<u8> {
     if ptr.is_null() || len <= 0 {
         Vec::new()
     } else {
         unsafe { slice::from_raw_parts(ptr, len as usize).to_vec() }
     }
-}
\ No newline at end of file
+}
+
+/// Server flavors reported by `mysql_pool_server_flavor`.
+pub const FLAVOR_UNKNOWN: u8 = 0;
+pub const FLAVOR_MYSQL: u8 = 1;
+pub const FLAVOR_MARIADB: u8 = 2;
+pub const FLAVOR_PERCONA: u8 = 3;
+
+/// Classifies a server from `VERSION()` and `@@version_comment`.
+///
+/// MariaDB marks its version string, e.g. `10.11.6-MariaDB-log`, while Percona
+/// Server only shows up in the comment; anything else mentioning MySQL in the
+/// comment is taken to be Oracle MySQL.
+pub fn server_flavor(version: &str, comment: &str) -> u8 {
+    let has = |text: &str, name: &str| text.to_ascii_lowercase().contains(name);
+    if has(version, "mariadb") || has(comment, "mariadb") {
+        FLAVOR_MARIADB
+    } else if has(comment, "percona") {
+        FLAVOR_PERCONA
+    } else if has(comment, "mysql") {
+        FLAVOR_MYSQL
+    } else {
+        FLAVOR_UNKNOWN
+    }
+}
+
+/// Parses the leading `major.minor.patch` of a server version string; missing
+/// or unparsable components are 0.
+pub fn parse_version(version: &str) -> (u16, u16, u16) {
+    let mut parts = version.split('.').map(|part| {
+        let digits = part.bytes().take_while(u8::is_ascii_digit).count();
+        part[..digits].parse().unwrap_or(0)
+    });
+    let mut next = || parts.next().unwrap_or(0);
+    (next(), next(), next())
+}
+
+/// FNV-1a with a final avalanche step. Unlike `DefaultHasher` it is stable
+/// across builds and platforms, so hashes can be persisted and compared between
+/// processes.
+pub fn stable_hash(data: &[u8]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    for b in data {
+        hash = (hash ^ *b as u64).wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    hash ^ (hash >> 33)
+}